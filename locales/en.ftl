@@ -0,0 +1,42 @@
+## Confirmation email
+
+confirmation-subject = Please confirm your subscription
+confirmation-greeting = Welcome to the newsletter, { $name }.
+confirmation-instructions = Visit { $link } to confirm your subscription.
+confirmation-action = Confirm your subscription
+
+## Welcome email, sent instead when no confirmation is needed
+
+welcome-subject = Welcome to the newsletter
+welcome-greeting = Welcome to the newsletter, { $name }, you will receive the next issue.
+welcome-instructions = Visit { $link } to manage your subscription.
+welcome-action = Manage your subscription
+
+## Email change confirmation
+
+email-change-subject = Confirm your new email address
+email-change-intro = You asked for the newsletter to be sent to this address.
+email-change-instructions = Visit { $link } to confirm the change.
+email-change-action = Confirm the change
+
+## Subscription confirmation page
+
+confirmed-title = Subscription confirmed
+confirmed = Thank you, your subscription is confirmed.
+invalid-link-title = Invalid link
+invalid-link = This link is not valid.
+
+## Email change confirmation pages
+
+email-changed-title = Email address changed
+email-changed = The newsletter will now be sent to { $email }.
+email-change-failed-title = Email address not changed
+email-change-taken = { $email } is already subscribed.
+
+## Unsubscribe pages
+
+unsubscribe-title = Unsubscribe
+unsubscribe-question = Stop sending the newsletter to { $email }?
+unsubscribe-button = Unsubscribe
+unsubscribed-title = Unsubscribed
+unsubscribed = You have been unsubscribed and will not receive any more issues.
@@ -0,0 +1,42 @@
+## Confirmation email
+
+confirmation-subject = Veuillez confirmer votre abonnement
+confirmation-greeting = Bienvenue dans la newsletter, { $name }.
+confirmation-instructions = Rendez-vous sur { $link } pour confirmer votre abonnement.
+confirmation-action = Confirmer votre abonnement
+
+## Welcome email, sent instead when no confirmation is needed
+
+welcome-subject = Bienvenue dans la newsletter
+welcome-greeting = Bienvenue dans la newsletter, { $name }, vous recevrez le prochain numéro.
+welcome-instructions = Rendez-vous sur { $link } pour gérer votre abonnement.
+welcome-action = Gérer votre abonnement
+
+## Email change confirmation
+
+email-change-subject = Confirmez votre nouvelle adresse e-mail
+email-change-intro = Vous avez demandé à recevoir la newsletter à cette adresse.
+email-change-instructions = Rendez-vous sur { $link } pour confirmer le changement.
+email-change-action = Confirmer le changement
+
+## Subscription confirmation page
+
+confirmed-title = Abonnement confirmé
+confirmed = Merci, votre abonnement est confirmé.
+invalid-link-title = Lien invalide
+invalid-link = Ce lien n’est pas valide.
+
+## Email change confirmation pages
+
+email-changed-title = Adresse e-mail modifiée
+email-changed = La newsletter sera désormais envoyée à { $email }.
+email-change-failed-title = Adresse e-mail inchangée
+email-change-taken = { $email } est déjà abonné.
+
+## Unsubscribe pages
+
+unsubscribe-title = Se désabonner
+unsubscribe-question = Ne plus envoyer la newsletter à { $email } ?
+unsubscribe-button = Se désabonner
+unsubscribed-title = Désabonnement effectué
+unsubscribed = Vous êtes désabonné et ne recevrez plus aucun numéro.
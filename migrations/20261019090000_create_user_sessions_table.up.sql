@@ -0,0 +1,8 @@
+create table user_sessions(
+   session_id uuid primary key,
+   user_id uuid not null references users (user_id),
+   created_at timestamptz not null,
+   last_active_at timestamptz not null,
+   ip_address text,
+   user_agent text
+);
@@ -0,0 +1,7 @@
+create table sessions(
+   session_key text primary key,
+   session_state text not null,
+   expires_at timestamptz not null
+);
+
+create index sessions_expires_at_idx on sessions (expires_at);
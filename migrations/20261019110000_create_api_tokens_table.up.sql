@@ -0,0 +1,8 @@
+create table api_tokens(
+   api_token_id uuid primary key,
+   user_id uuid not null references users (user_id),
+   name text not null,
+   token_hash text not null unique,
+   created_at timestamptz not null,
+   last_used_at timestamptz
+);
@@ -0,0 +1,2 @@
+drop table list_memberships;
+drop table lists;
@@ -0,0 +1,18 @@
+create table lists(
+   list_id uuid primary key,
+   slug text not null unique,
+   name text not null,
+   created_at timestamptz not null
+);
+create table list_memberships(
+   list_id uuid not null references lists (list_id) on delete cascade,
+   subscriber_id uuid not null references subscriptions (id) on delete cascade,
+   joined_at timestamptz not null,
+   primary key(list_id, subscriber_id)
+);
+-- Everybody who subscribed so far joined the one implicit list.
+insert into lists (list_id, slug, name, created_at)
+values (gen_random_uuid(), 'default', 'Newsletter', now());
+insert into list_memberships (list_id, subscriber_id, joined_at)
+select l.list_id, s.id, s.subscribed_at
+from lists l cross join subscriptions s;
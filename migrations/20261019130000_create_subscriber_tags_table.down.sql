@@ -0,0 +1 @@
+drop table subscriber_tags;
@@ -0,0 +1,7 @@
+create table subscriber_tags(
+   subscriber_id uuid not null references subscriptions (id) on delete cascade,
+   tag text not null,
+   tagged_at timestamptz not null,
+   primary key(subscriber_id, tag)
+);
+create index subscriber_tags_tag_idx on subscriber_tags (tag);
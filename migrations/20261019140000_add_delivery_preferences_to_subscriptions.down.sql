@@ -0,0 +1,3 @@
+alter table subscriptions
+   drop column plain_text_only,
+   drop column paused_until;
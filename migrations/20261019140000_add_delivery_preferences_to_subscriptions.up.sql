@@ -0,0 +1,3 @@
+alter table subscriptions
+   add column plain_text_only boolean not null default false,
+   add column paused_until date;
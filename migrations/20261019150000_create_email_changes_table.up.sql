@@ -0,0 +1,9 @@
+create table email_changes(
+   change_token text primary key,
+   subscriber_id uuid not null references subscriptions (id) on delete cascade,
+   old_email text not null,
+   new_email text not null,
+   requested_at timestamptz not null,
+   confirmed_at timestamptz
+);
+create index email_changes_subscriber_id_idx on email_changes (subscriber_id);
@@ -0,0 +1 @@
+alter table lists drop column opt_in;
@@ -0,0 +1,2 @@
+-- Null follows the opt-in mode configured for the application.
+alter table lists add column opt_in text;
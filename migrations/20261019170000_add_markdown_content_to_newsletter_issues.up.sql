@@ -0,0 +1,3 @@
+-- The source of issues written in Markdown, null for issues written as
+-- separate plain text and HTML bodies.
+alter table newsletter_issues add column markdown_content text;
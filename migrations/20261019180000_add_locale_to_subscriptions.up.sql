@@ -0,0 +1,2 @@
+-- Everybody subscribed so far has been receiving English.
+alter table subscriptions add column locale text not null default 'en';
@@ -0,0 +1,2 @@
+drop table newsletter_issue_assets;
+drop table newsletter_assets;
@@ -0,0 +1,14 @@
+create table newsletter_assets (
+    name text primary key,
+    content_type text not null,
+    content bytea not null,
+    uploaded_at timestamptz not null
+);
+
+-- Assets sent with an issue, inline where its HTML refers to them.
+create table newsletter_issue_assets (
+    newsletter_issue_id uuid not null
+        references newsletter_issues (newsletter_issue_id),
+    asset_name text not null references newsletter_assets (name),
+    primary key (newsletter_issue_id, asset_name)
+);
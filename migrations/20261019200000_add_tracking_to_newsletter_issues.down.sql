@@ -0,0 +1,3 @@
+drop table tracking_events;
+drop table tracking_tokens;
+alter table newsletter_issues drop column tracking;
@@ -0,0 +1,24 @@
+-- Whether opens and clicks of the issue are recorded.
+alter table newsletter_issues
+    add column tracking boolean not null default false;
+
+-- Issued to every recipient of a tracked issue: one for the open pixel and
+-- one for each link of the HTML body.
+create table tracking_tokens (
+    token text primary key,
+    newsletter_issue_id uuid not null
+        references newsletter_issues (newsletter_issue_id),
+    subscriber_id uuid not null references subscriptions (id) on delete cascade,
+    -- Where the link leads, null for the open pixel.
+    url text
+);
+create index tracking_tokens_newsletter_issue_id_idx
+    on tracking_tokens (newsletter_issue_id);
+create index tracking_tokens_subscriber_id_idx
+    on tracking_tokens (subscriber_id);
+
+create table tracking_events (
+    token text not null references tracking_tokens (token) on delete cascade,
+    occurred_at timestamptz not null
+);
+create index tracking_events_token_idx on tracking_events (token);
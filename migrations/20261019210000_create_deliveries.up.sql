@@ -0,0 +1,18 @@
+-- One row per issue sent to a subscriber, kept up to date by the email
+-- provider's webhooks.
+create table deliveries (
+    newsletter_issue_id uuid not null
+        references newsletter_issues (newsletter_issue_id),
+    subscriber_id uuid not null references subscriptions (id) on delete cascade,
+    -- sent, failed, delivered, bounced or complained.
+    status text not null,
+    -- The provider's id for the email, null if it wasn't accepted.
+    message_id text,
+    -- Why sending failed or the kind of bounce.
+    detail text,
+    sent_at timestamptz not null,
+    updated_at timestamptz not null,
+    primary key (newsletter_issue_id, subscriber_id)
+);
+create index deliveries_message_id_idx on deliveries (message_id);
+create index deliveries_subscriber_id_idx on deliveries (subscriber_id);
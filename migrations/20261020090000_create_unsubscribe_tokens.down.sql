@@ -0,0 +1 @@
+drop table unsubscribe_tokens;
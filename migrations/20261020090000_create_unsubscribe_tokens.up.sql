@@ -0,0 +1,8 @@
+-- Put in the issues sent to a subscriber. Unlike subscription tokens, they
+-- only let whoever holds the issue unsubscribe, so forwarding an issue
+-- doesn't hand over the preference center.
+create table unsubscribe_tokens (
+    unsubscribe_token text primary key,
+    subscriber_id uuid not null unique
+        references subscriptions (id) on delete cascade
+);
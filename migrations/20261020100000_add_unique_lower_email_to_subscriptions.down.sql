@@ -0,0 +1 @@
+drop index subscriptions_lower_email_idx;
@@ -0,0 +1,89 @@
+-- Subscribers whose addresses only differ by case are merged first: the
+-- confirmed one is kept, or else the oldest, and takes over the rows of
+-- the others.
+create temporary table merged_subscriptions on commit drop as
+select id, kept_id, kept_email
+from (
+    select
+        id,
+        first_value(id) over w as kept_id,
+        first_value(email) over w as kept_email
+    from subscriptions
+    window w as (
+        partition by lower(email)
+        order by status = 'confirmed' desc, subscribed_at, id
+    )
+) s
+where id <> kept_id;
+
+update subscription_tokens t
+set subscriber_id = m.kept_id
+from merged_subscriptions m
+where t.subscriber_id = m.id;
+
+update email_changes c
+set subscriber_id = m.kept_id
+from merged_subscriptions m
+where c.subscriber_id = m.id;
+
+update tracking_tokens t
+set subscriber_id = m.kept_id
+from merged_subscriptions m
+where t.subscriber_id = m.id;
+
+insert into list_memberships (list_id, subscriber_id, joined_at)
+select lm.list_id, m.kept_id, lm.joined_at
+from list_memberships lm
+join merged_subscriptions m on m.id = lm.subscriber_id
+on conflict do nothing;
+
+insert into subscriber_tags (subscriber_id, tag, tagged_at)
+select m.kept_id, t.tag, t.tagged_at
+from subscriber_tags t
+join merged_subscriptions m on m.id = t.subscriber_id
+on conflict do nothing;
+
+insert into deliveries (
+    newsletter_issue_id,
+    subscriber_id,
+    status,
+    message_id,
+    detail,
+    sent_at,
+    updated_at
+)
+select
+    d.newsletter_issue_id,
+    m.kept_id,
+    d.status,
+    d.message_id,
+    d.detail,
+    d.sent_at,
+    d.updated_at
+from deliveries d
+join merged_subscriptions m on m.id = d.subscriber_id
+on conflict do nothing;
+
+-- Pending deliveries are queued by address.
+insert into issue_delivery_queue (newsletter_issue_id, subscriber_email)
+select q.newsletter_issue_id, m.kept_email
+from issue_delivery_queue q
+join subscriptions s on s.email = q.subscriber_email
+join merged_subscriptions m on m.id = s.id
+on conflict do nothing;
+
+delete from issue_delivery_queue q
+using subscriptions s, merged_subscriptions m
+where s.email = q.subscriber_email
+and m.id = s.id;
+
+-- Memberships, tags, deliveries and unsubscribe tokens left on the merged
+-- rows go with them.
+delete from subscriptions s
+using merged_subscriptions m
+where s.id = m.id;
+
+-- Addresses are compared case-insensitively everywhere, let the database
+-- enforce it so that concurrent inserts can't slip a duplicate in.
+create unique index subscriptions_lower_email_idx
+    on subscriptions (lower(email));
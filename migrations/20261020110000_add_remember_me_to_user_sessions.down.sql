@@ -0,0 +1 @@
+alter table user_sessions drop column remember_me;
@@ -0,0 +1,3 @@
+-- Remembered sessions expire later, which pruning needs to know about.
+alter table user_sessions
+    add column remember_me boolean not null default false;
@@ -0,0 +1 @@
+alter table email_changes drop column superseded_at;
@@ -0,0 +1,3 @@
+-- Set on the pending changes of a subscriber once another one is confirmed,
+-- so that their links can't switch the address again.
+alter table email_changes add column superseded_at timestamptz;
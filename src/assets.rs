@@ -0,0 +1,199 @@
+use crate::{domain::AssetName, email_client::Attachment, Database, DbPool};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use sqlx::Transaction;
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AssetError {
+    #[error("There is no asset called {0}")]
+    Unknown(String),
+    #[error("An asset called {0} already exists")]
+    Duplicate(String),
+    #[error(
+        "The assets add up to {size} bytes, more than the {limit} an email \
+        can carry"
+    )]
+    TooLarge { size: usize, limit: usize },
+    #[error(transparent)]
+    Unexpected(#[from] anyhow::Error),
+}
+
+#[derive(Clone, Debug)]
+pub struct AssetSummary {
+    pub name: String,
+    pub content_type: String,
+    pub size: i32,
+    pub uploaded_at: DateTime<Utc>,
+}
+
+#[tracing::instrument(name = "Listing assets", skip_all)]
+pub async fn all_assets(pool: &DbPool) -> anyhow::Result<Vec<AssetSummary>> {
+    sqlx::query_as!(
+        AssetSummary,
+        r#"
+        select
+            name,
+            content_type,
+            octet_length(content) as "size!",
+            uploaded_at
+        from newsletter_assets
+        order by name;
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query for assets")
+}
+
+#[tracing::instrument(name = "Storing an asset", skip(content, pool))]
+pub async fn store_asset(
+    name: &AssetName,
+    content_type: &str,
+    content: &[u8],
+    pool: &DbPool,
+) -> Result<(), AssetError> {
+    let inserted = sqlx::query!(
+        r#"
+        insert into newsletter_assets (name, content_type, content, uploaded_at)
+        values ($1, $2, $3, now())
+        on conflict (name) do nothing;
+        "#,
+        name.as_ref(),
+        content_type,
+        content,
+    )
+    .execute(pool)
+    .await
+    .context("Failed to insert an asset")?
+    .rows_affected()
+        == 1;
+    if !inserted {
+        return Err(AssetError::Duplicate(name.to_string()));
+    }
+    Ok(())
+}
+
+/// The assets `html` shows inline, referring to them as `"cid:<name>"`.
+pub fn inline_asset_names(html: &str) -> BTreeSet<String> {
+    html.match_indices("\"cid:")
+        .map(|(i, reference)| {
+            html[i + reference.len()..]
+                .split(|c: char| {
+                    !(c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+                })
+                .next()
+                .unwrap_or_default()
+        })
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// The assets to send with an issue: those picked and those its HTML shows
+/// inline. Fails if any of them doesn't exist or they don't fit in one
+/// email.
+#[tracing::instrument(name = "Resolving issue assets", skip(html, pool))]
+pub async fn resolve_issue_assets(
+    picked: &[String],
+    html: &str,
+    max_size: usize,
+    pool: &DbPool,
+) -> Result<Vec<String>, AssetError> {
+    let mut names = inline_asset_names(html);
+    names.extend(picked.iter().cloned());
+    let names: Vec<String> = names.into_iter().collect();
+    let found = sqlx::query!(
+        r#"
+        select name, octet_length(content) as "size!"
+        from newsletter_assets
+        where name = any($1);
+        "#,
+        &names,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query for assets")?;
+    if let Some(unknown) = names
+        .iter()
+        .find(|name| !found.iter().any(|r| r.name == **name))
+    {
+        return Err(AssetError::Unknown(unknown.clone()));
+    }
+    let size = found.iter().map(|r| r.size as usize).sum();
+    if size > max_size {
+        return Err(AssetError::TooLarge {
+            size,
+            limit: max_size,
+        });
+    }
+    Ok(names)
+}
+
+#[tracing::instrument(skip(transaction))]
+pub async fn attach_assets(
+    newsletter_issue_id: &Uuid,
+    names: &[String],
+    transaction: &mut Transaction<'_, Database>,
+) -> anyhow::Result<()> {
+    sqlx::query!(
+        r#"
+        insert into newsletter_issue_assets (newsletter_issue_id, asset_name)
+        select $1, unnest($2::text[]);
+        "#,
+        newsletter_issue_id,
+        names,
+    )
+    .execute(transaction)
+    .await
+    .map(|_| ())
+    .context("Failed to attach assets to a newsletter issue")
+}
+
+/// The assets sent with an issue, given a content id when `html` shows
+/// them inline.
+#[tracing::instrument(skip(html, pool))]
+pub async fn issue_attachments(
+    newsletter_issue_id: &Uuid,
+    html: &str,
+    pool: &DbPool,
+) -> anyhow::Result<Vec<Attachment>> {
+    let inline = inline_asset_names(html);
+    let rows = sqlx::query!(
+        r#"
+        select a.name, a.content_type, a.content
+        from newsletter_issue_assets ia
+        join newsletter_assets a on a.name = ia.asset_name
+        where ia.newsletter_issue_id = $1
+        order by a.name;
+        "#,
+        newsletter_issue_id,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query for the assets of a newsletter issue")?;
+    Ok(rows
+        .into_iter()
+        .map(|r| Attachment {
+            content_id: inline.contains(&r.name).then(|| r.name.clone()),
+            name: r.name,
+            content_type: r.content_type,
+            content: r.content,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::inline_asset_names;
+
+    #[test]
+    fn inline_images_are_found_in_attributes() {
+        let html = r#"<p>Say cid:nope</p><img src="cid:logo.png" alt="">
+            <img src="cid:header_2.jpg"><img src="https://example.com/a.png">
+            <img src="cid:logo.png">"#;
+        let names: Vec<_> = inline_asset_names(html).into_iter().collect();
+        assert_eq!(names, ["header_2.jpg", "logo.png"]);
+    }
+}
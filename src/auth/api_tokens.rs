@@ -0,0 +1,122 @@
+use crate::DbPool;
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use secrecy::{ExposeSecret, Secret};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub struct ApiToken {
+    pub api_token_id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// Creates a new API token for the user.
+/// Only a hash of the token is stored, so the returned value
+/// is the only chance to show it to the user.
+#[tracing::instrument(name = "Creating an API token", skip(pool))]
+pub async fn create_api_token(
+    user_id: &Uuid,
+    name: &str,
+    pool: &DbPool,
+) -> anyhow::Result<Secret<String>> {
+    let token = generate_api_token();
+    sqlx::query!(
+        r#"
+        insert into api_tokens (
+            api_token_id,
+            user_id,
+            name,
+            token_hash,
+            created_at
+        )
+        values ($1, $2, $3, $4, now());
+        "#,
+        Uuid::new_v4(),
+        user_id,
+        name,
+        hash_api_token(&token),
+    )
+    .execute(pool)
+    .await
+    .context("Failed to store an API token")
+    .map(|_| token)
+}
+
+#[tracing::instrument(name = "Listing API tokens", skip(pool))]
+pub async fn list_api_tokens(
+    user_id: &Uuid,
+    pool: &DbPool,
+) -> anyhow::Result<Vec<ApiToken>> {
+    sqlx::query_as!(
+        ApiToken,
+        r#"
+        select api_token_id, name, created_at, last_used_at
+        from api_tokens
+        where user_id = $1
+        order by created_at;
+        "#,
+        user_id,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query for API tokens")
+}
+
+#[tracing::instrument(name = "Revoking an API token", skip(pool))]
+pub async fn revoke_api_token(
+    user_id: &Uuid,
+    api_token_id: &Uuid,
+    pool: &DbPool,
+) -> anyhow::Result<()> {
+    sqlx::query!(
+        r#"
+        delete from api_tokens
+        where api_token_id = $1
+        and user_id = $2;
+        "#,
+        api_token_id,
+        user_id,
+    )
+    .execute(pool)
+    .await
+    .map(|_| ())
+    .context("Failed to revoke an API token")
+}
+
+/// Returns the id of the user the token belongs to, if it is known.
+#[tracing::instrument(name = "Validating an API token", skip_all)]
+pub async fn validate_api_token(
+    token: Secret<String>,
+    pool: &DbPool,
+) -> anyhow::Result<Option<Uuid>> {
+    sqlx::query!(
+        r#"
+        update api_tokens
+        set last_used_at = now()
+        where token_hash = $1
+        returning user_id;
+        "#,
+        hash_api_token(&token),
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to query for an API token")
+    .map(|r| r.map(|r| r.user_id))
+}
+
+fn hash_api_token(token: &Secret<String>) -> String {
+    format!("{:x}", Sha256::digest(token.expose_secret().as_bytes()))
+}
+
+fn generate_api_token() -> Secret<String> {
+    use rand::{distributions::Alphanumeric, thread_rng, Rng};
+    let mut rng = thread_rng();
+    let token = std::iter::repeat_with(|| rng.sample(Alphanumeric))
+        .map(char::from)
+        .take(40)
+        .collect();
+    Secret::new(token)
+}
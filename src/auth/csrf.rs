@@ -0,0 +1,121 @@
+use crate::Session;
+use actix_web::{
+    body::MessageBody,
+    dev::{Payload, ServiceRequest, ServiceResponse},
+    error::ErrorForbidden,
+    http::{header::CONTENT_TYPE, Method},
+    web::Bytes,
+    FromRequest,
+};
+use actix_web_lab::middleware::Next;
+use serde::Deserialize;
+
+const CSRF_TOKEN_HEADER: &str = "X-CSRF-Token";
+
+#[derive(Deserialize)]
+struct CsrfTokenField {
+    csrf_token: Option<String>,
+}
+
+/// Returns the CSRF token bound to the session, creating one if needed.
+pub fn csrf_token(session: &Session) -> actix_web::Result<String> {
+    match session.get_csrf_token()? {
+        Some(token) => Ok(token),
+        None => Ok(rotate_csrf_token(session)?),
+    }
+}
+
+pub fn rotate_csrf_token(
+    session: &Session,
+) -> Result<String, actix_session::SessionInsertError> {
+    let token = generate_csrf_token();
+    session.insert_csrf_token(token.clone())?;
+    Ok(token)
+}
+
+/// Rejects state-changing requests that do not carry the CSRF token
+/// of the current session in the `X-CSRF-Token` header or the
+/// `csrf_token` form field.
+/// Multipart bodies can't be read here without consuming them, so their
+/// handlers check the `csrf_token` field with [`check_csrf_field`].
+pub async fn reject_forged_requests(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> actix_web::Result<ServiceResponse<impl MessageBody>> {
+    if is_safe(req.method()) {
+        return next.call(req).await;
+    }
+    let session = {
+        let (http_request, payload) = req.parts_mut();
+        Session::from_request(http_request, payload).await
+    }?;
+    let expected = session
+        .get_csrf_token()?
+        .ok_or_else(|| ErrorForbidden("Missing CSRF token in session"))?;
+    let provided = match provided_token(&req) {
+        Some(token) => Some(token),
+        None if is_multipart(&req) => return next.call(req).await,
+        None => token_from_form(&mut req).await?,
+    };
+    if provided.as_deref() != Some(expected.as_str()) {
+        return Err(ErrorForbidden("Missing or invalid CSRF token"));
+    }
+    next.call(req).await
+}
+
+fn is_safe(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+fn provided_token(req: &ServiceRequest) -> Option<String> {
+    req.headers()
+        .get(CSRF_TOKEN_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .map(String::from)
+}
+
+fn is_multipart(req: &ServiceRequest) -> bool {
+    req.headers()
+        .get(CONTENT_TYPE)
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|h| h.starts_with("multipart/form-data"))
+}
+
+/// Checks the `csrf_token` field of a multipart form against the session.
+pub fn check_csrf_field(
+    session: &Session,
+    provided: &[u8],
+) -> actix_web::Result<()> {
+    match session.get_csrf_token()? {
+        Some(expected) if expected.as_bytes() == provided => Ok(()),
+        _ => Err(ErrorForbidden("Missing or invalid CSRF token")),
+    }
+}
+
+async fn token_from_form(
+    req: &mut ServiceRequest,
+) -> actix_web::Result<Option<String>> {
+    let is_form = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|h| h.starts_with("application/x-www-form-urlencoded"));
+    if !is_form {
+        return Ok(None);
+    }
+    let body = req.extract::<Bytes>().await?;
+    let token = serde_urlencoded::from_bytes::<CsrfTokenField>(&body)
+        .ok()
+        .and_then(|f| f.csrf_token);
+    req.set_payload(Payload::from(body));
+    Ok(token)
+}
+
+fn generate_csrf_token() -> String {
+    use rand::{distributions::Alphanumeric, thread_rng, Rng};
+    let mut rng = thread_rng();
+    std::iter::repeat_with(|| rng.sample(Alphanumeric))
+        .map(char::from)
+        .take(32)
+        .collect()
+}
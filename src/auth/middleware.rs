@@ -1,14 +1,25 @@
 use std::ops::Deref;
 
-use crate::{utils::see_other, Session};
+use super::{revoke_session, touch_session, validate_api_token};
+use crate::{
+    configuration::{SessionConfig, WebhooksConfig},
+    utils::{e500, see_other},
+    DbPool, Session,
+};
 use actix_web::{
-    body::MessageBody,
+    body::{EitherBody, MessageBody},
     dev::{ServiceRequest, ServiceResponse},
     error::InternalError,
-    FromRequest, HttpMessage,
+    http::header::{AUTHORIZATION, WWW_AUTHENTICATE},
+    web::Data,
+    FromRequest, HttpMessage, HttpResponse,
 };
+use actix_web_flash_messages::FlashMessage;
 use actix_web_lab::middleware::Next;
 use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use secrecy::{ExposeSecret, Secret};
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Copy)]
@@ -31,19 +42,145 @@ impl Deref for UserId {
 pub async fn reject_anonynous_users(
     mut req: ServiceRequest,
     next: Next<impl MessageBody>,
-) -> actix_web::Result<ServiceResponse<impl MessageBody>> {
+) -> actix_web::Result<ServiceResponse<EitherBody<impl MessageBody>>> {
     let session = {
         let (http_requst, payload) = req.parts_mut();
         Session::from_request(http_requst, payload).await
     }?;
-    match session.get_user_id()? {
+    let user_id = match session.get_user_id()? {
+        Some(user_id) => user_id,
+        None => return Err(end_session(&session, "User is not logged in")),
+    };
+    let pool = req
+        .app_data::<Data<DbPool>>()
+        .ok_or_else(|| e500("Database pool is not configured"))?;
+    let config = req
+        .app_data::<Data<SessionConfig>>()
+        .ok_or_else(|| e500("Session is not configured"))?;
+    let session_id = match session.get_session_id()? {
+        Some(session_id) => session_id,
+        None => return Err(end_session(&session, "User session is unknown")),
+    };
+    if has_expired(&session, config)? {
+        revoke_session(&user_id, &session_id, pool)
+            .await
+            .map_err(e500)?;
+        session.logout();
+        // Flash messages are only attached to successful responses
+        FlashMessage::info("Your session has expired. Please log in again.")
+            .send();
+        return Ok(req
+            .into_response(see_other("/login"))
+            .map_into_right_body());
+    }
+    if !touch_session(&user_id, &session_id, pool)
+        .await
+        .map_err(e500)?
+    {
+        return Err(end_session(&session, "User session has been revoked"));
+    }
+    session.insert_last_seen_at(Utc::now())?;
+    req.extensions_mut().insert(UserId(user_id));
+    next.call(req)
+        .await
+        .map(ServiceResponse::map_into_left_body)
+}
+
+fn has_expired(
+    session: &Session,
+    config: &SessionConfig,
+) -> Result<bool, actix_session::SessionGetError> {
+    let now = Utc::now();
+    let elapsed_since =
+        |t: DateTime<Utc>| (now - t).to_std().unwrap_or_default();
+    let remember_me = session.get_remember_me()?.unwrap_or(false);
+    let (max_age, idle_timeout) = if remember_me {
+        (config.remember_me_max_age, config.remember_me_idle_timeout)
+    } else {
+        (config.max_age, config.idle_timeout)
+    };
+    let expired = match session.get_logged_in_at()? {
+        Some(logged_in_at) => elapsed_since(logged_in_at) > max_age,
+        None => true,
+    };
+    let idle = match session.get_last_seen_at()? {
+        Some(last_seen_at) => elapsed_since(last_seen_at) > idle_timeout,
+        None => true,
+    };
+    Ok(expired || idle)
+}
+
+fn end_session(session: &Session, reason: &'static str) -> actix_web::Error {
+    session.logout();
+    InternalError::from_response(anyhow!(reason), see_other("/login")).into()
+}
+
+/// Authenticates API clients by the `Authorization: Bearer <token>` header.
+pub async fn reject_unauthenticated_clients(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> actix_web::Result<ServiceResponse<impl MessageBody>> {
+    let token = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .map(|t| Secret::new(t.trim().to_owned()));
+    let token = match token {
+        Some(token) => token,
+        None => return Err(unauthorized("Missing bearer token")),
+    };
+    let pool = req
+        .app_data::<Data<DbPool>>()
+        .ok_or_else(|| e500("Database pool is not configured"))?;
+    match validate_api_token(token, pool).await.map_err(e500)? {
         Some(user_id) => {
             req.extensions_mut().insert(UserId(user_id));
             next.call(req).await
         }
-        None => {
-            let e = anyhow!("User is not logged in");
-            Err(InternalError::from_response(e, see_other("/login")).into())
-        }
+        None => Err(unauthorized("Invalid bearer token")),
     }
 }
+
+fn unauthorized(reason: &'static str) -> actix_web::Error {
+    let response = HttpResponse::Unauthorized()
+        .insert_header((WWW_AUTHENTICATE, "Bearer"))
+        .json(serde_json::json!({ "error": reason }));
+    InternalError::from_response(anyhow!(reason), response).into()
+}
+
+/// Authenticates the email provider's webhook calls by basic auth.
+pub async fn reject_unauthenticated_webhooks(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> actix_web::Result<ServiceResponse<impl MessageBody>> {
+    let config = req
+        .app_data::<Data<WebhooksConfig>>()
+        .ok_or_else(|| e500("Webhooks are not configured"))?;
+    let credentials = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Basic "))
+        .and_then(|encoded| base64::decode(encoded.trim()).ok())
+        .and_then(|decoded| String::from_utf8(decoded).ok());
+    let credentials = match credentials {
+        Some(credentials) => credentials,
+        None => return Err(unauthorized_webhook("Missing basic auth")),
+    };
+    let expected =
+        format!("{}:{}", config.username, config.password.expose_secret());
+    // Comparing digests keeps the time taken from revealing how much of
+    // the credentials matched.
+    if Sha256::digest(credentials) != Sha256::digest(expected) {
+        return Err(unauthorized_webhook("Invalid basic auth credentials"));
+    }
+    next.call(req).await
+}
+
+fn unauthorized_webhook(reason: &'static str) -> actix_web::Error {
+    let response = HttpResponse::Unauthorized()
+        .insert_header((WWW_AUTHENTICATE, r#"Basic realm="webhooks""#))
+        .finish();
+    InternalError::from_response(anyhow!(reason), response).into()
+}
@@ -1,5 +1,11 @@
+mod api_tokens;
+mod csrf;
 mod middleware;
 mod password;
+mod sessions;
 
+pub use api_tokens::*;
+pub use csrf::*;
 pub use middleware::*;
 pub use password::*;
+pub use sessions::*;
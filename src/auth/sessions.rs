@@ -0,0 +1,230 @@
+use crate::{configuration::SessionConfig, DbPool};
+use actix_web::{http::header::USER_AGENT, HttpRequest};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// How stale `last_active_at` may get before a request updates it, so that
+/// authenticated requests don't all write to the database.
+const TOUCH_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone)]
+pub struct ClientInfo {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl From<&HttpRequest> for ClientInfo {
+    fn from(request: &HttpRequest) -> Self {
+        let ip_address = request
+            .connection_info()
+            .realip_remote_addr()
+            .map(String::from);
+        let user_agent = request
+            .headers()
+            .get(USER_AGENT)
+            .and_then(|h| h.to_str().ok())
+            .map(String::from);
+        Self {
+            ip_address,
+            user_agent,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ActiveSession {
+    pub session_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub last_active_at: DateTime<Utc>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+#[tracing::instrument(name = "Registering a user session", skip_all)]
+pub async fn register_session(
+    user_id: &Uuid,
+    client: ClientInfo,
+    remember_me: bool,
+    pool: &DbPool,
+) -> anyhow::Result<Uuid> {
+    let session_id = Uuid::new_v4();
+    sqlx::query!(
+        r#"
+        insert into user_sessions (
+            session_id,
+            user_id,
+            created_at,
+            last_active_at,
+            ip_address,
+            user_agent,
+            remember_me
+        )
+        values ($1, $2, now(), now(), $3, $4, $5);
+        "#,
+        session_id,
+        user_id,
+        client.ip_address,
+        client.user_agent,
+        remember_me,
+    )
+    .execute(pool)
+    .await
+    .context("Failed to register a user session")
+    .map(|_| session_id)
+}
+
+/// Records activity on a session, at most once per [`TOUCH_INTERVAL`].
+/// Returns `false` if the session has been revoked.
+#[tracing::instrument(name = "Touching a user session", skip_all)]
+pub async fn touch_session(
+    user_id: &Uuid,
+    session_id: &Uuid,
+    pool: &DbPool,
+) -> anyhow::Result<bool> {
+    let stale_before = Utc::now() - chrono::Duration::from_std(TOUCH_INTERVAL)?;
+    sqlx::query_scalar!(
+        r#"
+        with session as (
+            select session_id, last_active_at
+            from user_sessions
+            where session_id = $1
+            and user_id = $2
+        ), touched as (
+            update user_sessions
+            set last_active_at = now()
+            from session
+            where user_sessions.session_id = session.session_id
+            and session.last_active_at < $3
+        )
+        select count(*) as "count!" from session;
+        "#,
+        session_id,
+        user_id,
+        stale_before,
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to update user session activity")
+    .map(|count| count == 1)
+}
+
+/// Deletes the sessions of a user that have expired or idled out.
+/// The middleware only notices them if they are used again, which a closed
+/// browser never does.
+#[tracing::instrument(name = "Pruning expired user sessions", skip_all)]
+pub async fn prune_sessions(
+    user_id: &Uuid,
+    config: &SessionConfig,
+    pool: &DbPool,
+) -> anyhow::Result<()> {
+    let now = Utc::now();
+    let before = |d: Duration| -> anyhow::Result<DateTime<Utc>> {
+        Ok(now - chrono::Duration::from_std(d)?)
+    };
+    sqlx::query!(
+        r#"
+        delete from user_sessions
+        where user_id = $1
+        and case when remember_me
+            then created_at < $4 or last_active_at < $5
+            else created_at < $2 or last_active_at < $3
+        end;
+        "#,
+        user_id,
+        before(config.max_age)?,
+        before(config.idle_timeout)?,
+        before(config.remember_me_max_age)?,
+        before(config.remember_me_idle_timeout)?,
+    )
+    .execute(pool)
+    .await
+    .context("Failed to prune expired user sessions")?;
+    Ok(())
+}
+
+#[tracing::instrument(name = "Listing user sessions", skip_all)]
+pub async fn list_sessions(
+    user_id: &Uuid,
+    pool: &DbPool,
+) -> anyhow::Result<Vec<ActiveSession>> {
+    sqlx::query_as!(
+        ActiveSession,
+        r#"
+        select
+            session_id,
+            created_at,
+            last_active_at,
+            ip_address,
+            user_agent
+        from user_sessions
+        where user_id = $1
+        order by last_active_at desc;
+        "#,
+        user_id,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query for user sessions")
+}
+
+#[tracing::instrument(name = "Revoking a user session", skip_all)]
+pub async fn revoke_session(
+    user_id: &Uuid,
+    session_id: &Uuid,
+    pool: &DbPool,
+) -> anyhow::Result<()> {
+    sqlx::query!(
+        r#"
+        delete from user_sessions
+        where session_id = $1
+        and user_id = $2;
+        "#,
+        session_id,
+        user_id,
+    )
+    .execute(pool)
+    .await
+    .map(|_| ())
+    .context("Failed to revoke a user session")
+}
+
+#[tracing::instrument(name = "Revoking all user sessions", skip_all)]
+pub async fn revoke_all_sessions(
+    user_id: &Uuid,
+    pool: &DbPool,
+) -> anyhow::Result<()> {
+    sqlx::query!(
+        r#"
+        delete from user_sessions
+        where user_id = $1;
+        "#,
+        user_id,
+    )
+    .execute(pool)
+    .await
+    .map(|_| ())
+    .context("Failed to revoke user sessions")
+}
+
+#[tracing::instrument(name = "Revoking other user sessions", skip_all)]
+pub async fn revoke_other_sessions(
+    user_id: &Uuid,
+    current_session_id: &Uuid,
+    pool: &DbPool,
+) -> anyhow::Result<()> {
+    sqlx::query!(
+        r#"
+        delete from user_sessions
+        where user_id = $1
+        and session_id <> $2;
+        "#,
+        user_id,
+        current_session_id,
+    )
+    .execute(pool)
+    .await
+    .map(|_| ())
+    .context("Failed to revoke other user sessions")
+}
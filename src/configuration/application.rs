@@ -1,3 +1,5 @@
+use super::{SessionConfig, WebhooksConfig};
+use crate::domain::OptInMode;
 use secrecy::Secret;
 use serde::Deserialize;
 use serde_aux::field_attributes::deserialize_number_from_string;
@@ -8,6 +10,10 @@ pub struct ApplicationConfig {
     #[serde(deserialize_with = "deserialize_number_from_string")]
     pub port: u16,
     pub base_url: String,
-    pub redis_url: Secret<String>,
+    pub redis_url: Option<Secret<String>>,
     pub hmac_secret: Secret<String>,
+    pub session: SessionConfig,
+    /// Used for lists that don't set their own mode.
+    pub opt_in: OptInMode,
+    pub webhooks: WebhooksConfig,
 }
@@ -1,6 +1,7 @@
 use reqwest::Url;
 use secrecy::Secret;
 use serde::Deserialize;
+use serde_aux::field_attributes::deserialize_number_from_string;
 use serde_with::{serde_as, DisplayFromStr};
 use std::time::Duration;
 
@@ -13,5 +14,11 @@ pub struct EmailClientConfig {
     #[serde_as(as = "DisplayFromStr")]
     pub base_url: Url,
     pub sender: SubscriberEmail,
+    /// Shown next to `sender`, e.g. "Acme News".
+    #[serde(default)]
+    pub sender_name: Option<String>,
     pub authorization_token: Secret<String>,
+    /// The most bytes the attachments of a single email can add up to.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_attachments_size: usize,
 }
@@ -0,0 +1,18 @@
+use serde::Deserialize;
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct EmailTemplatesConfig {
+    /// Holds a `.txt` and a `.html` template for every kind of email.
+    pub directory: PathBuf,
+    pub subjects: EmailSubjects,
+}
+
+/// Subject lines, rendered with the same values as the email bodies.
+#[derive(Clone, Debug, Deserialize)]
+pub struct EmailSubjects {
+    pub confirmation: String,
+    pub welcome: String,
+    pub email_change: String,
+    pub newsletter: String,
+}
@@ -0,0 +1,11 @@
+use serde::Deserialize;
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct LocalesConfig {
+    /// Holds a `<locale>.ftl` Fluent catalogue for every supported locale.
+    pub directory: PathBuf,
+    /// Used when a subscriber's language isn't supported, must have a
+    /// catalogue.
+    pub default: String,
+}
@@ -1,12 +1,22 @@
 mod application;
 mod database;
 mod email_client;
+mod email_templates;
 mod environment;
+mod locales;
+mod session;
+mod webhooks;
 
+pub use application::ApplicationConfig;
 pub use database::DatabaseConfig;
 pub use email_client::EmailClientConfig;
+pub use email_templates::{EmailSubjects, EmailTemplatesConfig};
+pub use locales::LocalesConfig;
+pub use session::{SameSite, SessionConfig, SessionStoreKind};
+pub use webhooks::WebhooksConfig;
+
+pub use crate::domain::OptInMode;
 
-use application::ApplicationConfig;
 use environment::Environment;
 use serde::Deserialize;
 
@@ -16,6 +26,8 @@ pub struct Config {
     pub application: ApplicationConfig,
     pub database: DatabaseConfig,
     pub email_client: EmailClientConfig,
+    pub email_templates: EmailTemplatesConfig,
+    pub locales: LocalesConfig,
 }
 
 impl Config {
@@ -0,0 +1,51 @@
+use actix_web::cookie::SameSite as CookieSameSite;
+use serde::Deserialize;
+use serde_aux::field_attributes::deserialize_bool_from_anything;
+use std::time::Duration;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SessionConfig {
+    pub store: SessionStoreKind,
+    pub cookie_name: String,
+    #[serde(deserialize_with = "deserialize_bool_from_anything")]
+    pub cookie_secure: bool,
+    pub cookie_same_site: SameSite,
+    /// Logs the user out after a period of inactivity. Users who asked to
+    /// be remembered get `remember_me_idle_timeout` instead.
+    pub idle_timeout: Duration,
+    /// The longer period of inactivity allowed to users who asked to be
+    /// remembered, so that a forgotten device doesn't stay logged in for
+    /// the whole `remember_me_max_age`.
+    pub remember_me_idle_timeout: Duration,
+    /// Absolute lifetime of a session, regardless of activity.
+    pub max_age: Duration,
+    /// Absolute lifetime of a session when the user asked to be remembered.
+    pub remember_me_max_age: Duration,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionStoreKind {
+    Redis,
+    Postgres,
+    /// Not shared between instances and lost on restart.
+    Memory,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+impl From<SameSite> for CookieSameSite {
+    fn from(value: SameSite) -> Self {
+        match value {
+            SameSite::Strict => Self::Strict,
+            SameSite::Lax => Self::Lax,
+            SameSite::None => Self::None,
+        }
+    }
+}
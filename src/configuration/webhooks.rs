@@ -0,0 +1,11 @@
+use secrecy::Secret;
+use serde::Deserialize;
+
+/// The basic auth credentials the email provider calls our webhooks with.
+#[derive(Clone, Debug, Deserialize)]
+pub struct WebhooksConfig {
+    pub username: String,
+    /// Only set for local development, deployments must provide it through
+    /// `APP_WEBHOOKS__PASSWORD` or the application refuses to start.
+    pub password: Secret<String>,
+}
@@ -0,0 +1,99 @@
+//! What became of each issue sent to a subscriber.
+use crate::{email_client::SendResult, Database, DbPool};
+use anyhow::Context;
+use sqlx::Transaction;
+use uuid::Uuid;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeliveryStatus {
+    /// Accepted by the provider.
+    Sent,
+    /// Not accepted by the provider, or not even sent.
+    Failed,
+    Delivered,
+    Bounced,
+    Complained,
+}
+
+impl AsRef<str> for DeliveryStatus {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Sent => "sent",
+            Self::Failed => "failed",
+            Self::Delivered => "delivered",
+            Self::Bounced => "bounced",
+            Self::Complained => "complained",
+        }
+    }
+}
+
+/// Logs the outcome of sending an issue to a subscriber.
+#[tracing::instrument(skip(result, transaction))]
+pub async fn record_delivery(
+    newsletter_issue_id: &Uuid,
+    subscriber_id: &Uuid,
+    result: &anyhow::Result<SendResult>,
+    transaction: &mut Transaction<'_, Database>,
+) -> anyhow::Result<()> {
+    let (status, message_id, detail) = match result {
+        Ok(sent) => (DeliveryStatus::Sent, sent.message_id.clone(), None),
+        Err(e) => (DeliveryStatus::Failed, None, Some(e.to_string())),
+    };
+    sqlx::query!(
+        r#"
+        insert into deliveries (
+            newsletter_issue_id,
+            subscriber_id,
+            status,
+            message_id,
+            detail,
+            sent_at,
+            updated_at
+        )
+        values ($1, $2, $3, $4, $5, now(), now())
+        on conflict (newsletter_issue_id, subscriber_id) do update
+        set status = excluded.status,
+            message_id = excluded.message_id,
+            detail = excluded.detail,
+            sent_at = excluded.sent_at,
+            updated_at = excluded.updated_at;
+        "#,
+        newsletter_issue_id,
+        subscriber_id,
+        status.as_ref(),
+        message_id,
+        detail,
+    )
+    .execute(transaction)
+    .await
+    .context("Failed to record a delivery")
+    .map(|_| ())
+}
+
+/// Applies what the provider later reported about an email. A delivery
+/// doesn't override a bounce or complaint reported before it.
+/// Returns `false` if no delivery was updated, e.g. because the email
+/// wasn't an issue.
+#[tracing::instrument(skip(pool))]
+pub async fn update_delivery(
+    message_id: &str,
+    status: DeliveryStatus,
+    detail: Option<&str>,
+    pool: &DbPool,
+) -> anyhow::Result<bool> {
+    sqlx::query!(
+        r#"
+        update deliveries
+        set status = $2, detail = $3, updated_at = now()
+        where message_id = $1
+        and ($2 <> 'delivered' or status = 'sent');
+        "#,
+        message_id,
+        status.as_ref(),
+        detail,
+    )
+    .execute(pool)
+    .await
+    .context("Failed to update a delivery")
+    .map(|r| r.rows_affected() > 0)
+}
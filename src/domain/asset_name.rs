@@ -0,0 +1,63 @@
+/// What an uploaded asset is called and referred to by, e.g. `logo.png` in
+/// `<img src="cid:logo.png">`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AssetName(String);
+
+impl std::fmt::Display for AssetName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl TryFrom<String> for AssetName {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let valid = !value.is_empty()
+            && value.len() <= 100
+            && !value.starts_with('.')
+            && value.chars().all(|c| {
+                c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_')
+            });
+        if valid {
+            Ok(Self(value))
+        } else {
+            Err(format!("{value} is not a valid asset name"))
+        }
+    }
+}
+
+impl AsRef<str> for AssetName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AssetName;
+
+    #[test]
+    fn file_names_are_valid() {
+        for name in ["logo.png", "Header_2026-10.JPG", "a"] {
+            assert!(AssetName::try_from(name.to_string()).is_ok());
+        }
+    }
+
+    #[test]
+    fn empty_string_is_rejected() {
+        assert!(AssetName::try_from(String::new()).is_err());
+    }
+
+    #[test]
+    fn a_name_longer_than_100_characters_is_rejected() {
+        assert!(AssetName::try_from("a".repeat(101)).is_err());
+    }
+
+    #[test]
+    fn names_with_other_characters_are_rejected() {
+        for name in [".htaccess", "my logo.png", "../logo.png", "logo\".png"] {
+            assert!(AssetName::try_from(name.to_string()).is_err());
+        }
+    }
+}
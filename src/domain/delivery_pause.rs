@@ -0,0 +1,69 @@
+use chrono::{Duration, NaiveDate, Utc};
+
+/// The day newsletter delivery resumes on, between tomorrow and a year
+/// from now.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeliveryPause(NaiveDate);
+
+impl DeliveryPause {
+    pub fn parse(value: &str, today: NaiveDate) -> Result<Self, String> {
+        let resumes_on = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .map_err(|_| format!("{value} is not a valid date"))?;
+        if resumes_on <= today {
+            return Err("A pause must end after today.".into());
+        }
+        if resumes_on > today + Duration::days(365) {
+            return Err("Delivery can be paused for a year at most.".into());
+        }
+        Ok(Self(resumes_on))
+    }
+
+    pub fn resumes_on(&self) -> NaiveDate {
+        self.0
+    }
+}
+
+impl TryFrom<String> for DeliveryPause {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse(&value, Utc::now().date_naive())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeliveryPause;
+    use chrono::NaiveDate;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, 19).unwrap()
+    }
+
+    #[test]
+    fn a_date_within_the_next_year_is_valid() {
+        for date in ["2026-10-20", "2027-10-19"] {
+            let pause = DeliveryPause::parse(date, today()).unwrap();
+            assert_eq!(pause.resumes_on().to_string(), date);
+        }
+    }
+
+    #[test]
+    fn today_and_past_dates_are_rejected() {
+        for date in ["2026-10-19", "2025-01-01"] {
+            assert!(DeliveryPause::parse(date, today()).is_err());
+        }
+    }
+
+    #[test]
+    fn dates_more_than_a_year_ahead_are_rejected() {
+        assert!(DeliveryPause::parse("2027-10-20", today()).is_err());
+    }
+
+    #[test]
+    fn malformed_dates_are_rejected() {
+        for date in ["", "19/10/2026", "2026-02-30", "tomorrow"] {
+            assert!(DeliveryPause::parse(date, today()).is_err());
+        }
+    }
+}
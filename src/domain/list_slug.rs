@@ -0,0 +1,67 @@
+/// The URL-safe identifier of a mailing list, e.g. `product-updates`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ListSlug(String);
+
+impl ListSlug {
+    pub fn default_list() -> Self {
+        Self("default".into())
+    }
+}
+
+impl std::fmt::Display for ListSlug {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl TryFrom<String> for ListSlug {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let valid = !value.is_empty()
+            && value.len() <= 64
+            && value.chars().all(|c| {
+                c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-'
+            });
+        if valid {
+            Ok(Self(value))
+        } else {
+            Err(format!("{value} is not a valid list identifier"))
+        }
+    }
+}
+
+impl AsRef<str> for ListSlug {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ListSlug;
+
+    #[test]
+    fn lowercase_letters_digits_and_dashes_are_valid() {
+        let slug = "product-updates-2026".to_string();
+        assert!(ListSlug::try_from(slug).is_ok());
+    }
+
+    #[test]
+    fn empty_string_is_rejected() {
+        assert!(ListSlug::try_from(String::new()).is_err());
+    }
+
+    #[test]
+    fn a_slug_longer_than_64_characters_is_rejected() {
+        let slug = "a".repeat(65);
+        assert!(ListSlug::try_from(slug).is_err());
+    }
+
+    #[test]
+    fn slugs_with_other_characters_are_rejected() {
+        for slug in ["Product", "product updates", "product_updates", "é"] {
+            assert!(ListSlug::try_from(slug.to_string()).is_err());
+        }
+    }
+}
@@ -1,7 +1,19 @@
+mod asset_name;
+mod delivery_pause;
+mod list_slug;
 mod new_subscriber;
+mod opt_in_mode;
 mod subscriber_email;
 mod subscriber_name;
+mod subscriber_status;
+mod tag;
 
+pub use asset_name::*;
+pub use delivery_pause::*;
+pub use list_slug::*;
 pub use new_subscriber::*;
+pub use opt_in_mode::*;
 pub use subscriber_email::*;
 pub use subscriber_name::*;
+pub use subscriber_status::*;
+pub use tag::*;
@@ -0,0 +1,41 @@
+use serde::Deserialize;
+
+/// Whether new subscribers confirm their address before receiving issues.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OptInMode {
+    /// Subscribers are sent a confirmation link.
+    Double,
+    /// Subscribers are confirmed right away and sent a welcome email.
+    Single,
+}
+
+impl OptInMode {
+    pub const ALL: [Self; 2] = [Self::Double, Self::Single];
+}
+
+impl std::fmt::Display for OptInMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.as_ref().fmt(f)
+    }
+}
+
+impl TryFrom<String> for OptInMode {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::ALL
+            .into_iter()
+            .find(|m| m.as_ref() == value)
+            .ok_or_else(|| format!("{value} is not a valid opt-in mode"))
+    }
+}
+
+impl AsRef<str> for OptInMode {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Double => "double",
+            Self::Single => "single",
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use serde::{de, Deserialize};
+use serde::{de, Deserialize, Serialize};
 use validator::validate_email;
 
 #[derive(Clone, Debug)]
@@ -38,6 +38,15 @@ impl<'de> Deserialize<'de> for SubscriberEmail {
     }
 }
 
+impl Serialize for SubscriberEmail {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_ref())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::SubscriberEmail;
@@ -1,3 +1,4 @@
+use serde::Serialize;
 use unicode_segmentation::UnicodeSegmentation;
 
 #[derive(Clone, Debug)]
@@ -31,6 +32,15 @@ impl AsRef<str> for SubscriberName {
     }
 }
 
+impl Serialize for SubscriberName {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_ref())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::SubscriberName;
@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriberStatus {
+    PendingConfirmation,
+    Confirmed,
+    Unsubscribed,
+    /// Their address hard bounced, so issues are no longer sent to it.
+    Bounced,
+    /// They marked an issue as spam.
+    Complained,
+}
+
+impl SubscriberStatus {
+    pub const ALL: [Self; 5] = [
+        Self::PendingConfirmation,
+        Self::Confirmed,
+        Self::Unsubscribed,
+        Self::Bounced,
+        Self::Complained,
+    ];
+
+    /// Whether the provider told us to stop sending to the address. Unlike
+    /// unsubscribing, this is for good: nothing but an admin undoes it.
+    pub fn is_suppressed(&self) -> bool {
+        matches!(self, Self::Bounced | Self::Complained)
+    }
+}
+
+impl std::fmt::Display for SubscriberStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.as_ref().fmt(f)
+    }
+}
+
+impl TryFrom<String> for SubscriberStatus {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::ALL
+            .into_iter()
+            .find(|s| s.as_ref() == value)
+            .ok_or_else(|| format!("{value} is not a valid subscriber status"))
+    }
+}
+
+impl AsRef<str> for SubscriberStatus {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::PendingConfirmation => "pending_confirmation",
+            Self::Confirmed => "confirmed",
+            Self::Unsubscribed => "unsubscribed",
+            Self::Bounced => "bounced",
+            Self::Complained => "complained",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SubscriberStatus;
+
+    #[test]
+    fn statuses_round_trip_through_strings() {
+        for status in SubscriberStatus::ALL {
+            let parsed = SubscriberStatus::try_from(status.to_string());
+            assert_eq!(parsed, Ok(status));
+        }
+    }
+
+    #[test]
+    fn unknown_status_is_rejected() {
+        let status = "subscribed".to_string();
+        assert!(SubscriberStatus::try_from(status).is_err());
+    }
+
+    #[test]
+    fn statuses_are_serialized_as_stored() {
+        let json = serde_json::to_string(&SubscriberStatus::Confirmed);
+        assert_eq!(json.unwrap(), r#""confirmed""#);
+    }
+}
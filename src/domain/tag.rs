@@ -0,0 +1,59 @@
+/// A label attached to subscribers for segmenting, e.g. `beta`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tag(String);
+
+impl std::fmt::Display for Tag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl TryFrom<String> for Tag {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let valid = !value.is_empty()
+            && value.len() <= 64
+            && value.chars().all(|c| {
+                c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-'
+            });
+        if valid {
+            Ok(Self(value))
+        } else {
+            Err(format!("{value} is not a valid tag"))
+        }
+    }
+}
+
+impl AsRef<str> for Tag {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Tag;
+
+    #[test]
+    fn lowercase_letters_digits_and_dashes_are_valid() {
+        assert!(Tag::try_from("beta-2026".to_string()).is_ok());
+    }
+
+    #[test]
+    fn empty_string_is_rejected() {
+        assert!(Tag::try_from(String::new()).is_err());
+    }
+
+    #[test]
+    fn a_tag_longer_than_64_characters_is_rejected() {
+        assert!(Tag::try_from("a".repeat(65)).is_err());
+    }
+
+    #[test]
+    fn tags_with_other_characters_are_rejected() {
+        for tag in ["Beta", "beta tester", "beta_tester", "é"] {
+            assert!(Tag::try_from(tag.to_string()).is_err());
+        }
+    }
+}
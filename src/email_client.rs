@@ -1,47 +1,256 @@
 use crate::{
     configuration::EmailClientConfig as Config, domain::SubscriberEmail,
 };
-use reqwest::Url;
+use anyhow::bail;
+use reqwest::{StatusCode, Url};
 use secrecy::{ExposeSecret, Secret};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Clone, Debug)]
 pub struct EmailClient {
     http_client: reqwest::Client,
     base_url: Url,
-    sender: SubscriberEmail,
+    sender: Mailbox,
     authorization_token: Secret<String>,
+    max_attachments_size: usize,
+}
+
+/// An address, optionally with the name shown next to it.
+#[derive(Clone, Debug)]
+pub struct Mailbox {
+    name: Option<String>,
+    email: SubscriberEmail,
+}
+
+impl Mailbox {
+    pub fn named(name: impl Into<String>, email: SubscriberEmail) -> Self {
+        // Line breaks would let the name inject headers.
+        let name: String =
+            name.into().chars().filter(|c| !c.is_control()).collect();
+        let name = name.trim();
+        Self {
+            name: (!name.is_empty()).then(|| name.to_owned()),
+            email,
+        }
+    }
+}
+
+impl From<SubscriberEmail> for Mailbox {
+    fn from(email: SubscriberEmail) -> Self {
+        Self { name: None, email }
+    }
+}
+
+impl std::fmt::Display for Mailbox {
+    /// `Acme News <news@acme.com>`, quoting the name when it has
+    /// punctuation that would otherwise be read as part of the address.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match &self.name {
+            Some(name) => name,
+            None => return write!(f, "{}", self.email),
+        };
+        let is_atom = |c: char| {
+            c.is_ascii_alphanumeric()
+                || c == ' '
+                || "!#$%&'*+-/=?^_`{|}~".contains(c)
+        };
+        if name.chars().all(is_atom) {
+            write!(f, "{name} <{}>", self.email)
+        } else {
+            let quoted = name.replace('\\', "\\\\").replace('"', "\\\"");
+            write!(f, "\"{quoted}\" <{}>", self.email)
+        }
+    }
+}
+
+/// The provider's answer to an email it accepted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SendResult {
+    pub status: StatusCode,
+    /// Identifies the email in the provider's webhooks, `None` if the
+    /// answer didn't say.
+    pub message_id: Option<String>,
+    /// e.g. "OK".
+    pub message: String,
+}
+
+/// A file sent along with an email.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attachment {
+    pub name: String,
+    pub content_type: String,
+    pub content: Vec<u8>,
+    /// Set for images shown inline, which the HTML body refers to as
+    /// `cid:<content id>`.
+    pub content_id: Option<String>,
+}
+
+/// An email to send from the configured sender, built up from its first
+/// recipient, subject and plain text body.
+#[derive(Clone, Debug)]
+pub struct EmailMessage {
+    to: Vec<Mailbox>,
+    cc: Vec<Mailbox>,
+    bcc: Vec<Mailbox>,
+    reply_to: Option<Mailbox>,
+    subject: String,
+    text_body: String,
+    html_body: String,
+    headers: Vec<(String, String)>,
+    tag: Option<String>,
+    metadata: BTreeMap<String, String>,
+    attachments: Vec<Attachment>,
+}
+
+impl EmailMessage {
+    pub fn new(
+        to: impl Into<Mailbox>,
+        subject: impl Into<String>,
+        text_body: impl Into<String>,
+    ) -> Self {
+        Self {
+            to: vec![to.into()],
+            cc: Vec::new(),
+            bcc: Vec::new(),
+            reply_to: None,
+            subject: subject.into(),
+            text_body: text_body.into(),
+            html_body: String::new(),
+            headers: Vec::new(),
+            tag: None,
+            metadata: BTreeMap::new(),
+            attachments: Vec::new(),
+        }
+    }
+
+    /// Left out when empty, making a plain-text-only email.
+    pub fn html_body(mut self, html_body: impl Into<String>) -> Self {
+        self.html_body = html_body.into();
+        self
+    }
+
+    pub fn to(mut self, recipient: impl Into<Mailbox>) -> Self {
+        self.to.push(recipient.into());
+        self
+    }
+
+    pub fn cc(mut self, recipient: impl Into<Mailbox>) -> Self {
+        self.cc.push(recipient.into());
+        self
+    }
+
+    pub fn bcc(mut self, recipient: impl Into<Mailbox>) -> Self {
+        self.bcc.push(recipient.into());
+        self
+    }
+
+    pub fn reply_to(mut self, mailbox: impl Into<Mailbox>) -> Self {
+        self.reply_to = Some(mailbox.into());
+        self
+    }
+
+    pub fn header(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// The provider groups its statistics by tag.
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    /// Stored by the provider and sent back with its webhooks.
+    pub fn metadata(
+        mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn attachments(mut self, attachments: Vec<Attachment>) -> Self {
+        self.attachments = attachments;
+        self
+    }
+
+    fn validate(&self, max_attachments_size: usize) -> anyhow::Result<()> {
+        let size: usize =
+            self.attachments.iter().map(|a| a.content.len()).sum();
+        if size > max_attachments_size {
+            bail!(
+                "The attachments take {size} bytes, more than the \
+                {max_attachments_size} allowed"
+            );
+        }
+        for (name, value) in &self.headers {
+            let is_token = |c: char| {
+                c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)
+            };
+            if name.is_empty() || !name.chars().all(is_token) {
+                bail!("{name:?} is not a valid header name");
+            }
+            if value.contains(['\r', '\n']) {
+                bail!("The value of the {name} header spans several lines");
+            }
+        }
+        Ok(())
+    }
 }
 
 impl EmailClient {
     pub fn new(config: Config) -> Self {
+        let sender = match config.sender_name {
+            Some(name) => Mailbox::named(name, config.sender),
+            None => config.sender.into(),
+        };
         Self {
             http_client: reqwest::Client::builder()
                 .timeout(config.timeout)
                 .build()
                 .unwrap(),
             base_url: config.base_url,
-            sender: config.sender,
+            sender,
             authorization_token: config.authorization_token,
+            max_attachments_size: config.max_attachments_size,
         }
     }
 
+    pub fn max_attachments_size(&self) -> usize {
+        self.max_attachments_size
+    }
+
     pub async fn send_email(
         &self,
         recipient: &SubscriberEmail,
         subject: &str,
         text_body: &str,
         html_body: &str,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<SendResult> {
+        self.send(
+            &EmailMessage::new(recipient.clone(), subject, text_body)
+                .html_body(html_body),
+        )
+        .await
+    }
+
+    /// Fails without sending anything if the attachments add up to more
+    /// than the configured limit or a custom header is malformed.
+    pub async fn send(
+        &self,
+        message: &EmailMessage,
+    ) -> anyhow::Result<SendResult> {
+        message.validate(self.max_attachments_size)?;
         let url = self.base_url.join("/email").unwrap();
-        let request_body = SendEmailRequest {
-            from: self.sender.as_ref(),
-            to: recipient.as_ref(),
-            subject,
-            text_body,
-            html_body,
-        };
-        self.http_client
+        let request_body = SendEmailRequest::new(&self.sender, message);
+        let response = self
+            .http_client
             .post(url)
             .header(
                 "X-Postmark-Server-Token",
@@ -49,21 +258,127 @@ impl EmailClient {
             )
             .json(&request_body)
             .send()
-            .await?
-            .error_for_status()
-            .map(|_| ())
-            .map_err(anyhow::Error::from)
+            .await?;
+        let status = response.status();
+        // The email is out once accepted, even if the answer is unreadable.
+        let body: SendEmailResponse = response.json().await.unwrap_or_default();
+        if !status.is_success() {
+            bail!(
+                "The email provider answered {status} (error {}): {}",
+                body.error_code,
+                body.message
+            );
+        }
+        Ok(SendResult {
+            status,
+            message_id: body.message_id,
+            message: body.message,
+        })
     }
 }
 
 #[derive(Serialize)]
 #[serde(rename_all = "PascalCase")]
 struct SendEmailRequest<'a> {
-    from: &'a str,
-    to: &'a str,
+    from: String,
+    /// Comma separated, like `cc` and `bcc`.
+    to: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    cc: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    bcc: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reply_to: Option<String>,
     subject: &'a str,
     text_body: &'a str,
+    /// Left out for plain-text-only emails.
+    #[serde(skip_serializing_if = "str::is_empty")]
     html_body: &'a str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    headers: Vec<HeaderRequest<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag: Option<&'a str>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    metadata: &'a BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    attachments: Vec<AttachmentRequest<'a>>,
+}
+
+impl<'a> SendEmailRequest<'a> {
+    fn new(sender: &Mailbox, message: &'a EmailMessage) -> Self {
+        let join = |mailboxes: &[Mailbox]| {
+            mailboxes
+                .iter()
+                .map(Mailbox::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        Self {
+            from: sender.to_string(),
+            to: join(&message.to),
+            cc: join(&message.cc),
+            bcc: join(&message.bcc),
+            reply_to: message.reply_to.as_ref().map(Mailbox::to_string),
+            subject: &message.subject,
+            text_body: &message.text_body,
+            html_body: &message.html_body,
+            headers: message
+                .headers
+                .iter()
+                .map(|(name, value)| HeaderRequest { name, value })
+                .collect(),
+            tag: message.tag.as_deref(),
+            metadata: &message.metadata,
+            attachments: message
+                .attachments
+                .iter()
+                .map(AttachmentRequest::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SendEmailResponse {
+    #[serde(rename = "MessageID")]
+    message_id: Option<String>,
+    #[serde(default)]
+    error_code: i64,
+    #[serde(default)]
+    message: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct HeaderRequest<'a> {
+    name: &'a str,
+    value: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct AttachmentRequest<'a> {
+    name: &'a str,
+    /// Base64 encoded.
+    content: String,
+    content_type: &'a str,
+    #[serde(rename = "ContentID", skip_serializing_if = "Option::is_none")]
+    content_id: Option<String>,
+}
+
+impl<'a> From<&'a Attachment> for AttachmentRequest<'a> {
+    fn from(attachment: &'a Attachment) -> Self {
+        Self {
+            name: &attachment.name,
+            content: base64::encode(&attachment.content),
+            content_type: &attachment.content_type,
+            content_id: attachment
+                .content_id
+                .as_ref()
+                .map(|id| format!("cid:{id}")),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -100,6 +415,204 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn send_email_returns_the_message_id() {
+        let server = MockServer::start().await;
+        let answer = serde_json::json!({
+            "To": "ursula@example.com",
+            "SubmittedAt": "2026-10-19T16:09:19.5597788Z",
+            "MessageID": "b7bc2f4a-e38e-4336-af7d-e6c392c2f817",
+            "ErrorCode": 0,
+            "Message": "OK",
+        });
+        configure_server(
+            &server,
+            ResponseTemplate::new(200).set_body_json(answer),
+        )
+        .await;
+        let result = send_email(&server).await.unwrap();
+        assert_eq!(
+            result,
+            SendResult {
+                status: StatusCode::OK,
+                message_id: Some("b7bc2f4a-e38e-4336-af7d-e6c392c2f817".into()),
+                message: "OK".into(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn send_email_errors_carry_the_provider_message() {
+        let server = MockServer::start().await;
+        let answer = serde_json::json!({
+            "ErrorCode": 300,
+            "Message": "Invalid email request",
+        });
+        configure_server(
+            &server,
+            ResponseTemplate::new(422).set_body_json(answer),
+        )
+        .await;
+        let error = send_email(&server).await.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The email provider answered 422 Unprocessable Entity (error 300): \
+            Invalid email request"
+        );
+    }
+
+    #[tokio::test]
+    async fn attachments_are_sent_base64_encoded() {
+        use wiremock::{
+            matchers::{body_partial_json, path},
+            Mock,
+        };
+        let server = MockServer::start().await;
+        Mock::given(path("/email"))
+            .and(body_partial_json(serde_json::json!({
+                "Attachments": [
+                    {
+                        "Name": "logo.png",
+                        "Content": "YWFh",
+                        "ContentType": "image/png",
+                        "ContentID": "cid:logo.png",
+                    },
+                    {
+                        "Name": "logo.png",
+                        "Content": "YQ==",
+                        "ContentType": "image/png",
+                    },
+                ]
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let attachments =
+            [attachment(3, Some("logo.png")), attachment(1, None)];
+        let message = EmailMessage::new(email(), "Subject", "Text")
+            .html_body("<img src=\"cid:logo.png\">")
+            .attachments(attachments.to_vec());
+        let result = email_client(&server).send(&message).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn attachments_over_the_size_limit_are_not_sent() {
+        use wiremock::{matchers::any, Mock};
+        let server = MockServer::start().await;
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+        let attachments = [attachment(1000, None), attachment(25, None)];
+        let message = EmailMessage::new(email(), "Subject", "Text")
+            .attachments(attachments.to_vec());
+        let result = email_client(&server).send(&message).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn messages_are_mapped_to_postmark_fields() {
+        use wiremock::{matchers::path, Mock};
+        let server = MockServer::start().await;
+        Mock::given(path("/email"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let address =
+            |s: &str| SubscriberEmail::try_from(s.to_owned()).unwrap();
+        let message = EmailMessage::new(
+            Mailbox::named("Ursula Le Guin", address("ursula@example.com")),
+            "Subject",
+            "Text",
+        )
+        .to(address("le.guin@example.com"))
+        .cc(Mailbox::named(
+            "Editors, Inc.",
+            address("editors@example.com"),
+        ))
+        .bcc(address("archive@example.com"))
+        .bcc(address("audit@example.com"))
+        .reply_to(Mailbox::named("Acme Support", address("help@acme.com")))
+        .header("List-Unsubscribe", "<https://acme.com/unsubscribe>")
+        .header("X-Campaign", "spring")
+        .tag("newsletter")
+        .metadata("issue", "42");
+        let client = EmailClient::new(Config {
+            sender: address("news@acme.com"),
+            sender_name: Some("Acme News".into()),
+            ..config(&server)
+        });
+        client.send(&message).await.unwrap();
+
+        let request = &server.received_requests().await.unwrap()[0];
+        let body: serde_json::Value = request.body_json().unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "From": "Acme News <news@acme.com>",
+                "To": "Ursula Le Guin <ursula@example.com>, le.guin@example.com",
+                "Cc": "\"Editors, Inc.\" <editors@example.com>",
+                "Bcc": "archive@example.com, audit@example.com",
+                "ReplyTo": "Acme Support <help@acme.com>",
+                "Subject": "Subject",
+                "TextBody": "Text",
+                "Headers": [
+                    {
+                        "Name": "List-Unsubscribe",
+                        "Value": "<https://acme.com/unsubscribe>",
+                    },
+                    { "Name": "X-Campaign", "Value": "spring" },
+                ],
+                "Tag": "newsletter",
+                "Metadata": { "issue": "42" },
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn malformed_headers_are_not_sent() {
+        use wiremock::{matchers::any, Mock};
+        let server = MockServer::start().await;
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+        for (name, value) in [
+            ("X-Campaign", "spring\r\nBcc: everyone@example.com"),
+            ("X Campaign", "spring"),
+            ("", "spring"),
+        ] {
+            let message = EmailMessage::new(email(), "Subject", "Text")
+                .header(name, value);
+            let result = email_client(&server).send(&message).await;
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn display_names_are_quoted_when_needed() {
+        let email =
+            SubscriberEmail::try_from("a@example.com".to_owned()).unwrap();
+        let display =
+            |name: &str| Mailbox::named(name, email.clone()).to_string();
+        assert_eq!(display("Acme News"), "Acme News <a@example.com>");
+        assert_eq!(display(" "), "a@example.com");
+        assert_eq!(display("Le Guin, U."), r#""Le Guin, U." <a@example.com>"#);
+        assert_eq!(
+            display(r#"The "Best" \ News"#),
+            r#""The \"Best\" \\ News" <a@example.com>"#
+        );
+        assert_eq!(
+            display("Acme\r\nBcc: x@example.com"),
+            r#""AcmeBcc: x@example.com" <a@example.com>"#
+        );
+    }
+
     async fn configure_server(server: &MockServer, response: ResponseTemplate) {
         use reqwest::header::CONTENT_TYPE;
         use wiremock::{
@@ -117,29 +630,48 @@ mod tests {
             .await
     }
 
-    async fn send_email(server: &MockServer) -> anyhow::Result<()> {
-        use fake::{
-            faker::{
-                internet::en::SafeEmail,
-                lorem::en::{Paragraph, Sentence},
-            },
-            Fake, Faker,
-        };
-        let email =
-            || SubscriberEmail::try_from(SafeEmail().fake::<String>()).unwrap();
-        let subject = Sentence(1..2).fake::<String>();
-        let content = Paragraph(1..10).fake::<String>();
-        let config = Config {
+    fn email() -> SubscriberEmail {
+        use fake::{faker::internet::en::SafeEmail, Fake};
+        SubscriberEmail::try_from(SafeEmail().fake::<String>()).unwrap()
+    }
+
+    fn email_client(server: &MockServer) -> EmailClient {
+        EmailClient::new(config(server))
+    }
+
+    fn config(server: &MockServer) -> Config {
+        use fake::{Fake, Faker};
+        Config {
             timeout: Duration::from_millis(200),
             base_url: Url::parse(&server.uri()).unwrap(),
             sender: email(),
+            sender_name: None,
             authorization_token: Secret::new(Faker.fake()),
+            max_attachments_size: 1024,
+        }
+    }
+
+    async fn send_email(server: &MockServer) -> anyhow::Result<SendResult> {
+        use fake::{
+            faker::lorem::en::{Paragraph, Sentence},
+            Fake,
         };
-        EmailClient::new(config)
+        let subject = Sentence(1..2).fake::<String>();
+        let content = Paragraph(1..10).fake::<String>();
+        email_client(server)
             .send_email(&email(), &subject, &content, &content)
             .await
     }
 
+    fn attachment(size: usize, content_id: Option<&str>) -> Attachment {
+        Attachment {
+            name: "logo.png".into(),
+            content_type: "image/png".into(),
+            content: vec![b'a'; size],
+            content_id: content_id.map(String::from),
+        }
+    }
+
     fn expected_body() -> impl wiremock::Match + 'static {
         struct BodyMatcher;
         impl wiremock::Match for BodyMatcher {
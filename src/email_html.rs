@@ -0,0 +1,155 @@
+//! Prepares the HTML body of an issue for email clients: the CSS of
+//! `<style>` blocks is moved into `style` attributes, which is all most
+//! clients apply, then anything that could run code is stripped.
+
+use ammonia::Builder;
+use css_inline::CSSInliner;
+use std::{borrow::Cow, collections::HashSet};
+
+#[derive(Debug, thiserror::Error)]
+#[error("The HTML content could not be processed: {0}")]
+pub struct HtmlError(String);
+
+/// Attributes allowed on every tag, on top of ammonia's defaults, as
+/// layouts for email clients still rely on them.
+const PRESENTATION_ATTRIBUTES: [&str; 9] = [
+    "style",
+    "align",
+    "valign",
+    "bgcolor",
+    "width",
+    "height",
+    "border",
+    "cellpadding",
+    "cellspacing",
+];
+
+pub fn prepare_html(html: &str) -> Result<String, HtmlError> {
+    let inlined = CSSInliner::options()
+        .load_remote_stylesheets(false)
+        .keep_style_tags(false)
+        .keep_link_tags(false)
+        .build()
+        .inline(html)
+        .map_err(|e| HtmlError(e.to_string()))?;
+    let sanitized = sanitizer().clean(&inlined).to_string();
+    if sanitized.trim().is_empty() && !html.trim().is_empty() {
+        return Err(HtmlError(
+            "nothing is left once unsafe tags are removed".into(),
+        ));
+    }
+    Ok(sanitized)
+}
+
+fn sanitizer() -> Builder<'static> {
+    let mut builder = Builder::default();
+    builder
+        .add_generic_attributes(PRESENTATION_ATTRIBUTES)
+        .add_tags(["font"])
+        .add_tag_attributes("font", ["color", "face", "size"])
+        .clean_content_tags(HashSet::from(["script", "style", "title"]))
+        .link_rel(None)
+        // Inline images refer to the issue's assets by content id.
+        .add_url_schemes(["cid"])
+        .attribute_filter(|_, attribute, value| {
+            if attribute == "style" && is_dangerous_css(value) {
+                None
+            } else {
+                Some(Cow::Borrowed(value))
+            }
+        });
+    builder
+}
+
+/// Old clients run `expression()` and `javascript:` URLs found in CSS.
+fn is_dangerous_css(css: &str) -> bool {
+    let css = css.to_ascii_lowercase();
+    css.contains("expression(") || css.contains("javascript:")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::prepare_html;
+
+    #[test]
+    fn plain_content_is_left_as_is() {
+        let html = "<h1>Issue 1</h1>\n<p>Hello <em>there</em>.</p>\n";
+        assert_eq!(prepare_html(html).unwrap(), html);
+    }
+
+    #[test]
+    fn scripts_and_event_handlers_are_removed() {
+        let html = r#"<p onclick="steal()">Hi</p><script>steal()</script>
+            <a href="javascript:steal()" onmouseover="steal()">Link</a>
+            <iframe src="https://example.com"></iframe>"#;
+        let prepared = prepare_html(html).unwrap();
+        assert!(!prepared.contains("steal"), "{prepared}");
+        assert!(!prepared.contains("iframe"), "{prepared}");
+        assert!(prepared.contains("<p>Hi</p>"), "{prepared}");
+        assert!(prepared.contains("<a>Link</a>"), "{prepared}");
+    }
+
+    #[test]
+    fn style_blocks_are_inlined() {
+        let html = r#"<html>
+            <head>
+                <title>Issue 1</title>
+                <style>
+                    h1 { color: red; }
+                    .note { font-size: 12px; }
+                </style>
+            </head>
+            <body>
+                <h1>News</h1>
+                <p class="note" style="margin: 0">Small print</p>
+            </body>
+            </html>"#;
+        let prepared = prepare_html(html).unwrap();
+        assert!(!prepared.contains("<style"), "{prepared}");
+        assert!(!prepared.contains("Issue 1"), "{prepared}");
+        assert!(
+            prepared.contains(r#"<h1 style="color: red;">News</h1>"#),
+            "{prepared}"
+        );
+        assert!(
+            prepared.contains(r#"style="font-size: 12px;margin: 0""#),
+            "{prepared}"
+        );
+    }
+
+    #[test]
+    fn email_layout_attributes_are_kept() {
+        let html = r##"<table width="600" cellpadding="0" align="center"><tbody><tr><td bgcolor="#ffffff" valign="top">Cell</td></tr></tbody></table>"##;
+        assert_eq!(prepare_html(html).unwrap(), html);
+    }
+
+    #[test]
+    fn dangerous_css_is_dropped() {
+        let html = r#"<p style="width: expression(alert(1))">A</p><p style="background: url('javascript:alert(1)')">B</p>"#;
+        assert_eq!(prepare_html(html).unwrap(), "<p>A</p><p>B</p>");
+    }
+
+    #[test]
+    fn inline_images_are_kept() {
+        let html = r#"<img src="cid:logo.png" alt="Logo">"#;
+        assert_eq!(prepare_html(html).unwrap(), html);
+    }
+
+    #[test]
+    fn merge_fields_are_preserved() {
+        let html =
+            r#"<p>Hi {{ name }}</p><a href="{{ unsubscribe_url }}">Leave</a>"#;
+        assert_eq!(prepare_html(html).unwrap(), html);
+    }
+
+    #[test]
+    fn content_with_nothing_safe_is_rejected() {
+        let html = "<script>alert(1)</script><iframe></iframe>";
+        assert_eq!(
+            prepare_html(html).unwrap_err().to_string(),
+            "The HTML content could not be processed: nothing is left once \
+            unsafe tags are removed"
+        );
+        assert_eq!(prepare_html("").unwrap(), "");
+    }
+}
@@ -0,0 +1,455 @@
+use crate::{configuration::EmailTemplatesConfig, locales::Locales};
+use anyhow::Context;
+use htmlescape::encode_minimal;
+use minijinja::{
+    escape_formatter, path_loader, value::Kwargs, AutoEscape, Environment,
+    Error, ErrorKind, State, Value,
+};
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+/// The placeholders newsletter content can use, e.g. `{{ name }}`.
+pub const MERGE_FIELDS: [&str; 3] = ["name", "email", "unsubscribe_url"];
+
+/// What the merge fields stand for when sending an issue to a subscriber.
+#[derive(Clone, Debug, Serialize)]
+pub struct MergeFields<'a> {
+    pub name: &'a str,
+    pub email: &'a str,
+    pub unsubscribe_url: &'a str,
+}
+
+impl MergeFields<'_> {
+    fn get(&self, field: &str) -> Option<&str> {
+        match field {
+            "name" => Some(self.name),
+            "email" => Some(self.email),
+            "unsubscribe_url" => Some(self.unsubscribe_url),
+            _ => None,
+        }
+    }
+}
+
+/// The emails sent by the application, each rendered from a subject line
+/// and a `<name>.txt` and `<name>.html` template.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmailTemplate {
+    /// Asks a new subscriber to confirm their address.
+    Confirmation,
+    /// Greets a subscriber who joined a single opt-in list.
+    Welcome,
+    /// Asks a subscriber to confirm their new address.
+    EmailChange,
+    /// Wraps the content of a newsletter issue.
+    Newsletter,
+}
+
+impl EmailTemplate {
+    pub const ALL: [Self; 4] = [
+        Self::Confirmation,
+        Self::Welcome,
+        Self::EmailChange,
+        Self::Newsletter,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Confirmation => "confirmation",
+            Self::Welcome => "welcome",
+            Self::EmailChange => "email_change",
+            Self::Newsletter => "newsletter",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RenderedEmail {
+    pub subject: String,
+    pub text_body: String,
+    pub html_body: String,
+}
+
+/// Values are escaped in HTML bodies, pipe trusted markup through `safe`.
+///
+/// Templates translate text with `t("message-id", arg=value)`, in the
+/// `locale` of the context or the default locale without one.
+#[derive(Clone, Debug)]
+pub struct EmailTemplates {
+    env: Environment<'static>,
+}
+
+impl EmailTemplates {
+    /// Fails if any template is missing or doesn't compile, so that a broken
+    /// template is caught at startup rather than when sending.
+    pub fn load(
+        config: EmailTemplatesConfig,
+        locales: &Locales,
+    ) -> anyhow::Result<Self> {
+        let mut env = Environment::new();
+        env.set_loader(path_loader(&config.directory));
+        let locales = locales.clone();
+        env.add_function(
+            "t",
+            move |state: &State, id: &str, kwargs: Kwargs| {
+                let locale = state.lookup("locale").unwrap_or_default();
+                let args = kwargs
+                    .args()
+                    .map(|k| Ok((k, kwargs.get::<Value>(k)?.to_string())))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                let args: Vec<_> =
+                    args.iter().map(|(k, v)| (*k, v.as_str())).collect();
+                Ok(locales.message(
+                    locale.as_str().unwrap_or_default(),
+                    id,
+                    &args,
+                ))
+            },
+        );
+        // The default escaping also encodes slashes, mangling links.
+        env.set_formatter(|out, state, value| match value.as_str() {
+            Some(s)
+                if state.auto_escape() == AutoEscape::Html
+                    && !value.is_safe() =>
+            {
+                out.write_str(&encode_minimal(s)).map_err(|e| {
+                    Error::new(ErrorKind::WriteFailure, e.to_string())
+                })
+            }
+            _ => escape_formatter(out, state, value),
+        });
+        let subjects = &config.subjects;
+        for template in EmailTemplate::ALL {
+            let subject = match template {
+                EmailTemplate::Confirmation => &subjects.confirmation,
+                EmailTemplate::Welcome => &subjects.welcome,
+                EmailTemplate::EmailChange => &subjects.email_change,
+                EmailTemplate::Newsletter => &subjects.newsletter,
+            };
+            env.add_template_owned(
+                format!("{}.subject", template.name()),
+                subject.clone(),
+            )
+            .with_context(|| {
+                format!("Invalid subject for the {} email", template.name())
+            })?;
+            for extension in ["txt", "html"] {
+                let name = format!("{}.{extension}", template.name());
+                env.get_template(&name).with_context(|| {
+                    format!(
+                        "Failed to load {name} from {}",
+                        config.directory.display()
+                    )
+                })?;
+            }
+        }
+        Ok(Self { env })
+    }
+
+    pub fn render(
+        &self,
+        template: EmailTemplate,
+        context: impl Serialize,
+    ) -> anyhow::Result<RenderedEmail> {
+        let render = |extension: &str| {
+            let name = format!("{}.{extension}", template.name());
+            self.env
+                .get_template(&name)
+                .and_then(|t| t.render(&context))
+                .with_context(|| format!("Failed to render {name}"))
+        };
+        Ok(RenderedEmail {
+            subject: render("subject")?,
+            text_body: render("txt")?,
+            html_body: render("html")?,
+        })
+    }
+}
+
+/// Checks that both bodies of an issue compile and only use merge fields.
+pub fn validate_merge_fields(
+    text_content: &str,
+    html_content: &str,
+) -> Result<(), String> {
+    check_placeholders(text_content)
+        .map_err(|e| format!("The text content is invalid: {e}"))?;
+    check_placeholders(html_content)
+        .map_err(|e| format!("The HTML content is invalid: {e}"))
+}
+
+fn check_placeholders(content: &str) -> Result<(), String> {
+    let unknown: BTreeSet<_> = parse_content(content)?
+        .into_iter()
+        .filter_map(|part| match part {
+            ContentPart::Field(field) if !MERGE_FIELDS.contains(&field) => {
+                Some(field)
+            }
+            _ => None,
+        })
+        .collect();
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    let unknown: Vec<_> = unknown.into_iter().collect();
+    Err(format!(
+        "unknown placeholders {}, use {}",
+        unknown.join(", "),
+        MERGE_FIELDS.join(", "),
+    ))
+}
+
+/// Fills in the merge fields of newsletter content, escaping them in HTML
+/// content.
+///
+/// Issue content isn't a template: only bare `{{ field }}` placeholders are
+/// replaced, so it can't include the application's templates or mark values
+/// as safe.
+pub fn render_content(
+    content: &str,
+    html: bool,
+    fields: &MergeFields<'_>,
+) -> anyhow::Result<String> {
+    let mut rendered = String::with_capacity(content.len());
+    for part in parse_content(content).map_err(anyhow::Error::msg)? {
+        match part {
+            ContentPart::Text(text) => rendered.push_str(text),
+            ContentPart::Field(field) => {
+                let value = fields
+                    .get(field)
+                    .with_context(|| format!("Unknown merge field {field}"))?;
+                if html {
+                    rendered.push_str(&encode_minimal(value));
+                } else {
+                    rendered.push_str(value);
+                }
+            }
+        }
+    }
+    Ok(rendered)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ContentPart<'a> {
+    Text(&'a str),
+    Field(&'a str),
+}
+
+fn parse_content(content: &str) -> Result<Vec<ContentPart<'_>>, String> {
+    let mut parts = Vec::new();
+    let mut rest = content;
+    while let Some(start) = rest.find('{') {
+        let (text, tail) = rest.split_at(start);
+        if tail.starts_with("{%") || tail.starts_with("{#") {
+            return Err(
+                "syntax error: only {{ field }} placeholders are supported"
+                    .into(),
+            );
+        }
+        if !tail.starts_with("{{") {
+            parts.push(ContentPart::Text(&rest[..start + 1]));
+            rest = &tail[1..];
+            continue;
+        }
+        parts.push(ContentPart::Text(text));
+        let end = tail
+            .find("}}")
+            .ok_or("syntax error: unclosed placeholder")?;
+        let field = tail[2..end].trim();
+        let is_bare = !field.is_empty()
+            && field.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !is_bare {
+            return Err(format!(
+                "syntax error: {} is not a bare placeholder",
+                &tail[..end + 2]
+            ));
+        }
+        parts.push(ContentPart::Field(field));
+        rest = &tail[end + 2..];
+    }
+    parts.push(ContentPart::Text(rest));
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        render_content, validate_merge_fields, EmailTemplate, EmailTemplates,
+        MergeFields,
+    };
+    use crate::{
+        configuration::{EmailSubjects, EmailTemplatesConfig, LocalesConfig},
+        locales::Locales,
+    };
+    use serde_json::json;
+
+    fn locales() -> Locales {
+        Locales::load(LocalesConfig {
+            directory: "locales".into(),
+            default: "en".into(),
+        })
+        .unwrap()
+    }
+
+    fn config() -> EmailTemplatesConfig {
+        EmailTemplatesConfig {
+            directory: "templates/emails".into(),
+            subjects: EmailSubjects {
+                confirmation: "Confirm".into(),
+                welcome: "Welcome, {{ name }}".into(),
+                email_change: "Confirm".into(),
+                newsletter: "{{ title }}".into(),
+            },
+        }
+    }
+
+    #[test]
+    fn subjects_are_rendered_with_the_context() {
+        let email = EmailTemplates::load(config(), &locales())
+            .unwrap()
+            .render(
+                EmailTemplate::Welcome,
+                json!({"name": "Ursula <3", "preferences_link": "x"}),
+            )
+            .unwrap();
+        assert_eq!(email.subject, "Welcome, Ursula <3");
+    }
+
+    #[test]
+    fn values_are_escaped_in_html_bodies_only() {
+        let link = "https://example.com/?a=1&b=2";
+        let email = EmailTemplates::load(config(), &locales())
+            .unwrap()
+            .render(
+                EmailTemplate::Confirmation,
+                json!({"name": "Ursula", "confirmation_link": link}),
+            )
+            .unwrap();
+        assert!(email.text_body.contains(link));
+        assert!(email.html_body.contains("https://example.com/?a=1&amp;b=2"));
+    }
+
+    #[test]
+    fn the_newsletter_wraps_the_issue_content() {
+        let email = EmailTemplates::load(config(), &locales())
+            .unwrap()
+            .render(
+                EmailTemplate::Newsletter,
+                json!({
+                    "title": "Issue #1",
+                    "text_content": "Plain",
+                    "html_content": "<p>Rich</p>",
+                }),
+            )
+            .unwrap();
+        assert_eq!(email.subject, "Issue #1");
+        assert!(email.text_body.contains("Plain"));
+        assert!(email.html_body.contains("<p>Rich</p>"));
+    }
+
+    #[test]
+    fn a_missing_directory_is_rejected() {
+        let mut config = config();
+        config.directory = "no/such/directory".into();
+        assert!(EmailTemplates::load(config, &locales()).is_err());
+    }
+
+    #[test]
+    fn an_invalid_subject_is_rejected() {
+        let mut config = config();
+        config.subjects.newsletter = "{{ title".into();
+        assert!(EmailTemplates::load(config, &locales()).is_err());
+    }
+
+    #[test]
+    fn text_is_translated_into_the_locale_of_the_context() {
+        let mut config = config();
+        config.subjects.confirmation = "{{ t('confirmation-subject') }}".into();
+        let templates = EmailTemplates::load(config, &locales()).unwrap();
+        let render = |context| {
+            templates
+                .render(EmailTemplate::Confirmation, context)
+                .unwrap()
+        };
+        let email = render(json!({
+            "name": "Ursula",
+            "confirmation_link": "https://example.com",
+            "locale": "fr",
+        }));
+        assert_eq!(email.subject, "Veuillez confirmer votre abonnement");
+        assert!(email
+            .text_body
+            .starts_with("Bienvenue dans la newsletter, Ursula."));
+        let email = render(json!({
+            "name": "Ursula <3",
+            "confirmation_link": "https://example.com",
+        }));
+        assert_eq!(email.subject, "Please confirm your subscription");
+        assert!(email
+            .html_body
+            .contains("Welcome to the newsletter, Ursula &lt;3."));
+    }
+
+    fn merge_fields() -> MergeFields<'static> {
+        MergeFields {
+            name: "Ursula <3",
+            email: "ursula@example.com",
+            unsubscribe_url: "https://example.com/unsubscribe?t=a&b",
+        }
+    }
+
+    #[test]
+    fn merge_fields_are_filled_in() {
+        let content = "Hi {{ name }} ({{ email }}), {{ unsubscribe_url }}";
+        assert_eq!(
+            render_content(content, false, &merge_fields()).unwrap(),
+            "Hi Ursula <3 (ursula@example.com), \
+            https://example.com/unsubscribe?t=a&b"
+        );
+        assert_eq!(
+            render_content(content, true, &merge_fields()).unwrap(),
+            "Hi Ursula &lt;3 (ursula@example.com), \
+            https://example.com/unsubscribe?t=a&amp;b"
+        );
+    }
+
+    #[test]
+    fn content_without_placeholders_is_left_as_is() {
+        let content = "<p>Prices in {curly} braces & <b>bold</b></p>";
+        assert_eq!(
+            render_content(content, true, &merge_fields()).unwrap(),
+            content
+        );
+        assert_eq!(validate_merge_fields(content, content), Ok(()));
+    }
+
+    #[test]
+    fn unknown_placeholders_are_rejected() {
+        assert_eq!(
+            validate_merge_fields("{{ name }}", "{{ surname }} {{ age }}"),
+            Err("The HTML content is invalid: unknown placeholders age, \
+                surname, use name, email, unsubscribe_url"
+                .into())
+        );
+    }
+
+    #[test]
+    fn malformed_placeholders_are_rejected() {
+        let error = validate_merge_fields("Hi {{ name", "").unwrap_err();
+        assert!(error.starts_with("The text content is invalid: syntax error"));
+    }
+
+    #[test]
+    fn only_bare_placeholders_are_accepted() {
+        for content in [
+            r#"{% include "confirmation.html" %}"#,
+            r#"{{ "<script>"|safe }}"#,
+            "{{ name|upper }}",
+            "{# note #}",
+        ] {
+            let error = validate_merge_fields("", content).unwrap_err();
+            assert!(
+                error.starts_with("The HTML content is invalid: syntax error"),
+                "{content}: {error}"
+            );
+        }
+    }
+}
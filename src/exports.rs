@@ -0,0 +1,227 @@
+use crate::DbPool;
+use actix_web::web::Bytes;
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use futures_util::{stream, Stream, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+type Chunk = Result<Bytes, std::io::Error>;
+
+/// Number of encoded rows buffered ahead of a slow client.
+const CHANNEL_CAPACITY: usize = 64;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Jsonl,
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Jsonl => "application/x-ndjson",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Jsonl => "jsonl",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct SubscriptionRecord {
+    id: Uuid,
+    email: String,
+    name: String,
+    status: String,
+    subscribed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+struct NewsletterIssueRecord {
+    newsletter_issue_id: Uuid,
+    title: String,
+    text_content: String,
+    html_content: String,
+    markdown_content: Option<String>,
+    published_at: String,
+}
+
+/// Encodes records one at a time, writing the CSV header before the first.
+struct Encoder {
+    format: ExportFormat,
+    first: bool,
+}
+
+impl Encoder {
+    fn new(format: ExportFormat) -> Self {
+        Self {
+            format,
+            first: true,
+        }
+    }
+
+    fn encode<T: Serialize>(&mut self, record: &T) -> anyhow::Result<Bytes> {
+        let mut buffer = match self.format {
+            ExportFormat::Csv => {
+                let mut writer = csv::WriterBuilder::new()
+                    .has_headers(self.first)
+                    .from_writer(Vec::new());
+                writer.serialize(record)?;
+                writer.into_inner().context("Failed to flush a CSV row")?
+            }
+            ExportFormat::Jsonl => serde_json::to_vec(record)?,
+        };
+        if self.format == ExportFormat::Jsonl {
+            buffer.push(b'\n');
+        }
+        self.first = false;
+        Ok(Bytes::from(buffer))
+    }
+}
+
+/// Streams every row of `subscriptions`, oldest first.
+pub fn export_subscriptions(
+    format: ExportFormat,
+    pool: DbPool,
+) -> impl Stream<Item = Chunk> {
+    spawn_export("subscriptions", move |tx| async move {
+        let mut encoder = Encoder::new(format);
+        let mut rows = sqlx::query_as!(
+            SubscriptionRecord,
+            r#"
+            select id, email, name, status, subscribed_at
+            from subscriptions
+            order by subscribed_at, id;
+            "#,
+        )
+        .fetch(&pool);
+        while let Some(row) = rows
+            .try_next()
+            .await
+            .context("Failed to fetch a subscription")?
+        {
+            if tx.send(Ok(encoder.encode(&row)?)).await.is_err() {
+                break;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Streams every row of `newsletter_issues`, oldest first.
+pub fn export_newsletter_issues(
+    format: ExportFormat,
+    pool: DbPool,
+) -> impl Stream<Item = Chunk> {
+    spawn_export("newsletter_issues", move |tx| async move {
+        let mut encoder = Encoder::new(format);
+        let mut rows = sqlx::query_as!(
+            NewsletterIssueRecord,
+            r#"
+            select
+                newsletter_issue_id,
+                title,
+                text_content,
+                html_content,
+                markdown_content,
+                published_at
+            from newsletter_issues
+            order by published_at, newsletter_issue_id;
+            "#,
+        )
+        .fetch(&pool);
+        while let Some(row) = rows
+            .try_next()
+            .await
+            .context("Failed to fetch a newsletter issue")?
+        {
+            if tx.send(Ok(encoder.encode(&row)?)).await.is_err() {
+                break;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Runs `produce` in the background, handing its chunks to the returned
+/// stream through a bounded channel. Producing stops as soon as the
+/// client goes away; a failure ends the stream with an error.
+fn spawn_export<F, Fut>(
+    table: &'static str,
+    produce: F,
+) -> impl Stream<Item = Chunk>
+where
+    F: FnOnce(mpsc::Sender<Chunk>) -> Fut,
+    Fut: std::future::Future<Output = anyhow::Result<()>> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+    let producer = produce(tx.clone());
+    tokio::spawn(async move {
+        if let Err(e) = producer.await {
+            tracing::error!(
+                error.cause_chain = ?e,
+                error.message = %e,
+                table,
+                "Failed to export a table",
+            );
+            let error =
+                std::io::Error::other(format!("Failed to export {table}"));
+            let _ = tx.send(Err(error)).await;
+        }
+    });
+    stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Encoder, ExportFormat};
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Record {
+        name: &'static str,
+        count: u32,
+    }
+
+    #[test]
+    fn csv_header_is_written_once() {
+        let mut encoder = Encoder::new(ExportFormat::Csv);
+        let first = encoder
+            .encode(&Record {
+                name: "a",
+                count: 1,
+            })
+            .unwrap();
+        let second = encoder
+            .encode(&Record {
+                name: "b,c",
+                count: 2,
+            })
+            .unwrap();
+        assert_eq!(first, "name,count\na,1\n");
+        assert_eq!(second, "\"b,c\",2\n");
+    }
+
+    #[test]
+    fn json_lines_are_newline_terminated() {
+        let mut encoder = Encoder::new(ExportFormat::Jsonl);
+        let line = encoder
+            .encode(&Record {
+                name: "a",
+                count: 1,
+            })
+            .unwrap();
+        assert_eq!(line, "{\"name\":\"a\",\"count\":1}\n");
+    }
+}
@@ -0,0 +1,292 @@
+//! Right-of-access and right-to-erasure requests, looked up by email.
+use crate::{subscribers::EmailChange, DbPool};
+use anyhow::Context;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+/// Everything stored about the owner of an email address.
+#[derive(Debug, Serialize)]
+pub struct SubjectData {
+    pub email: String,
+    pub subscription: Option<SubscriptionData>,
+    pub lists: Vec<String>,
+    pub tags: Vec<String>,
+    pub email_changes: Vec<EmailChange>,
+    pub subscription_tokens: Vec<String>,
+    pub pending_deliveries: Vec<PendingDelivery>,
+    pub tracking_events: Vec<TrackingEvent>,
+    pub deliveries: Vec<Delivery>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SubscriptionData {
+    pub id: Uuid,
+    pub email: String,
+    pub name: String,
+    pub status: String,
+    pub subscribed_at: DateTime<Utc>,
+    pub plain_text_only: bool,
+    pub paused_until: Option<NaiveDate>,
+    pub locale: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PendingDelivery {
+    pub newsletter_issue_id: Uuid,
+    pub title: String,
+    pub subscriber_email: String,
+}
+
+/// An open or a click of a tracked issue.
+#[derive(Debug, Serialize)]
+pub struct TrackingEvent {
+    pub newsletter_issue_id: Uuid,
+    /// The link clicked, `None` for an open.
+    pub url: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// An issue sent to the subscriber and what became of it.
+#[derive(Debug, Serialize)]
+pub struct Delivery {
+    pub newsletter_issue_id: Uuid,
+    pub title: String,
+    pub status: String,
+    pub message_id: Option<String>,
+    pub detail: Option<String>,
+    pub sent_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SubjectData {
+    pub fn is_empty(&self) -> bool {
+        self.subscription.is_none()
+            && self.pending_deliveries.is_empty()
+            && self.email_changes.is_empty()
+    }
+}
+
+/// Emails are matched case-insensitively. Changes of address are matched
+/// by the addresses they moved from and to as well, so that a former
+/// address finds them.
+#[tracing::instrument(name = "Exporting data subject records", skip(pool))]
+pub async fn export_subject_data(
+    email: &str,
+    pool: &DbPool,
+) -> anyhow::Result<SubjectData> {
+    let subscription = sqlx::query_as!(
+        SubscriptionData,
+        r#"
+        select
+            id,
+            email,
+            name,
+            status,
+            subscribed_at,
+            plain_text_only,
+            paused_until,
+            locale
+        from subscriptions
+        where lower(email) = lower($1);
+        "#,
+        email,
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to query for a subscription")?;
+    let lists = sqlx::query!(
+        r#"
+        select l.slug
+        from list_memberships m
+        join lists l using (list_id)
+        join subscriptions s on s.id = m.subscriber_id
+        where lower(s.email) = lower($1)
+        order by l.slug;
+        "#,
+        email,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query for list memberships")?
+    .into_iter()
+    .map(|r| r.slug)
+    .collect();
+    let tags = sqlx::query!(
+        r#"
+        select t.tag
+        from subscriber_tags t
+        join subscriptions s on s.id = t.subscriber_id
+        where lower(s.email) = lower($1)
+        order by t.tag;
+        "#,
+        email,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query for tags")?
+    .into_iter()
+    .map(|r| r.tag)
+    .collect();
+    let email_changes = sqlx::query_as!(
+        EmailChange,
+        r#"
+        select c.old_email, c.new_email, c.requested_at, c.confirmed_at
+        from email_changes c
+        join subscriptions s on s.id = c.subscriber_id
+        where lower(s.email) = lower($1)
+        or lower(c.old_email) = lower($1)
+        or lower(c.new_email) = lower($1)
+        order by c.requested_at;
+        "#,
+        email,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query for email changes")?;
+    let subscription_tokens = sqlx::query!(
+        r#"
+        select t.subscription_token
+        from subscription_tokens t
+        join subscriptions s on s.id = t.subscriber_id
+        where lower(s.email) = lower($1)
+        order by t.subscription_token;
+        "#,
+        email,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query for subscription tokens")?
+    .into_iter()
+    .map(|r| r.subscription_token)
+    .collect();
+    let pending_deliveries = sqlx::query_as!(
+        PendingDelivery,
+        r#"
+        select q.newsletter_issue_id, i.title, q.subscriber_email
+        from issue_delivery_queue q
+        join newsletter_issues i using (newsletter_issue_id)
+        where lower(q.subscriber_email) = lower($1)
+        order by i.published_at;
+        "#,
+        email,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query for pending deliveries")?;
+    let tracking_events = sqlx::query_as!(
+        TrackingEvent,
+        r#"
+        select t.newsletter_issue_id, t.url, e.occurred_at
+        from tracking_events e
+        join tracking_tokens t using (token)
+        join subscriptions s on s.id = t.subscriber_id
+        where lower(s.email) = lower($1)
+        order by e.occurred_at;
+        "#,
+        email,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query for opens and clicks")?;
+    let deliveries = sqlx::query_as!(
+        Delivery,
+        r#"
+        select
+            d.newsletter_issue_id,
+            i.title,
+            d.status,
+            d.message_id,
+            d.detail,
+            d.sent_at,
+            d.updated_at
+        from deliveries d
+        join newsletter_issues i using (newsletter_issue_id)
+        join subscriptions s on s.id = d.subscriber_id
+        where lower(s.email) = lower($1)
+        order by d.sent_at;
+        "#,
+        email,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query for deliveries")?;
+    Ok(SubjectData {
+        email: email.to_owned(),
+        subscription,
+        lists,
+        tags,
+        email_changes,
+        subscription_tokens,
+        pending_deliveries,
+        tracking_events,
+        deliveries,
+    })
+}
+
+/// Removes every record tied to the email in a single transaction.
+/// List memberships, tags, email changes, deliveries, opens and clicks go
+/// along with the subscription. Changes of address from or to the email
+/// are deleted even if the subscriber has moved on to another address.
+/// Returns `false` if there was nothing to erase.
+#[tracing::instrument(name = "Erasing data subject records", skip(pool))]
+pub async fn erase_subject_data(
+    email: &str,
+    pool: &DbPool,
+) -> anyhow::Result<bool> {
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire connection from the pool")?;
+    sqlx::query!(
+        r#"
+        delete from subscription_tokens
+        where subscriber_id in (
+            select id from subscriptions where lower(email) = lower($1)
+        );
+        "#,
+        email,
+    )
+    .execute(&mut transaction)
+    .await
+    .context("Failed to delete subscription tokens")?;
+    let deliveries = sqlx::query!(
+        r#"
+        delete from issue_delivery_queue
+        where lower(subscriber_email) = lower($1);
+        "#,
+        email,
+    )
+    .execute(&mut transaction)
+    .await
+    .context("Failed to delete pending deliveries")?
+    .rows_affected();
+    let email_changes = sqlx::query!(
+        r#"
+        delete from email_changes
+        where lower(old_email) = lower($1)
+        or lower(new_email) = lower($1);
+        "#,
+        email,
+    )
+    .execute(&mut transaction)
+    .await
+    .context("Failed to delete email changes")?
+    .rows_affected();
+    let subscriptions = sqlx::query!(
+        r#"
+        delete from subscriptions
+        where lower(email) = lower($1);
+        "#,
+        email,
+    )
+    .execute(&mut transaction)
+    .await
+    .context("Failed to delete the subscription")?
+    .rows_affected();
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit transaction")?;
+    Ok(deliveries + email_changes + subscriptions > 0)
+}
@@ -18,6 +18,7 @@ impl PgHasArrayType for HeaderPair {
     }
 }
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
 pub enum NextAction {
     StartProcessing(Transaction<'static, Database>),
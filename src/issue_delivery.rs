@@ -1,6 +1,18 @@
 use std::time::Duration;
 
-use crate::{domain::SubscriberEmail, Config, Database, DbPool, EmailClient};
+use crate::{
+    assets::issue_attachments,
+    deliveries::record_delivery,
+    domain::SubscriberEmail,
+    email_client::EmailMessage,
+    email_templates::{render_content, MergeFields, RenderedEmail},
+    routes::{unsubscribe_token, unsubscribe_url},
+    tracking::track_html,
+    Config, Database, DbPool, EmailClient, EmailTemplate, EmailTemplates,
+    Locales,
+};
+use anyhow::Context;
+use minijinja::context;
 use sqlx::Transaction;
 use tracing::{field::display, Span};
 use uuid::Uuid;
@@ -8,15 +20,20 @@ use uuid::Uuid;
 pub async fn run_worker(config: Config) -> anyhow::Result<()> {
     let pool = DbPool::connect_lazy_with(config.database.with_db());
     let email_client = EmailClient::new(config.email_client);
-    worker_loop(&pool, &email_client).await
+    let locales = Locales::load(config.locales)?;
+    let templates = EmailTemplates::load(config.email_templates, &locales)?;
+    let base_url = config.application.base_url;
+    worker_loop(&pool, &email_client, &templates, &base_url).await
 }
 
 async fn worker_loop(
     pool: &DbPool,
     email_client: &EmailClient,
+    templates: &EmailTemplates,
+    base_url: &str,
 ) -> anyhow::Result<()> {
     loop {
-        match try_execute_task(pool, email_client).await {
+        match try_execute_task(pool, email_client, templates, base_url).await {
             Ok(ExecutionOutcome::Completed) => {}
             Ok(ExecutionOutcome::EmptyQueue) => {
                 tokio::time::sleep(Duration::from_secs(10)).await
@@ -38,15 +55,17 @@ pub enum ExecutionOutcome {
 pub async fn try_execute_task(
     pool: &DbPool,
     email_client: &EmailClient,
+    templates: &EmailTemplates,
+    base_url: &str,
 ) -> anyhow::Result<ExecutionOutcome> {
     let task = dequeue_task(pool).await?;
     if task.is_none() {
         return Ok(ExecutionOutcome::EmptyQueue);
     }
-    let (transaction, issue_id, email) = task.unwrap();
+    let (mut transaction, issue_id, email) = task.unwrap();
     Span::current()
-        .record("newsletter_issue_id", &display(issue_id))
-        .record("subscriber_email", &display(&email));
+        .record("newsletter_issue_id", display(issue_id))
+        .record("subscriber_email", display(&email));
     let email = match SubscriberEmail::try_from(email) {
         Ok(email) => email,
         Err(e) => {
@@ -59,15 +78,62 @@ pub async fn try_execute_task(
             return Ok(ExecutionOutcome::Completed);
         }
     };
-    let NewsletterIssue {
-        title,
-        text_content,
-        html_content,
-    } = get_issue(&issue_id, pool).await?;
-    if let Err(e) = email_client
-        .send_email(&email, &title, &text_content, &html_content)
-        .await
-    {
+    let issue = get_issue(&issue_id, pool).await?;
+    let recipient = match get_recipient(&email, pool).await? {
+        Some(recipient) => recipient,
+        None => {
+            tracing::error!("Skipping a subscriber who no longer exists");
+            delete_task(&issue_id, email.as_ref(), transaction).await?;
+            return Ok(ExecutionOutcome::Completed);
+        }
+    };
+    let unsubscribe_url = format!(
+        "{base_url}{}",
+        unsubscribe_url(&unsubscribe_token(&recipient.id, pool).await?)
+    );
+    let fields = MergeFields {
+        name: &recipient.name,
+        email: email.as_ref(),
+        unsubscribe_url: &unsubscribe_url,
+    };
+    let mut attachments =
+        issue_attachments(&issue_id, &issue.html_content, pool).await?;
+    if recipient.plain_text_only {
+        // Inline images only make sense next to the HTML body.
+        attachments.retain(|a| a.content_id.is_none());
+    }
+    let tracking = issue.tracking;
+    let sent = match render_issue(templates, issue, &fields) {
+        Ok(rendered) => {
+            let html_body = if recipient.plain_text_only {
+                String::new()
+            } else if tracking {
+                track_html(
+                    &rendered.html_body,
+                    &issue_id,
+                    &recipient.id,
+                    base_url,
+                    &mut transaction,
+                )
+                .await?
+            } else {
+                rendered.html_body
+            };
+            let message = EmailMessage::new(
+                email.clone(),
+                rendered.subject,
+                rendered.text_body,
+            )
+            .html_body(html_body)
+            .header("List-Unsubscribe", format!("<{unsubscribe_url}>"))
+            .tag("newsletter")
+            .metadata("newsletter_issue_id", issue_id.to_string())
+            .attachments(attachments);
+            email_client.send(&message).await
+        }
+        Err(e) => Err(e),
+    };
+    if let Err(e) = &sent {
         tracing::error!(
             error.cause_chain = ?e,
             error.message = %e,
@@ -75,6 +141,7 @@ pub async fn try_execute_task(
              confirmed subscriber. Skipping",
         );
     }
+    record_delivery(&issue_id, &recipient.id, &sent, &mut transaction).await?;
     delete_task(&issue_id, email.as_ref(), transaction).await?;
     Ok(ExecutionOutcome::Completed)
 }
@@ -84,6 +151,7 @@ struct NewsletterIssue {
     title: String,
     text_content: String,
     html_content: String,
+    tracking: bool,
 }
 
 async fn get_issue(
@@ -93,7 +161,7 @@ async fn get_issue(
     sqlx::query_as!(
         NewsletterIssue,
         r#"
-        select title, text_content, html_content
+        select title, text_content, html_content, tracking
         from newsletter_issues
         where newsletter_issue_id = $1;
         "#,
@@ -103,6 +171,45 @@ async fn get_issue(
     .await
 }
 
+/// Fills in the recipient's merge fields and wraps the issue in the
+/// newsletter template.
+fn render_issue(
+    templates: &EmailTemplates,
+    issue: NewsletterIssue,
+    fields: &MergeFields<'_>,
+) -> anyhow::Result<RenderedEmail> {
+    let text_content = render_content(&issue.text_content, false, fields)?;
+    let html_content = render_content(&issue.html_content, true, fields)?;
+    templates.render(
+        EmailTemplate::Newsletter,
+        context! { title => issue.title, text_content, html_content },
+    )
+}
+
+struct Recipient {
+    id: Uuid,
+    name: String,
+    plain_text_only: bool,
+}
+
+async fn get_recipient(
+    email: &SubscriberEmail,
+    pool: &DbPool,
+) -> anyhow::Result<Option<Recipient>> {
+    sqlx::query_as!(
+        Recipient,
+        r#"
+        select id, name, plain_text_only
+        from subscriptions
+        where email = $1;
+        "#,
+        email.as_ref(),
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to query for the recipient")
+}
+
 async fn dequeue_task(
     pool: &DbPool,
 ) -> anyhow::Result<Option<(Transaction<'static, Database>, Uuid, String)>> {
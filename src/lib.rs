@@ -1,17 +1,33 @@
+mod assets;
 mod auth;
 pub mod configuration;
+mod deliveries;
 mod domain;
 mod email_client;
+mod email_html;
+mod email_templates;
+mod exports;
+pub mod gdpr;
 mod idempotency;
 pub mod issue_delivery;
+mod lists;
+mod locales;
+mod markdown;
 mod routes;
+mod segments;
 mod server;
 mod session;
+mod session_store;
+mod subscribers;
+mod tags;
 pub mod telemetry;
+mod tracking;
 mod utils;
 
 pub use configuration::Config;
-pub use email_client::EmailClient;
+pub use email_client::{Attachment, EmailClient, EmailMessage, Mailbox};
+pub use email_templates::{EmailTemplate, EmailTemplates};
+pub use locales::Locales;
 pub use server::Server;
 pub use session::Session;
 
@@ -0,0 +1,196 @@
+use crate::{
+    domain::{ListSlug, OptInMode},
+    Database, DbPool,
+};
+use anyhow::Context;
+use sqlx::Transaction;
+use uuid::Uuid;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ListError {
+    #[error("There is no list called {0}")]
+    Unknown(String),
+    #[error("A list called {0} already exists")]
+    Duplicate(String),
+    #[error(transparent)]
+    Unexpected(#[from] anyhow::Error),
+}
+
+#[derive(Clone, Debug)]
+pub struct ListSummary {
+    pub slug: String,
+    pub name: String,
+    pub confirmed_members: i64,
+    /// `None` follows the application-wide mode.
+    pub opt_in: Option<OptInMode>,
+}
+
+#[tracing::instrument(name = "Listing mailing lists", skip_all)]
+pub async fn all_lists(pool: &DbPool) -> anyhow::Result<Vec<ListSummary>> {
+    let rows = sqlx::query!(
+        r#"
+        select
+            l.slug,
+            l.name,
+            l.opt_in,
+            count(s.id) as "confirmed_members!"
+        from lists l
+        left join list_memberships m on m.list_id = l.list_id
+        left join subscriptions s
+            on s.id = m.subscriber_id and s.status = 'confirmed'
+        group by l.list_id
+        order by l.created_at, l.slug;
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query for mailing lists")?;
+    rows.into_iter()
+        .map(|r| {
+            Ok(ListSummary {
+                slug: r.slug,
+                name: r.name,
+                confirmed_members: r.confirmed_members,
+                opt_in: r
+                    .opt_in
+                    .map(OptInMode::try_from)
+                    .transpose()
+                    .map_err(anyhow::Error::msg)?,
+            })
+        })
+        .collect()
+}
+
+#[tracing::instrument(name = "Creating a mailing list", skip(pool))]
+pub async fn create_list(
+    slug: &ListSlug,
+    name: &str,
+    opt_in: Option<OptInMode>,
+    pool: &DbPool,
+) -> Result<Uuid, ListError> {
+    let list_id = Uuid::new_v4();
+    let inserted = sqlx::query!(
+        r#"
+        insert into lists (list_id, slug, name, opt_in, created_at)
+        values ($1, $2, $3, $4, now())
+        on conflict (slug) do nothing;
+        "#,
+        list_id,
+        slug.as_ref(),
+        name,
+        opt_in.as_ref().map(AsRef::as_ref),
+    )
+    .execute(pool)
+    .await
+    .context("Failed to insert a mailing list")?
+    .rows_affected()
+        == 1;
+    if !inserted {
+        return Err(ListError::Duplicate(slug.to_string()));
+    }
+    Ok(list_id)
+}
+
+/// `None` makes the list follow the application-wide mode.
+#[tracing::instrument(name = "Changing the opt-in mode of a list", skip(pool))]
+pub async fn set_list_opt_in(
+    slug: &ListSlug,
+    opt_in: Option<OptInMode>,
+    pool: &DbPool,
+) -> Result<(), ListError> {
+    let updated = sqlx::query!(
+        r#"
+        update lists
+        set opt_in = $2
+        where slug = $1;
+        "#,
+        slug.as_ref(),
+        opt_in.as_ref().map(AsRef::as_ref),
+    )
+    .execute(pool)
+    .await
+    .context("Failed to update a mailing list")?
+    .rows_affected()
+        == 1;
+    if !updated {
+        return Err(ListError::Unknown(slug.to_string()));
+    }
+    Ok(())
+}
+
+#[tracing::instrument(name = "Getting the opt-in mode of a list", skip(pool))]
+pub async fn list_opt_in(
+    list_id: &Uuid,
+    pool: &DbPool,
+) -> anyhow::Result<Option<OptInMode>> {
+    sqlx::query!(
+        r#"
+        select opt_in
+        from lists
+        where list_id = $1;
+        "#,
+        list_id,
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to query for a mailing list")?
+    .opt_in
+    .map(OptInMode::try_from)
+    .transpose()
+    .map_err(anyhow::Error::msg)
+}
+
+/// Looks up the ids of the given lists, failing on the first unknown slug.
+#[tracing::instrument(name = "Resolving mailing lists", skip(pool))]
+pub async fn resolve_lists(
+    slugs: &[ListSlug],
+    pool: &DbPool,
+) -> Result<Vec<Uuid>, ListError> {
+    let names: Vec<String> = slugs.iter().map(|s| s.to_string()).collect();
+    let found = sqlx::query!(
+        r#"
+        select list_id, slug
+        from lists
+        where slug = any($1);
+        "#,
+        &names,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query for mailing lists")?;
+    names
+        .into_iter()
+        .map(|name| {
+            found
+                .iter()
+                .find(|r| r.slug == name)
+                .map(|r| r.list_id)
+                .ok_or(ListError::Unknown(name))
+        })
+        .collect()
+}
+
+/// Returns `false` if the subscriber was already a member.
+#[tracing::instrument(
+    name = "Adding a subscriber to a list",
+    skip(transaction)
+)]
+pub async fn add_to_list(
+    list_id: &Uuid,
+    subscriber_id: &Uuid,
+    transaction: &mut Transaction<'_, Database>,
+) -> anyhow::Result<bool> {
+    sqlx::query!(
+        r#"
+        insert into list_memberships (list_id, subscriber_id, joined_at)
+        values ($1, $2, now())
+        on conflict do nothing;
+        "#,
+        list_id,
+        subscriber_id,
+    )
+    .execute(transaction)
+    .await
+    .map(|r| r.rows_affected() == 1)
+    .context("Failed to add a subscriber to a list")
+}
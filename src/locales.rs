@@ -0,0 +1,238 @@
+use crate::configuration::LocalesConfig;
+use actix_web::{http::header::ACCEPT_LANGUAGE, HttpRequest};
+use anyhow::{anyhow, Context};
+use fluent_bundle::{concurrent::FluentBundle, FluentArgs, FluentResource};
+use fluent_langneg::{
+    negotiate_languages, parse_accepted_languages, NegotiationStrategy,
+};
+use std::{fmt, sync::Arc};
+use unic_langid::LanguageIdentifier;
+
+/// The Fluent catalogues subscriber-facing text is translated with.
+#[derive(Clone)]
+pub struct Locales {
+    inner: Arc<Catalogues>,
+}
+
+struct Catalogues {
+    locales: Vec<LanguageIdentifier>,
+    /// In the same order as `locales`.
+    bundles: Vec<FluentBundle<FluentResource>>,
+    default: usize,
+}
+
+impl fmt::Debug for Locales {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Locales")
+            .field("locales", &self.inner.locales)
+            .field("default", &self.inner.locales[self.inner.default])
+            .finish()
+    }
+}
+
+impl Locales {
+    /// Fails if a catalogue doesn't parse or the default locale has none,
+    /// so that a broken translation is caught at startup.
+    pub fn load(config: LocalesConfig) -> anyhow::Result<Self> {
+        let directory = &config.directory;
+        let entries = std::fs::read_dir(directory).with_context(|| {
+            format!("Failed to list catalogues in {}", directory.display())
+        })?;
+        let mut locales = Vec::new();
+        let mut bundles = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension() != Some("ftl".as_ref()) {
+                continue;
+            }
+            let locale: LanguageIdentifier = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| s.parse().ok())
+                .with_context(|| {
+                    format!("{} isn't named after a locale", path.display())
+                })?;
+            let source = std::fs::read_to_string(&path).with_context(|| {
+                format!("Failed to read {}", path.display())
+            })?;
+            let resource =
+                FluentResource::try_new(source).map_err(|(_, errors)| {
+                    anyhow!("Failed to parse {}: {errors:?}", path.display())
+                })?;
+            let mut bundle = FluentBundle::new_concurrent(vec![locale.clone()]);
+            // Isolation marks around placeables would end up in plain text
+            // emails and page titles.
+            bundle.set_use_isolating(false);
+            bundle.add_resource(resource).map_err(|errors| {
+                anyhow!("Failed to load {}: {errors:?}", path.display())
+            })?;
+            locales.push(locale);
+            bundles.push(bundle);
+        }
+        let default = locales
+            .iter()
+            .position(|l| l.to_string() == config.default)
+            .with_context(|| {
+                format!(
+                    "There is no catalogue for the default locale {} in {}",
+                    config.default,
+                    directory.display()
+                )
+            })?;
+        Ok(Self {
+            inner: Arc::new(Catalogues {
+                locales,
+                bundles,
+                default,
+            }),
+        })
+    }
+
+    pub fn default_locale(&self) -> String {
+        self.inner.locales[self.inner.default].to_string()
+    }
+
+    /// Picks the supported locale that best matches `requested`, either a
+    /// single language tag or an `Accept-Language` header value.
+    pub fn negotiate(&self, requested: &str) -> String {
+        let Catalogues {
+            locales, default, ..
+        } = self.inner.as_ref();
+        negotiate_languages(
+            &parse_accepted_languages(requested),
+            locales,
+            Some(&locales[*default]),
+            NegotiationStrategy::Lookup,
+        )[0]
+        .to_string()
+    }
+
+    /// Negotiates with the request's `Accept-Language` header, for pages
+    /// shown before the subscriber is known.
+    pub fn for_request(&self, request: &HttpRequest) -> String {
+        let requested = request
+            .headers()
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        self.negotiate(requested)
+    }
+
+    /// Formats a message in `locale`, falling back to the default locale
+    /// when `locale` isn't supported or doesn't translate the message.
+    pub fn message(
+        &self,
+        locale: &str,
+        id: &str,
+        args: &[(&str, &str)],
+    ) -> String {
+        let Catalogues {
+            locales,
+            bundles,
+            default,
+        } = self.inner.as_ref();
+        let args: FluentArgs = args.iter().copied().collect();
+        let requested = locale
+            .parse::<LanguageIdentifier>()
+            .ok()
+            .and_then(|l| locales.iter().position(|a| *a == l));
+        for bundle in
+            requested.into_iter().chain([*default]).map(|i| &bundles[i])
+        {
+            let pattern = match bundle.get_message(id).and_then(|m| m.value()) {
+                Some(pattern) => pattern,
+                None => continue,
+            };
+            let mut errors = Vec::new();
+            let text = bundle.format_pattern(pattern, Some(&args), &mut errors);
+            if !errors.is_empty() {
+                tracing::warn!(
+                    message_id = id,
+                    locale,
+                    "Failed to format a message: {errors:?}"
+                );
+            }
+            return text.into_owned();
+        }
+        tracing::error!(message_id = id, "No catalogue defines the message");
+        id.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Locales;
+    use crate::configuration::LocalesConfig;
+
+    fn locales() -> Locales {
+        Locales::load(LocalesConfig {
+            directory: "locales".into(),
+            default: "en".into(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn the_best_supported_locale_is_negotiated() {
+        let locales = locales();
+        assert_eq!(locales.negotiate("fr"), "fr");
+        assert_eq!(locales.negotiate("fr-CA"), "fr");
+        assert_eq!(locales.negotiate("de-DE, fr;q=0.8, en;q=0.5"), "fr");
+        assert_eq!(locales.negotiate("de"), "en");
+        assert_eq!(locales.negotiate(""), "en");
+        assert_eq!(locales.negotiate("not a language"), "en");
+    }
+
+    #[test]
+    fn messages_are_formatted_with_their_arguments() {
+        assert_eq!(
+            locales().message(
+                "fr",
+                "unsubscribe-question",
+                &[("email", "ursula@example.com")]
+            ),
+            "Ne plus envoyer la newsletter à ursula@example.com ?"
+        );
+    }
+
+    #[test]
+    fn unsupported_locales_fall_back_to_the_default() {
+        assert_eq!(
+            locales().message("de", "unsubscribe-button", &[]),
+            "Unsubscribe"
+        );
+    }
+
+    #[test]
+    fn every_catalogue_translates_every_message_of_the_default() {
+        let locales = locales();
+        let catalogues = locales.inner.as_ref();
+        let default = &catalogues.bundles[catalogues.default];
+        let source = std::fs::read_to_string("locales/en.ftl").unwrap();
+        let ids: Vec<_> = source
+            .lines()
+            .filter_map(|l| l.split_once(" = "))
+            .map(|(id, _)| id)
+            .collect();
+        assert!(!ids.is_empty());
+        for bundle in &catalogues.bundles {
+            for id in &ids {
+                assert!(default.has_message(id));
+                assert!(
+                    bundle.has_message(id),
+                    "{:?} doesn't translate {id}",
+                    bundle.locales
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn a_missing_default_catalogue_is_rejected() {
+        assert!(Locales::load(LocalesConfig {
+            directory: "locales".into(),
+            default: "de".into(),
+        })
+        .is_err());
+    }
+}
@@ -2,14 +2,71 @@ use std::fmt::{Debug, Display};
 
 use dotenvy::dotenv;
 use tokio::task::JoinError;
-use zero2prod::{issue_delivery, telemetry, Config, Server};
+use zero2prod::{gdpr, issue_delivery, telemetry, Config, DbPool, Server};
+
+const USAGE: &str = "\
+Usage:
+    zero2prod                      Run the server and the delivery worker
+    zero2prod gdpr-export <email>  Print everything held about <email> as JSON
+    zero2prod gdpr-erase <email>   Erase everything held about <email>";
+
+enum Command {
+    Serve,
+    GdprExport(String),
+    GdprErase(String),
+}
+
+impl Command {
+    fn parse(args: &[String]) -> Option<Self> {
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        match args.as_slice() {
+            [] => Some(Self::Serve),
+            ["gdpr-export", email] => Some(Self::GdprExport(email.to_string())),
+            ["gdpr-erase", email] => Some(Self::GdprErase(email.to_string())),
+            _ => None,
+        }
+    }
+}
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     dotenv().ok();
-    telemetry::init("zero2prod", "info", std::io::stdout)
-        .expect("Failed to initialize telemetry");
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match Command::parse(&args) {
+        Some(command) => command,
+        None => {
+            eprintln!("{USAGE}");
+            std::process::exit(2);
+        }
+    };
+    let telemetry = match command {
+        Command::Serve => telemetry::init("zero2prod", "info", std::io::stdout),
+        // Keep stdout for the command output.
+        _ => telemetry::init("zero2prod", "warn", std::io::stderr),
+    };
+    telemetry.expect("Failed to initialize telemetry");
     let config = Config::init().expect("Failed to initialize config");
+    match command {
+        Command::Serve => serve(config).await,
+        Command::GdprExport(email) => {
+            let pool = DbPool::connect_lazy_with(config.database.with_db());
+            let data = gdpr::export_subject_data(&email, &pool).await?;
+            println!("{}", serde_json::to_string_pretty(&data)?);
+            Ok(())
+        }
+        Command::GdprErase(email) => {
+            let pool = DbPool::connect_lazy_with(config.database.with_db());
+            if gdpr::erase_subject_data(&email, &pool).await? {
+                println!("Erased all data held for {email}");
+            } else {
+                println!("No data held for {email}");
+            }
+            Ok(())
+        }
+    }
+}
+
+async fn serve(config: Config) -> anyhow::Result<()> {
     let server = tokio::spawn(Server::build(config.clone()).await?.run());
     let worker = tokio::spawn(issue_delivery::run_worker(config));
     tokio::select! {
@@ -0,0 +1,279 @@
+//! Turns a Markdown issue into the plain text and HTML bodies of an email.
+//!
+//! Raw HTML in the source is escaped rather than passed through, and links
+//! and images only keep `http`, `https`, `mailto` and `cid` URLs, the last
+//! for assets shown inline. URLs are not
+//! percent-encoded so that merge fields such as `{{ unsubscribe_url }}`
+//! survive until delivery.
+
+use htmlescape::encode_minimal;
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag, TagEnd};
+use std::fmt::Write;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RenderedMarkdown {
+    pub text_content: String,
+    pub html_content: String,
+}
+
+pub fn render_markdown(source: &str) -> RenderedMarkdown {
+    RenderedMarkdown {
+        text_content: to_text(source),
+        html_content: to_html(source),
+    }
+}
+
+fn parser(source: &str) -> Parser<'_> {
+    Parser::new_ext(source, Options::ENABLE_STRIKETHROUGH)
+}
+
+/// Relative URLs and placeholders are kept, as are the schemes above.
+fn safe_url(url: &str) -> &str {
+    let scheme = match url.split_once(':') {
+        Some((scheme, _)) if !scheme.contains(['/', '?', '#', '{']) => scheme,
+        _ => return url,
+    };
+    if ["http", "https", "mailto", "cid"]
+        .iter()
+        .any(|s| s.eq_ignore_ascii_case(scheme))
+    {
+        url
+    } else {
+        ""
+    }
+}
+
+fn title_attribute(title: &str) -> String {
+    if title.is_empty() {
+        String::new()
+    } else {
+        format!(r#" title="{}""#, encode_minimal(title))
+    }
+}
+
+fn to_html(source: &str) -> String {
+    let mut events = Vec::new();
+    let mut parser = parser(source);
+    while let Some(event) = parser.next() {
+        let event = match event {
+            Event::Html(html) | Event::InlineHtml(html) => Event::Text(html),
+            Event::Start(Tag::Link {
+                dest_url, title, ..
+            }) => Event::InlineHtml(
+                format!(
+                    r#"<a href="{}"{}>"#,
+                    encode_minimal(safe_url(&dest_url)),
+                    title_attribute(&title),
+                )
+                .into(),
+            ),
+            Event::End(TagEnd::Link) => Event::InlineHtml("</a>".into()),
+            Event::Start(Tag::Image {
+                dest_url, title, ..
+            }) => {
+                let alt = image_alt(&mut parser);
+                Event::InlineHtml(
+                    format!(
+                        r#"<img src="{}" alt="{}"{}>"#,
+                        encode_minimal(safe_url(&dest_url)),
+                        encode_minimal(&alt),
+                        title_attribute(&title),
+                    )
+                    .into(),
+                )
+            }
+            event => event,
+        };
+        events.push(event);
+    }
+    let mut html = String::new();
+    html::push_html(&mut html, events.into_iter());
+    html
+}
+
+/// Consumes the events up to the end of the image, returning its text.
+fn image_alt<'a>(parser: &mut impl Iterator<Item = Event<'a>>) -> String {
+    let mut alt = String::new();
+    let mut depth = 0;
+    for event in parser {
+        match event {
+            Event::Start(Tag::Image { .. }) => depth += 1,
+            Event::End(TagEnd::Image) if depth == 0 => break,
+            Event::End(TagEnd::Image) => depth -= 1,
+            Event::Text(text) | Event::Code(text) => alt.push_str(&text),
+            _ => {}
+        }
+    }
+    alt
+}
+
+fn to_text(source: &str) -> String {
+    let mut text = String::new();
+    // The next number of every open list, `None` for bullet lists.
+    let mut lists: Vec<Option<u64>> = Vec::new();
+    let mut urls: Vec<CowStr<'_>> = Vec::new();
+    let mut heading_start = 0;
+    let mut in_code_block = false;
+    for event in parser(source) {
+        match event {
+            Event::Start(Tag::Heading { .. }) => heading_start = text.len(),
+            Event::End(TagEnd::Heading(level)) => {
+                let width = text[heading_start..].chars().count();
+                let underline = if level as usize == 1 { "=" } else { "-" };
+                write!(text, "\n{}\n\n", underline.repeat(width)).unwrap();
+            }
+            Event::End(TagEnd::Paragraph) => {
+                text.push_str(if lists.is_empty() { "\n\n" } else { "\n" })
+            }
+            Event::Start(Tag::List(start)) => {
+                end_line(&mut text);
+                lists.push(start);
+            }
+            Event::End(TagEnd::List(_)) => {
+                lists.pop();
+                if lists.is_empty() {
+                    text.push('\n');
+                }
+            }
+            Event::Start(Tag::Item) => {
+                let indent = "   ".repeat(lists.len().saturating_sub(1));
+                match lists.last_mut() {
+                    Some(Some(number)) => {
+                        write!(text, "{indent}{number}. ").unwrap();
+                        *number += 1;
+                    }
+                    _ => write!(text, "{indent}- ").unwrap(),
+                }
+            }
+            Event::End(TagEnd::Item) => end_line(&mut text),
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => {
+                in_code_block = false;
+                text.push('\n');
+            }
+            Event::Start(Tag::Link { dest_url, .. })
+            | Event::Start(Tag::Image { dest_url, .. }) => urls.push(dest_url),
+            Event::End(TagEnd::Link) | Event::End(TagEnd::Image) => {
+                let url = urls.pop().unwrap_or(CowStr::Borrowed(""));
+                if !url.is_empty() && !text.ends_with(url.as_ref()) {
+                    write!(text, " ({url})").unwrap();
+                }
+            }
+            Event::Text(value) if in_code_block => {
+                for line in value.split_inclusive('\n') {
+                    if text.is_empty() || text.ends_with('\n') {
+                        text.push_str("    ");
+                    }
+                    text.push_str(line);
+                }
+            }
+            Event::Text(value)
+            | Event::Code(value)
+            | Event::Html(value)
+            | Event::InlineHtml(value) => text.push_str(&value),
+            Event::SoftBreak | Event::HardBreak => text.push('\n'),
+            Event::Rule => text.push_str("---\n\n"),
+            _ => {}
+        }
+    }
+    text.trim_end().to_string()
+}
+
+fn end_line(text: &mut String) {
+    if !text.is_empty() && !text.ends_with('\n') {
+        text.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_markdown;
+
+    fn text(source: &str) -> String {
+        render_markdown(source).text_content
+    }
+
+    fn html(source: &str) -> String {
+        render_markdown(source).html_content
+    }
+
+    #[test]
+    fn paragraphs_and_headings_are_rendered() {
+        let source = "# Issue 1\n\nHello *there*.\n\n## News\n\nMore.";
+        assert_eq!(
+            html(source),
+            "<h1>Issue 1</h1>\n<p>Hello <em>there</em>.</p>\n\
+            <h2>News</h2>\n<p>More.</p>\n"
+        );
+        assert_eq!(
+            text(source),
+            "Issue 1\n=======\n\nHello there.\n\nNews\n----\n\nMore."
+        );
+    }
+
+    #[test]
+    fn lists_are_numbered_and_nested_in_plain_text() {
+        let source = "1. One\n2. Two\n   - Nested\n\nAfter";
+        assert_eq!(text(source), "1. One\n2. Two\n   - Nested\n\nAfter");
+    }
+
+    #[test]
+    fn links_show_their_url_in_plain_text() {
+        let source = "Read [the post](https://example.com/post) or \
+            <https://example.com>.";
+        assert_eq!(
+            text(source),
+            "Read the post (https://example.com/post) or https://example.com."
+        );
+        assert!(html(source)
+            .contains(r#"<a href="https://example.com/post">the post</a>"#));
+    }
+
+    #[test]
+    fn merge_fields_survive_in_urls_and_text() {
+        let source = "Hi {{ name }}, [leave](<{{ unsubscribe_url }}>)";
+        assert_eq!(
+            html(source),
+            "<p>Hi {{ name }}, <a href=\"{{ unsubscribe_url }}\">leave</a></p>\n"
+        );
+        assert_eq!(
+            text(source),
+            "Hi {{ name }}, leave ({{ unsubscribe_url }})"
+        );
+    }
+
+    #[test]
+    fn raw_html_is_escaped() {
+        let source =
+            "<script>alert(1)</script>\n\nHi <b onclick=\"x()\">you</b>";
+        let html = html(source);
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+        assert!(html.contains(r#"&lt;b onclick="x()"&gt;you&lt;/b&gt;"#));
+    }
+
+    #[test]
+    fn unsafe_urls_are_dropped() {
+        assert_eq!(
+            html(
+                "[click](javascript:alert(1)) ![pic](data:image/png;base64,x)"
+            ),
+            "<p><a href=\"\">click</a> <img src=\"\" alt=\"pic\"></p>\n"
+        );
+        assert!(html("[mail](mailto:a@example.com) [rel](/about)")
+            .contains(r#"href="mailto:a@example.com""#));
+        assert!(html("[rel](/about?a=1&b=2)")
+            .contains(r#"href="/about?a=1&amp;b=2""#));
+        assert!(html("![logo](cid:logo.png)")
+            .contains(r#"<img src="cid:logo.png" alt="logo">"#));
+    }
+
+    #[test]
+    fn code_blocks_are_indented_in_plain_text() {
+        let source = "Run:\n\n```\ncargo run\ncargo test\n```\n\nDone.";
+        assert_eq!(
+            text(source),
+            "Run:\n\n    cargo run\n    cargo test\n\nDone."
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use crate::{
+    auth::{csrf_token, list_api_tokens, UserId},
+    utils::e500,
+    DbPool, Session,
+};
+use actix_web::{
+    http::header::ContentType,
+    web::{Data, ReqData},
+    HttpResponse,
+};
+use actix_web_flash_messages::IncomingFlashMessages;
+use htmlescape::encode_minimal;
+use std::fmt::Write;
+use uuid::Uuid;
+
+pub async fn api_tokens(
+    user_id: ReqData<UserId>,
+    session: Session,
+    pool: Data<DbPool>,
+    flash_messages: IncomingFlashMessages,
+) -> actix_web::Result<HttpResponse> {
+    let mut msgs = String::new();
+    for m in flash_messages.iter() {
+        writeln!(msgs, "<p><i>{}</i></p>", m.content()).unwrap();
+    }
+    let page =
+        api_tokens_page(&user_id.into_inner(), &session, &pool, &msgs).await?;
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(page))
+}
+
+/// The tokens of the user and the form to create one, below `msgs`.
+pub(super) async fn api_tokens_page(
+    user_id: &Uuid,
+    session: &Session,
+    pool: &DbPool,
+    msgs: &str,
+) -> actix_web::Result<String> {
+    let csrf_token = csrf_token(session)?;
+    let tokens = list_api_tokens(user_id, pool).await.map_err(e500)?;
+    let mut rows = String::new();
+    for t in tokens {
+        writeln!(
+            rows,
+            r#"
+                    <tr>
+                        <td>{name}</td>
+                        <td>{created_at}</td>
+                        <td>{last_used_at}</td>
+                        <td>
+                            <form action="/admin/api_tokens/revoke" method="post">
+                                <input hidden type="text" name="api_token_id" value="{api_token_id}">
+                                <input hidden type="text" name="csrf_token" value="{csrf_token}">
+                                <button type="submit">Revoke</button>
+                            </form>
+                        </td>
+                    </tr>"#,
+            name = encode_minimal(&t.name),
+            created_at = t.created_at.format("%Y-%m-%d %H:%M:%S UTC"),
+            last_used_at = t
+                .last_used_at
+                .map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+                .unwrap_or_else(|| "Never".into()),
+            api_token_id = t.api_token_id,
+        )
+        .unwrap();
+    }
+    Ok(format!(
+        r#"
+            <!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>API tokens</title>
+            </head>
+            <body>
+                {msgs}
+                <table>
+                    <tr>
+                        <th>Name</th>
+                        <th>Created</th>
+                        <th>Last used</th>
+                        <th></th>
+                    </tr>
+                    {rows}
+                </table>
+                <form action="/admin/api_tokens" method="post">
+                    <label>Name:
+                        <input
+                            type="text"
+                            placeholder="What is the token for?"
+                            name="name"
+                        >
+                    </label>
+                    <input hidden type="text" name="csrf_token" value="{csrf_token}">
+                    <button type="submit">Create token</button>
+                </form>
+                <p><a href="/admin/dashboard">&lt;- Back</a></p>
+            </body>
+            </html>
+            "#,
+    ))
+}
@@ -0,0 +1,68 @@
+use super::api_tokens_page;
+use crate::{
+    auth::{create_api_token, revoke_api_token, UserId},
+    utils::{e500, see_other},
+    DbPool, Session,
+};
+use actix_web::{
+    http::header::{CacheControl, CacheDirective, ContentType},
+    web::{Data, Form, ReqData},
+    HttpResponse,
+};
+use actix_web_flash_messages::FlashMessage;
+use htmlescape::encode_minimal;
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct CreateFormData {
+    name: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct RevokeFormData {
+    api_token_id: Uuid,
+}
+
+/// Shows the new token right away: it is not kept anywhere it could be
+/// read back from, not even in a flash message cookie.
+pub async fn create_token(
+    user_id: ReqData<UserId>,
+    session: Session,
+    form: Form<CreateFormData>,
+    pool: Data<DbPool>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = *user_id.into_inner();
+    let name = form.0.name.trim();
+    if name.is_empty() {
+        FlashMessage::error("The token name cannot be empty.").send();
+        return Ok(see_other("/admin/api_tokens"));
+    }
+    let token = create_api_token(&user_id, name, &pool)
+        .await
+        .map_err(e500)?;
+    let msgs = format!(
+        "<p><i>Your new API token: <code>{}</code>. \
+         Copy it now, it will not be shown again.</i></p>",
+        encode_minimal(token.expose_secret())
+    );
+    let page = api_tokens_page(&user_id, &session, &pool, &msgs).await?;
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .insert_header(CacheControl(vec![CacheDirective::NoStore]))
+        .body(page))
+}
+
+pub async fn revoke_token(
+    user_id: ReqData<UserId>,
+    form: Form<RevokeFormData>,
+    pool: Data<DbPool>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = *user_id.into_inner();
+    revoke_api_token(&user_id, &form.api_token_id, &pool)
+        .await
+        .map_err(e500)?;
+    FlashMessage::info("The API token has been revoked.").send();
+    Ok(see_other("/admin/api_tokens"))
+}
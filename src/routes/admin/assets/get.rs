@@ -0,0 +1,78 @@
+use crate::{
+    assets::all_assets, auth::csrf_token, utils::e500, DbPool, EmailClient,
+    Session,
+};
+use actix_web::{http::header::ContentType, web::Data, HttpResponse};
+use actix_web_flash_messages::IncomingFlashMessages;
+use htmlescape::encode_minimal;
+use std::fmt::Write;
+
+pub async fn assets(
+    session: Session,
+    pool: Data<DbPool>,
+    email_client: Data<EmailClient>,
+    flash_messages: IncomingFlashMessages,
+) -> actix_web::Result<HttpResponse> {
+    let csrf_token = csrf_token(&session)?;
+    let mut msgs = String::new();
+    for m in flash_messages.iter() {
+        writeln!(msgs, "<p><i>{}</i></p>", m.content()).unwrap();
+    }
+    let mut rows = String::new();
+    for asset in all_assets(&pool).await.map_err(e500)? {
+        writeln!(
+            rows,
+            r#"
+                    <tr>
+                        <td>{name}</td>
+                        <td>{content_type}</td>
+                        <td>{size}</td>
+                        <td>{uploaded_at}</td>
+                    </tr>"#,
+            name = encode_minimal(&asset.name),
+            content_type = encode_minimal(&asset.content_type),
+            size = asset.size,
+            uploaded_at = asset.uploaded_at.format("%Y-%m-%d %H:%M"),
+        )
+        .unwrap();
+    }
+    let limit = email_client.max_attachments_size();
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(format!(
+            r#"
+            <!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Assets</title>
+            </head>
+            <body>
+                {msgs}
+                <table>
+                    <tr>
+                        <th>Name</th>
+                        <th>Type</th>
+                        <th>Size (bytes)</th>
+                        <th>Uploaded</th>
+                    </tr>
+                    {rows}
+                </table>
+                <form action="/admin/assets" method="post" enctype="multipart/form-data">
+                    <input hidden type="text" name="csrf_token" value="{csrf_token}">
+                    <label>File
+                        <input type="file" name="file">
+                    </label>
+                    <button type="submit">Upload</button>
+                </form>
+                <p>
+                    Assets are named after the uploaded file. Attach them to an issue,
+                    or show images inline with <code>&lt;img src="cid:name"&gt;</code>.
+                    The assets of an issue can add up to {limit} bytes.
+                </p>
+                <p><a href="/admin/dashboard">&lt;- Back</a></p>
+            </body>
+            </html>
+            "#,
+        )))
+}
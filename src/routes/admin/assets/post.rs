@@ -0,0 +1,91 @@
+use crate::{
+    assets::{store_asset, AssetError},
+    auth::check_csrf_field,
+    domain::AssetName,
+    utils::{e500, see_other},
+    DbPool, EmailClient, Session,
+};
+use actix_multipart::Multipart;
+use actix_web::{web::Data, HttpResponse};
+use actix_web_flash_messages::FlashMessage;
+use futures_util::TryStreamExt;
+use htmlescape::encode_minimal;
+
+#[tracing::instrument(name = "Upload an asset", skip_all)]
+pub async fn upload_asset(
+    mut payload: Multipart,
+    session: Session,
+    pool: Data<DbPool>,
+    email_client: Data<EmailClient>,
+) -> actix_web::Result<HttpResponse> {
+    // A larger file could never be sent anyway.
+    let limit = email_client.max_attachments_size();
+    let mut csrf_token = Vec::new();
+    let mut upload = None;
+    while let Some(mut field) = payload.try_next().await? {
+        if field.name() == Some("csrf_token") {
+            while let Some(chunk) = field.try_next().await? {
+                csrf_token.extend_from_slice(&chunk);
+            }
+            continue;
+        }
+        if field.name() != Some("file") {
+            continue;
+        }
+        // The token comes first, no need to read files without it.
+        check_csrf_field(&session, &csrf_token)?;
+        let file_name = field
+            .content_disposition()
+            .and_then(|d| d.get_filename())
+            .map(String::from);
+        let content_type = field
+            .content_type()
+            .map(|m| m.essence_str().to_owned())
+            .unwrap_or_else(|| "application/octet-stream".into());
+        let mut content = Vec::new();
+        while let Some(chunk) = field.try_next().await? {
+            if content.len() + chunk.len() > limit {
+                FlashMessage::error(format!(
+                    "The file is too large. The limit is {limit} bytes."
+                ))
+                .send();
+                return Ok(see_other("/admin/assets"));
+            }
+            content.extend_from_slice(&chunk);
+        }
+        upload = Some((file_name, content_type, content));
+    }
+    check_csrf_field(&session, &csrf_token)?;
+    let (file_name, content_type, content) = match upload {
+        Some((Some(file_name), content_type, content))
+            if !content.is_empty() =>
+        {
+            (file_name, content_type, content)
+        }
+        _ => {
+            FlashMessage::error("Please choose a non-empty file to upload.")
+                .send();
+            return Ok(see_other("/admin/assets"));
+        }
+    };
+    let name = match AssetName::try_from(file_name) {
+        Ok(name) => name,
+        Err(e) => {
+            FlashMessage::error(format!(
+                "{}. Use letters, digits, dots, dashes and underscores.",
+                encode_minimal(&e)
+            ))
+            .send();
+            return Ok(see_other("/admin/assets"));
+        }
+    };
+    match store_asset(&name, &content_type, &content, &pool).await {
+        Ok(()) => {
+            FlashMessage::info(format!("The asset {name} has been uploaded."))
+                .send();
+        }
+        Err(AssetError::Unexpected(e)) => return Err(e500(e)),
+        Err(e) => FlashMessage::error(e.to_string()).send(),
+    }
+    Ok(see_other("/admin/assets"))
+}
@@ -1,4 +1,8 @@
-use crate::{auth::UserId, utils::e500, DbPool};
+use crate::{
+    auth::{csrf_token, UserId},
+    utils::e500,
+    DbPool, Session,
+};
 use actix_web::{
     http::header::ContentType,
     web::{Data, ReqData},
@@ -10,8 +14,10 @@ use uuid::Uuid;
 pub async fn admin_dashboard(
     user_id: ReqData<UserId>,
     pool: Data<DbPool>,
+    session: Session,
 ) -> actix_web::Result<HttpResponse> {
     let user_id = *user_id.into_inner();
+    let csrf_token = csrf_token(&session)?;
     let username = get_username(user_id, &pool).await.map_err(e500)?;
     Ok(HttpResponse::Ok()
         .content_type(ContentType::html())
@@ -28,9 +34,19 @@ pub async fn admin_dashboard(
                 <p>Available actions:</p>
                 <ol>
                     <li><a href="/admin/newsletters">Post a newsletter</a></li>
+                    <li><a href="/admin/assets">Assets</a></li>
+                    <li><a href="/admin/stats">Opens and clicks</a></li>
+                    <li><a href="/admin/subscribers">Subscribers</a></li>
+                    <li><a href="/admin/lists">Mailing lists</a></li>
+                    <li><a href="/admin/tags">Tags</a></li>
+                    <li><a href="/admin/exports">Exports</a></li>
+                    <li><a href="/admin/gdpr">Data subject requests</a></li>
                     <li><a href="/admin/password">Change password</a></li>
+                    <li><a href="/admin/sessions">Active sessions</a></li>
+                    <li><a href="/admin/api_tokens">API tokens</a></li>
                     <li>
                         <form name="logoutForm" action="/admin/logout" method="post">
+                            <input hidden type="text" name="csrf_token" value="{csrf_token}">
                             <input type="submit" value="Logout">
                         </form>
                     </li>
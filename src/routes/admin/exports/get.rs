@@ -0,0 +1,83 @@
+use crate::{
+    exports::{export_newsletter_issues, export_subscriptions, ExportFormat},
+    DbPool,
+};
+use actix_web::{
+    http::header::{ContentType, CONTENT_DISPOSITION, CONTENT_TYPE},
+    web::{Data, Query},
+    HttpResponse,
+};
+use chrono::Utc;
+use serde::Deserialize;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ExportParams {
+    #[serde(default)]
+    format: ExportFormat,
+}
+
+pub async fn exports() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(
+            r#"
+            <!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Exports</title>
+            </head>
+            <body>
+                <p>Subscriptions:
+                    <a href="/admin/exports/subscriptions?format=csv">CSV</a>
+                    <a href="/admin/exports/subscriptions?format=jsonl">JSON Lines</a>
+                </p>
+                <p>Newsletter issues:
+                    <a href="/admin/exports/newsletter_issues?format=csv">CSV</a>
+                    <a href="/admin/exports/newsletter_issues?format=jsonl">JSON Lines</a>
+                </p>
+                <p><a href="/admin/dashboard">&lt;- Back</a></p>
+            </body>
+            </html>
+            "#,
+        )
+}
+
+#[tracing::instrument(name = "Export subscriptions", skip(pool))]
+pub async fn subscriptions_export(
+    params: Query<ExportParams>,
+    pool: Data<DbPool>,
+) -> HttpResponse {
+    let format = params.0.format;
+    download("subscriptions", format)
+        .streaming(export_subscriptions(format, pool.get_ref().clone()))
+}
+
+#[tracing::instrument(name = "Export newsletter issues", skip(pool))]
+pub async fn newsletter_issues_export(
+    params: Query<ExportParams>,
+    pool: Data<DbPool>,
+) -> HttpResponse {
+    let format = params.0.format;
+    download("newsletter_issues", format)
+        .streaming(export_newsletter_issues(format, pool.get_ref().clone()))
+}
+
+fn download(
+    name: &str,
+    format: ExportFormat,
+) -> actix_web::HttpResponseBuilder {
+    let filename = format!(
+        "{name}-{}.{}",
+        Utc::now().format("%Y%m%d%H%M%S"),
+        format.extension()
+    );
+    let mut response = HttpResponse::Ok();
+    response
+        .insert_header((CONTENT_TYPE, format.content_type()))
+        .insert_header((
+            CONTENT_DISPOSITION,
+            format!(r#"attachment; filename="{filename}""#),
+        ));
+    response
+}
@@ -0,0 +1,46 @@
+use crate::{auth::csrf_token, Session};
+use actix_web::{http::header::ContentType, HttpResponse};
+use actix_web_flash_messages::IncomingFlashMessages;
+use std::fmt::Write;
+
+pub async fn data_requests_form(
+    session: Session,
+    flash_messages: IncomingFlashMessages,
+) -> actix_web::Result<HttpResponse> {
+    let csrf_token = csrf_token(&session)?;
+    let mut msgs = String::new();
+    for m in flash_messages.iter() {
+        writeln!(msgs, "<p><i>{}</i></p>", m.content()).unwrap();
+    }
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(format!(
+            r#"
+            <!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Data subject requests</title>
+            </head>
+            <body>
+                {msgs}
+                <form action="/admin/gdpr/export" method="post">
+                    <label>Email
+                        <input type="text" placeholder="Enter the subscriber email" name="email">
+                    </label>
+                    <input hidden type="text" name="csrf_token" value="{csrf_token}">
+                    <button type="submit">Export data</button>
+                </form>
+                <form action="/admin/gdpr/erase" method="post">
+                    <label>Email
+                        <input type="text" placeholder="Enter the subscriber email" name="email">
+                    </label>
+                    <input hidden type="text" name="csrf_token" value="{csrf_token}">
+                    <button type="submit">Erase data</button>
+                </form>
+                <p><a href="/admin/dashboard">&lt;- Back</a></p>
+            </body>
+            </html>
+            "#,
+        )))
+}
@@ -0,0 +1,70 @@
+use crate::{
+    gdpr::{erase_subject_data, export_subject_data},
+    utils::{e500, see_other},
+    DbPool,
+};
+use actix_web::{
+    http::header::{ContentType, CONTENT_DISPOSITION},
+    web::{Data, Form},
+    HttpResponse,
+};
+use actix_web_flash_messages::FlashMessage;
+use htmlescape::encode_minimal;
+use serde::Deserialize;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct FormData {
+    email: String,
+}
+
+#[tracing::instrument(name = "Export a data subject's records", skip_all)]
+pub async fn export_data_subject(
+    form: Form<FormData>,
+    pool: Data<DbPool>,
+) -> actix_web::Result<HttpResponse> {
+    let email = form.0.email.trim().to_owned();
+    if email.is_empty() {
+        FlashMessage::error("Please enter an email.").send();
+        return Ok(see_other("/admin/gdpr"));
+    }
+    let data = export_subject_data(&email, &pool).await.map_err(e500)?;
+    if data.is_empty() {
+        FlashMessage::info(format!(
+            "We hold no data for {}.",
+            encode_minimal(&email)
+        ))
+        .send();
+        return Ok(see_other("/admin/gdpr"));
+    }
+    let body = serde_json::to_string_pretty(&data).map_err(e500)?;
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::json())
+        .insert_header((
+            CONTENT_DISPOSITION,
+            r#"attachment; filename="subject-data.json""#,
+        ))
+        .body(body))
+}
+
+#[tracing::instrument(name = "Erase a data subject's records", skip_all)]
+pub async fn erase_data_subject(
+    form: Form<FormData>,
+    pool: Data<DbPool>,
+) -> actix_web::Result<HttpResponse> {
+    let email = form.0.email.trim().to_owned();
+    if email.is_empty() {
+        FlashMessage::error("Please enter an email.").send();
+        return Ok(see_other("/admin/gdpr"));
+    }
+    let erased = erase_subject_data(&email, &pool).await.map_err(e500)?;
+    let email = encode_minimal(&email);
+    if erased {
+        FlashMessage::info(format!(
+            "All data held for {email} has been erased."
+        ))
+        .send();
+    } else {
+        FlashMessage::info(format!("We hold no data for {email}.")).send();
+    }
+    Ok(see_other("/admin/gdpr"))
+}
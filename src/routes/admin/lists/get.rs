@@ -0,0 +1,112 @@
+use crate::{
+    auth::csrf_token, domain::OptInMode, lists::all_lists, utils::e500, DbPool,
+    Session,
+};
+use actix_web::{http::header::ContentType, web::Data, HttpResponse};
+use actix_web_flash_messages::IncomingFlashMessages;
+use htmlescape::encode_minimal;
+use std::fmt::Write;
+
+/// `<option>`s for every mode, starting with the application default.
+fn opt_in_options(selected: Option<OptInMode>, default: OptInMode) -> String {
+    let mut options = format!(
+        r#"<option value=""{}>Application default ({default})</option>"#,
+        if selected.is_none() { " selected" } else { "" },
+    );
+    for mode in OptInMode::ALL {
+        let attribute = if selected == Some(mode) {
+            " selected"
+        } else {
+            ""
+        };
+        write!(
+            options,
+            r#"<option value="{mode}"{attribute}>{mode}</option>"#
+        )
+        .unwrap();
+    }
+    options
+}
+
+pub async fn mailing_lists(
+    session: Session,
+    pool: Data<DbPool>,
+    default_opt_in: Data<OptInMode>,
+    flash_messages: IncomingFlashMessages,
+) -> actix_web::Result<HttpResponse> {
+    let default_opt_in = **default_opt_in;
+    let csrf_token = csrf_token(&session)?;
+    let mut msgs = String::new();
+    for m in flash_messages.iter() {
+        writeln!(msgs, "<p><i>{}</i></p>", m.content()).unwrap();
+    }
+    let mut rows = String::new();
+    for list in all_lists(&pool).await.map_err(e500)? {
+        writeln!(
+            rows,
+            r#"
+                    <tr>
+                        <td>{slug}</td>
+                        <td>{name}</td>
+                        <td>{members}</td>
+                        <td>
+                            <form action="/admin/lists/opt_in" method="post">
+                                <select name="opt_in">{options}</select>
+                                <input hidden type="text" name="slug" value="{slug}">
+                                <input hidden type="text" name="csrf_token" value="{csrf_token}">
+                                <button type="submit">Change</button>
+                            </form>
+                        </td>
+                    </tr>"#,
+            slug = list.slug,
+            name = encode_minimal(&list.name),
+            members = list.confirmed_members,
+            options = opt_in_options(list.opt_in, default_opt_in),
+        )
+        .unwrap();
+    }
+    let options = opt_in_options(None, default_opt_in);
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(format!(
+            r#"
+            <!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Mailing lists</title>
+            </head>
+            <body>
+                {msgs}
+                <table>
+                    <tr>
+                        <th>Identifier</th>
+                        <th>Name</th>
+                        <th>Confirmed members</th>
+                        <th>Opt-in</th>
+                    </tr>
+                    {rows}
+                </table>
+                <form action="/admin/lists" method="post">
+                    <label>Identifier
+                        <input type="text" placeholder="e.g. product-updates" name="slug">
+                    </label>
+                    <label>Name
+                        <input type="text" placeholder="e.g. Product updates" name="name">
+                    </label>
+                    <label>Opt-in
+                        <select name="opt_in">{options}</select>
+                    </label>
+                    <input hidden type="text" name="csrf_token" value="{csrf_token}">
+                    <button type="submit">Create list</button>
+                </form>
+                <p>
+                    With double opt-in new subscribers confirm their address first.
+                    With single opt-in they are confirmed right away and get a welcome email.
+                </p>
+                <p><a href="/admin/dashboard">&lt;- Back</a></p>
+            </body>
+            </html>
+            "#,
+        )))
+}
@@ -0,0 +1,108 @@
+use crate::{
+    domain::{ListSlug, OptInMode},
+    lists::{create_list, set_list_opt_in, ListError},
+    utils::{e500, see_other},
+    DbPool,
+};
+use actix_web::{
+    web::{Data, Form},
+    HttpResponse,
+};
+use actix_web_flash_messages::FlashMessage;
+use htmlescape::encode_minimal;
+use serde::Deserialize;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct FormData {
+    slug: String,
+    name: String,
+    /// Empty to follow the application-wide mode.
+    #[serde(default)]
+    opt_in: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct OptInFormData {
+    slug: String,
+    #[serde(default)]
+    opt_in: String,
+}
+
+fn parse_opt_in(opt_in: String) -> Result<Option<OptInMode>, String> {
+    if opt_in.is_empty() {
+        return Ok(None);
+    }
+    OptInMode::try_from(opt_in).map(Some)
+}
+
+#[tracing::instrument(name = "Create a mailing list", skip_all)]
+pub async fn create_mailing_list(
+    form: Form<FormData>,
+    pool: Data<DbPool>,
+) -> actix_web::Result<HttpResponse> {
+    let FormData { slug, name, opt_in } = form.0;
+    let name = name.trim();
+    if name.is_empty() {
+        FlashMessage::error("The list name cannot be empty.").send();
+        return Ok(see_other("/admin/lists"));
+    }
+    let slug = match ListSlug::try_from(slug.trim().to_owned()) {
+        Ok(slug) => slug,
+        Err(e) => {
+            FlashMessage::error(format!(
+                "{}. Use lowercase letters, digits and dashes.",
+                encode_minimal(&e)
+            ))
+            .send();
+            return Ok(see_other("/admin/lists"));
+        }
+    };
+    let opt_in = match parse_opt_in(opt_in) {
+        Ok(opt_in) => opt_in,
+        Err(e) => {
+            FlashMessage::error(encode_minimal(&e)).send();
+            return Ok(see_other("/admin/lists"));
+        }
+    };
+    match create_list(&slug, name, opt_in, &pool).await {
+        Ok(_) => {
+            FlashMessage::info(format!("The list {slug} has been created."))
+                .send();
+        }
+        Err(ListError::Unexpected(e)) => return Err(e500(e)),
+        Err(e) => FlashMessage::error(e.to_string()).send(),
+    }
+    Ok(see_other("/admin/lists"))
+}
+
+#[tracing::instrument(
+    name = "Change the opt-in mode of a mailing list",
+    skip_all,
+    fields(slug = %form.slug)
+)]
+pub async fn change_list_opt_in(
+    form: Form<OptInFormData>,
+    pool: Data<DbPool>,
+) -> actix_web::Result<HttpResponse> {
+    let OptInFormData { slug, opt_in } = form.0;
+    let changes = ListSlug::try_from(slug)
+        .and_then(|slug| Ok((slug, parse_opt_in(opt_in)?)));
+    let (slug, opt_in) = match changes {
+        Ok(changes) => changes,
+        Err(e) => {
+            FlashMessage::error(encode_minimal(&e)).send();
+            return Ok(see_other("/admin/lists"));
+        }
+    };
+    match set_list_opt_in(&slug, opt_in, &pool).await {
+        Ok(()) => {
+            FlashMessage::info(format!(
+                "The opt-in mode of {slug} has been updated."
+            ))
+            .send();
+        }
+        Err(ListError::Unexpected(e)) => return Err(e500(e)),
+        Err(e) => FlashMessage::error(e.to_string()).send(),
+    }
+    Ok(see_other("/admin/lists"))
+}
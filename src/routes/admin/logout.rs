@@ -1,12 +1,27 @@
-use actix_web::HttpResponse;
+use actix_web::{
+    web::{Data, ReqData},
+    HttpResponse,
+};
 use actix_web_flash_messages::FlashMessage;
 
-use crate::{utils::see_other, Session};
+use crate::{
+    auth::{revoke_session, UserId},
+    utils::{e500, see_other},
+    DbPool, Session,
+};
 
-pub async fn logout(session: Session) -> actix_web::Result<HttpResponse> {
-    if session.get_user_id()?.is_some() {
-        session.logout();
-        FlashMessage::info("You have successfully logged out.").send();
+pub async fn logout(
+    user_id: ReqData<UserId>,
+    session: Session,
+    pool: Data<DbPool>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = *user_id.into_inner();
+    if let Some(session_id) = session.get_session_id()? {
+        revoke_session(&user_id, &session_id, &pool)
+            .await
+            .map_err(e500)?;
     }
+    session.logout();
+    FlashMessage::info("You have successfully logged out.").send();
     Ok(see_other("/login"))
 }
@@ -1,9 +1,27 @@
+mod api_tokens;
+mod assets;
 mod dashboard;
+mod exports;
+mod gdpr;
+mod lists;
 mod logout;
 mod newsletters;
 mod password;
+mod sessions;
+mod stats;
+mod subscribers;
+mod tags;
 
+pub use api_tokens::*;
+pub use assets::*;
 pub use dashboard::*;
+pub use exports::*;
+pub use gdpr::*;
+pub use lists::*;
 pub use logout::*;
 pub use newsletters::*;
 pub use password::*;
+pub use sessions::*;
+pub use stats::*;
+pub use subscribers::*;
+pub use tags::*;
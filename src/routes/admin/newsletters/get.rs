@@ -1,17 +1,101 @@
+use crate::{
+    assets::all_assets, auth::csrf_token, lists::all_lists, utils::e500,
+    DbPool, Session,
+};
 use actix_web::http::header::ContentType;
-use actix_web::HttpResponse;
+use actix_web::{web::Data, HttpResponse};
 use actix_web_flash_messages::IncomingFlashMessages;
+use htmlescape::encode_minimal;
 use std::fmt::Write;
 use uuid::Uuid;
 
+/// What the editor has filled in so far, kept across recipient previews.
+#[derive(Clone, Debug, Default)]
+pub struct NewsletterDraft {
+    pub title: String,
+    pub text_content: String,
+    pub html_content: String,
+    pub markdown_content: String,
+    pub idempotency_key: Option<String>,
+    /// `None` preselects the default list.
+    pub lists: Option<Vec<String>>,
+    pub segment: String,
+    pub assets: Vec<String>,
+    pub tracking: bool,
+}
+
 pub async fn publish_newsletter_form(
     flash_messages: IncomingFlashMessages,
+    session: Session,
+    pool: Data<DbPool>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    let idempotency_key = Uuid::new_v4();
     let mut msgs = String::new();
     for m in flash_messages.iter() {
         writeln!(msgs, "<p><i>{}</i></p>", m.content()).unwrap();
     }
+    render_newsletter_form(&NewsletterDraft::default(), &msgs, &session, &pool)
+        .await
+}
+
+/// `msgs` is inserted as is above the form.
+pub async fn render_newsletter_form(
+    draft: &NewsletterDraft,
+    msgs: &str,
+    session: &Session,
+    pool: &DbPool,
+) -> Result<HttpResponse, actix_web::Error> {
+    let idempotency_key = match &draft.idempotency_key {
+        Some(key) => encode_minimal(key),
+        None => Uuid::new_v4().to_string(),
+    };
+    let csrf_token = csrf_token(session)?;
+    let mut lists = String::new();
+    for list in all_lists(pool).await.map_err(e500)? {
+        let checked = match &draft.lists {
+            Some(selected) => selected.contains(&list.slug),
+            None => list.slug == "default",
+        };
+        writeln!(
+            lists,
+            r#"
+                    <label>
+                        <input type="checkbox" name="lists" value="{slug}"{checked}>
+                        {name} ({members} confirmed)
+                    </label>
+                    <br>"#,
+            slug = list.slug,
+            checked = if checked { " checked" } else { "" },
+            name = encode_minimal(&list.name),
+            members = list.confirmed_members,
+        )
+        .unwrap();
+    }
+    let mut assets = String::new();
+    for asset in all_assets(pool).await.map_err(e500)? {
+        writeln!(
+            assets,
+            r#"
+                    <label>
+                        <input type="checkbox" name="assets" value="{name}"{checked}>
+                        {name} ({size} bytes)
+                    </label>
+                    <br>"#,
+            name = encode_minimal(&asset.name),
+            checked = if draft.assets.contains(&asset.name) {
+                " checked"
+            } else {
+                ""
+            },
+            size = asset.size,
+        )
+        .unwrap();
+    }
+    let title = encode_minimal(&draft.title);
+    let text_content = encode_minimal(&draft.text_content);
+    let html_content = encode_minimal(&draft.html_content);
+    let markdown_content = encode_minimal(&draft.markdown_content);
+    let segment = encode_minimal(&draft.segment);
+    let tracking = if draft.tracking { " checked" } else { "" };
     Ok(HttpResponse::Ok()
         .content_type(ContentType::html())
         .body(format!(
@@ -30,6 +114,7 @@ pub async fn publish_newsletter_form(
                             type="text"
                             placeholder="Enter the issue title"
                             name="title"
+                            value="{title}"
                         >
                     </label>
                     <br>
@@ -39,7 +124,7 @@ pub async fn publish_newsletter_form(
                             name="textContent"
                             rows="20"
                             cols="50"
-                        ></textarea>
+                        >{text_content}</textarea>
                     </label>
                     <br>
                     <label>HTML content:<br>
@@ -48,10 +133,42 @@ pub async fn publish_newsletter_form(
                             name="htmlContent"
                             rows="20"
                             cols="50"
-                        ></textarea>
+                        >{html_content}</textarea>
+                    </label>
+                    <br>
+                    <label>Or write Markdown instead, both bodies are generated from it:<br>
+                        <textarea
+                            placeholder="Enter the content in Markdown"
+                            name="markdownContent"
+                            rows="20"
+                            cols="50"
+                        >{markdown_content}</textarea>
+                    </label>
+                    <br>
+                    <p>All of them can use {{{{ name }}}}, {{{{ email }}}} and {{{{ unsubscribe_url }}}}, filled in for each subscriber.</p>
+                    <p>Attach (images shown with <code>&lt;img src="cid:name"&gt;</code> are attached anyway):</p>
+                    {assets}
+                    <p><a href="/admin/assets">Upload assets</a></p>
+                    <p>Send to:</p>
+                    {lists}
+                    <label>Only subscribers matching (optional):<br>
+                        <input
+                            type="text"
+                            placeholder="e.g. tag = beta and subscribed before 2026-01-01"
+                            name="segment"
+                            value="{segment}"
+                            size="60"
+                        >
+                    </label>
+                    <br>
+                    <label>
+                        <input type="checkbox" name="tracking" value="true"{tracking}>
+                        Track opens and clicks
                     </label>
                     <br>
                     <input hidden type="text" name="idempotencyKey" value="{idempotency_key}">
+                    <input hidden type="text" name="csrf_token" value="{csrf_token}">
+                    <button type="submit" formaction="/admin/newsletters/preview">Preview recipient count</button>
                     <button type="submit">Publish</button>
                 </form>
                 <p><a href="/admin/dashboard">&lt;- Back</a></p>
@@ -1,37 +1,162 @@
+use super::{render_newsletter_form, NewsletterDraft};
 use crate::{
+    assets::{attach_assets, resolve_issue_assets, AssetError},
     auth::UserId,
+    domain::ListSlug,
+    email_html::{prepare_html, HtmlError},
+    email_templates::validate_merge_fields,
     idempotency::{store_response, try_process, IdempotencyKey, NextAction},
+    lists::{resolve_lists, ListError},
+    markdown::render_markdown,
+    segments::{count_recipients, push_recipients, Segment},
     utils::{e400, e500, see_other},
-    Database, DbPool,
+    Database, DbPool, EmailClient, Session,
 };
 use actix_web::{
-    web::{Data, Form, ReqData},
+    web::{Data, ReqData},
     HttpResponse, ResponseError,
 };
 use actix_web_flash_messages::FlashMessage;
+use actix_web_lab::extract::UrlEncodedForm;
 use anyhow::Context;
+use htmlescape::encode_minimal;
 use serde::Deserialize;
-use sqlx::Transaction;
+use sqlx::{QueryBuilder, Transaction};
 use uuid::Uuid;
 
 #[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FormData {
     title: String,
+    #[serde(default)]
     text_content: String,
+    #[serde(default)]
     html_content: String,
+    #[serde(default)]
+    markdown_content: String,
     idempotency_key: String,
+    #[serde(default)]
+    lists: Vec<String>,
+    #[serde(default)]
+    segment: String,
+    /// Sent along with the issue, on top of the images it shows inline.
+    #[serde(default)]
+    assets: Vec<String>,
+    /// Records opens and clicks.
+    #[serde(default)]
+    tracking: bool,
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum PublishError {
+    #[error("{0}")]
+    Validation(String),
     #[error(transparent)]
     Unexpected(#[from] anyhow::Error),
 }
 
+impl From<ListError> for PublishError {
+    fn from(e: ListError) -> Self {
+        match e {
+            ListError::Unexpected(e) => Self::Unexpected(e),
+            e => Self::Validation(e.to_string()),
+        }
+    }
+}
+
+impl From<AssetError> for PublishError {
+    fn from(e: AssetError) -> Self {
+        match e {
+            AssetError::Unexpected(e) => Self::Unexpected(e),
+            e => Self::Validation(e.to_string()),
+        }
+    }
+}
+
 impl ResponseError for PublishError {
     fn status_code(&self) -> reqwest::StatusCode {
-        reqwest::StatusCode::INTERNAL_SERVER_ERROR
+        match self {
+            Self::Validation(_) => reqwest::StatusCode::BAD_REQUEST,
+            Self::Unexpected(_) => reqwest::StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+enum TargetError {
+    #[error("{0}")]
+    Validation(String),
+    #[error(transparent)]
+    Unexpected(#[from] anyhow::Error),
+}
+
+/// Who an issue goes to: members of any of the lists matching the segment.
+struct Target {
+    list_ids: Vec<Uuid>,
+    segment: Option<Segment>,
+}
+
+async fn resolve_target(
+    lists: &[String],
+    segment: &str,
+    pool: &DbPool,
+) -> Result<Target, TargetError> {
+    if lists.is_empty() {
+        return Err(TargetError::Validation(
+            "Please pick at least one list.".into(),
+        ));
+    }
+    let lists = lists
+        .iter()
+        .map(|list| ListSlug::try_from(list.clone()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| TargetError::Validation(encode_minimal(&e)))?;
+    let list_ids = match resolve_lists(&lists, pool).await {
+        Ok(list_ids) => list_ids,
+        Err(ListError::Unexpected(e)) => return Err(e.into()),
+        Err(e) => return Err(TargetError::Validation(e.to_string())),
+    };
+    let segment = if segment.trim().is_empty() {
+        None
+    } else {
+        let segment = Segment::parse(segment).map_err(|e| {
+            TargetError::Validation(encode_minimal(&format!(
+                "The segment is invalid: {e}"
+            )))
+        })?;
+        Some(segment)
+    };
+    Ok(Target { list_ids, segment })
+}
+
+/// The bodies of an issue, written by hand or generated from Markdown.
+/// The HTML body is sanitized and has its CSS inlined.
+pub struct IssueContent {
+    pub text_content: String,
+    pub html_content: String,
+    /// What the bodies were generated from, if anything.
+    pub markdown_content: Option<String>,
+}
+
+impl IssueContent {
+    pub fn new(
+        text_content: String,
+        html_content: String,
+    ) -> Result<Self, HtmlError> {
+        Ok(Self {
+            text_content,
+            html_content: prepare_html(&html_content)?,
+            markdown_content: None,
+        })
+    }
+
+    pub fn from_markdown(markdown_content: String) -> Result<Self, HtmlError> {
+        let rendered = render_markdown(&markdown_content);
+        Ok(Self {
+            text_content: rendered.text_content,
+            html_content: prepare_html(&rendered.html_content)?,
+            markdown_content: Some(markdown_content),
+        })
     }
 }
 
@@ -46,20 +171,74 @@ fn send_success_message() {
 )]
 pub async fn publish_newsletter(
     user_id: ReqData<UserId>,
-    form: Form<FormData>,
+    form: UrlEncodedForm<FormData>,
     pool: Data<DbPool>,
+    email_client: Data<EmailClient>,
 ) -> actix_web::Result<HttpResponse> {
     let user_id = *user_id.into_inner();
     tracing::Span::current()
-        .record("user_id", &tracing::field::display(&user_id));
+        .record("user_id", tracing::field::display(&user_id));
     let FormData {
         title,
         text_content,
         html_content,
+        markdown_content,
         idempotency_key,
+        lists,
+        segment,
+        assets,
+        tracking,
     } = form.0;
     let idempotency_key: IdempotencyKey =
         idempotency_key.try_into().map_err(e400)?;
+    let content = if markdown_content.trim().is_empty() {
+        IssueContent::new(text_content, html_content)
+    } else if text_content.is_empty() && html_content.is_empty() {
+        IssueContent::from_markdown(markdown_content)
+    } else {
+        FlashMessage::error(
+            "Fill in either the Markdown content or the plain text and HTML \
+            content, not both.",
+        )
+        .send();
+        return Ok(see_other("/admin/newsletters"));
+    };
+    let content = match content {
+        Ok(content) => content,
+        Err(e) => {
+            FlashMessage::error(encode_minimal(&e.to_string())).send();
+            return Ok(see_other("/admin/newsletters"));
+        }
+    };
+    if let Err(e) =
+        validate_merge_fields(&content.text_content, &content.html_content)
+    {
+        FlashMessage::error(encode_minimal(&e)).send();
+        return Ok(see_other("/admin/newsletters"));
+    }
+    let target = match resolve_target(&lists, &segment, &pool).await {
+        Ok(target) => target,
+        Err(TargetError::Unexpected(e)) => return Err(e500(e)),
+        Err(e) => {
+            FlashMessage::error(e.to_string()).send();
+            return Ok(see_other("/admin/newsletters"));
+        }
+    };
+    let assets = match resolve_issue_assets(
+        &assets,
+        &content.html_content,
+        email_client.max_attachments_size(),
+        &pool,
+    )
+    .await
+    {
+        Ok(assets) => assets,
+        Err(AssetError::Unexpected(e)) => return Err(e500(e)),
+        Err(e) => {
+            FlashMessage::error(encode_minimal(&e.to_string())).send();
+            return Ok(see_other("/admin/newsletters"));
+        }
+    };
     let asdf = try_process(&user_id, &idempotency_key, &pool)
         .await
         .map_err(e500)?;
@@ -70,19 +249,23 @@ pub async fn publish_newsletter(
             return Ok(response);
         }
     };
-    let issue_id = insert_newsletter_issues(
-        &title,
-        &text_content,
-        &html_content,
+    let issue_id =
+        insert_newsletter_issues(&title, &content, tracking, &mut transaction)
+            .await
+            .context("Failed to store newsletter issue details")
+            .map_err(e500)?;
+    attach_assets(&issue_id, &assets, &mut transaction)
+        .await
+        .map_err(e500)?;
+    enqueue_delivery_tasks(
+        &issue_id,
+        &target.list_ids,
+        target.segment.as_ref(),
         &mut transaction,
     )
     .await
-    .context("Failed to store newsletter issue details")
+    .context("Failed to enqueue delivery tasks")
     .map_err(e500)?;
-    enqueue_delivery_tasks(&issue_id, &mut transaction)
-        .await
-        .context("Failed to enqueue delivery tasks")
-        .map_err(e500)?;
     let response = see_other("/admin/newsletters");
     let response =
         store_response(&user_id, &idempotency_key, response, transaction)
@@ -92,11 +275,59 @@ pub async fn publish_newsletter(
     Ok(response)
 }
 
+/// Shows the form again, as filled in, with how many subscribers the
+/// issue would reach.
+#[tracing::instrument(name = "Previewing newsletter recipients", skip_all)]
+pub async fn preview_recipients(
+    form: UrlEncodedForm<FormData>,
+    session: Session,
+    pool: Data<DbPool>,
+) -> actix_web::Result<HttpResponse> {
+    let FormData {
+        title,
+        text_content,
+        html_content,
+        markdown_content,
+        idempotency_key,
+        lists,
+        segment,
+        assets,
+        tracking,
+    } = form.0;
+    let notice = match resolve_target(&lists, &segment, &pool).await {
+        Ok(target) => {
+            let recipients = count_recipients(
+                &target.list_ids,
+                target.segment.as_ref(),
+                &pool,
+            )
+            .await
+            .map_err(e500)?;
+            format!("This issue would reach {recipients} subscribers.")
+        }
+        Err(TargetError::Unexpected(e)) => return Err(e500(e)),
+        Err(e) => e.to_string(),
+    };
+    let draft = NewsletterDraft {
+        title,
+        text_content,
+        html_content,
+        markdown_content,
+        idempotency_key: Some(idempotency_key),
+        lists: Some(lists),
+        segment,
+        assets,
+        tracking,
+    };
+    let msgs = format!("<p><i>{notice}</i></p>");
+    render_newsletter_form(&draft, &msgs, &session, &pool).await
+}
+
 #[tracing::instrument(skip_all)]
-async fn insert_newsletter_issues(
+pub async fn insert_newsletter_issues(
     title: &str,
-    text_content: &str,
-    html_content: &str,
+    content: &IssueContent,
+    tracking: bool,
     transaction: &mut Transaction<'_, Database>,
 ) -> sqlx::Result<Uuid> {
     let issue_id = Uuid::new_v4();
@@ -107,38 +338,38 @@ async fn insert_newsletter_issues(
            title,
            text_content,
            html_content,
+           markdown_content,
+           tracking,
            published_at
         )
-        values ($1, $2, $3, $4, now());
+        values ($1, $2, $3, $4, $5, $6, now());
         "#,
         issue_id,
         title,
-        text_content,
-        html_content,
+        content.text_content,
+        content.html_content,
+        content.markdown_content,
+        tracking,
     )
     .execute(transaction)
     .await
     .map(|_| issue_id)
 }
 
+/// Queues the issue once for every confirmed member of any of the lists
+/// who matches the segment.
 #[tracing::instrument(skip_all)]
-async fn enqueue_delivery_tasks(
+pub async fn enqueue_delivery_tasks(
     newsletter_issue_id: &Uuid,
+    list_ids: &[Uuid],
+    segment: Option<&Segment>,
     transaction: &mut Transaction<'_, Database>,
 ) -> sqlx::Result<()> {
-    sqlx::query!(
-        r#"
-        insert into issue_delivery_queue(
-           newsletter_issue_id,
-           subscriber_email
-        )
-        select $1, email
-        from subscriptions
-        where status = 'confirmed';
-        "#,
-        newsletter_issue_id
-    )
-    .execute(transaction)
-    .await
-    .map(|_| ())
+    let mut builder = QueryBuilder::new(
+        "insert into issue_delivery_queue(newsletter_issue_id, \
+        subscriber_email) select ",
+    );
+    builder.push_bind(*newsletter_issue_id).push(", s.email");
+    push_recipients(&mut builder, list_ids, segment);
+    builder.build().execute(transaction).await.map(|_| ())
 }
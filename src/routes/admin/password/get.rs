@@ -1,10 +1,13 @@
+use crate::{auth::csrf_token, Session};
 use actix_web::{http::header::ContentType, HttpResponse};
 use actix_web_flash_messages::IncomingFlashMessages;
 use std::fmt::Write;
 
 pub async fn change_password_form(
     flash_messages: IncomingFlashMessages,
+    session: Session,
 ) -> actix_web::Result<HttpResponse> {
+    let csrf_token = csrf_token(&session)?;
     let mut msgs = String::new();
     for m in flash_messages.iter() {
         writeln!(msgs, "<p><i>{}</i></p>", m.content()).unwrap()
@@ -46,6 +49,7 @@ pub async fn change_password_form(
                         >
                     </label>
                     <br>
+                    <input hidden type="text" name="csrf_token" value="{csrf_token}">
                     <button type="submit">Change password</button>
             </form>
                 <p><a href="/admin/dashboard">&lt;- Back</a></p>
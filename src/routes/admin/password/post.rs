@@ -1,8 +1,11 @@
 use crate::{
-    auth::{self, validate_credentials, AuthError, Credentials, UserId},
+    auth::{
+        self, revoke_other_sessions, validate_credentials, AuthError,
+        Credentials, UserId,
+    },
     routes::get_username,
     utils::{e500, see_other},
-    DbPool,
+    DbPool, Session,
 };
 
 use actix_web::{
@@ -24,6 +27,7 @@ pub async fn change_password(
     user_id: ReqData<UserId>,
     form: Form<FormData>,
     pool: Data<DbPool>,
+    session: Session,
 ) -> actix_web::Result<HttpResponse> {
     let user_id = *user_id.into_inner();
     if form.new_password.expose_secret()
@@ -54,6 +58,11 @@ pub async fn change_password(
     auth::change_password(&user_id, form.0.new_password, &pool)
         .await
         .map_err(e500)?;
+    if let Some(session_id) = session.get_session_id()? {
+        revoke_other_sessions(&user_id, &session_id, &pool)
+            .await
+            .map_err(e500)?;
+    }
     FlashMessage::info("Your password has been changed.").send();
     Ok(see_other("/admin/password"))
 }
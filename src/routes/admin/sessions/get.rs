@@ -0,0 +1,96 @@
+use crate::{
+    auth::{csrf_token, list_sessions, prune_sessions, UserId},
+    configuration::SessionConfig,
+    utils::e500,
+    DbPool, Session,
+};
+use actix_web::{
+    http::header::ContentType,
+    web::{Data, ReqData},
+    HttpResponse,
+};
+use actix_web_flash_messages::IncomingFlashMessages;
+use htmlescape::encode_minimal;
+use std::fmt::Write;
+
+pub async fn active_sessions(
+    user_id: ReqData<UserId>,
+    session: Session,
+    pool: Data<DbPool>,
+    session_config: Data<SessionConfig>,
+    flash_messages: IncomingFlashMessages,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = *user_id.into_inner();
+    let current_session_id = session.get_session_id()?;
+    let csrf_token = csrf_token(&session)?;
+    prune_sessions(&user_id, &session_config, &pool)
+        .await
+        .map_err(e500)?;
+    let sessions = list_sessions(&user_id, &pool).await.map_err(e500)?;
+    let mut msgs = String::new();
+    for m in flash_messages.iter() {
+        writeln!(msgs, "<p><i>{}</i></p>", m.content()).unwrap();
+    }
+    let mut rows = String::new();
+    for s in sessions {
+        let current = if Some(s.session_id) == current_session_id {
+            " (current)"
+        } else {
+            ""
+        };
+        writeln!(
+            rows,
+            r#"
+                    <tr>
+                        <td>{created_at}{current}</td>
+                        <td>{last_active_at}</td>
+                        <td>{ip_address}</td>
+                        <td>{user_agent}</td>
+                        <td>
+                            <form action="/admin/sessions/revoke" method="post">
+                                <input hidden type="text" name="session_id" value="{session_id}">
+                                <input hidden type="text" name="csrf_token" value="{csrf_token}">
+                                <button type="submit">Revoke</button>
+                            </form>
+                        </td>
+                    </tr>"#,
+            created_at = s.created_at.format("%Y-%m-%d %H:%M:%S UTC"),
+            last_active_at = s.last_active_at.format("%Y-%m-%d %H:%M:%S UTC"),
+            ip_address = encode_minimal(s.ip_address.as_deref().unwrap_or("")),
+            user_agent = encode_minimal(s.user_agent.as_deref().unwrap_or("")),
+            session_id = s.session_id,
+        )
+        .unwrap();
+    }
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(format!(
+            r#"
+            <!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Active sessions</title>
+            </head>
+            <body>
+                {msgs}
+                <table>
+                    <tr>
+                        <th>Created</th>
+                        <th>Last activity</th>
+                        <th>IP address</th>
+                        <th>User agent</th>
+                        <th></th>
+                    </tr>
+                    {rows}
+                </table>
+                <form action="/admin/sessions/revoke_all" method="post">
+                    <input hidden type="text" name="csrf_token" value="{csrf_token}">
+                    <button type="submit">Log out everywhere</button>
+                </form>
+                <p><a href="/admin/dashboard">&lt;- Back</a></p>
+            </body>
+            </html>
+            "#,
+        )))
+}
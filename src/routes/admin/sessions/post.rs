@@ -0,0 +1,50 @@
+use crate::{
+    auth::{revoke_all_sessions, revoke_session, UserId},
+    utils::{e500, see_other},
+    DbPool, Session,
+};
+use actix_web::{
+    web::{Data, Form, ReqData},
+    HttpResponse,
+};
+use actix_web_flash_messages::FlashMessage;
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct FormData {
+    session_id: Uuid,
+}
+
+#[tracing::instrument(name = "Revoke a session", skip_all)]
+pub async fn revoke_active_session(
+    user_id: ReqData<UserId>,
+    form: Form<FormData>,
+    session: Session,
+    pool: Data<DbPool>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = *user_id.into_inner();
+    revoke_session(&user_id, &form.session_id, &pool)
+        .await
+        .map_err(e500)?;
+    if session.get_session_id()? == Some(form.session_id) {
+        session.logout();
+        FlashMessage::info("You have successfully logged out.").send();
+        return Ok(see_other("/login"));
+    }
+    FlashMessage::info("The session has been revoked.").send();
+    Ok(see_other("/admin/sessions"))
+}
+
+#[tracing::instrument(name = "Revoke all sessions", skip_all)]
+pub async fn revoke_all_active_sessions(
+    user_id: ReqData<UserId>,
+    session: Session,
+    pool: Data<DbPool>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = *user_id.into_inner();
+    revoke_all_sessions(&user_id, &pool).await.map_err(e500)?;
+    session.logout();
+    FlashMessage::info("You have been logged out everywhere.").send();
+    Ok(see_other("/login"))
+}
@@ -0,0 +1,140 @@
+use crate::{
+    tracking::{issue_stats, tracked_issues},
+    utils::e500,
+    DbPool,
+};
+use actix_web::{
+    http::header::ContentType,
+    web::{Data, Path},
+    HttpResponse,
+};
+use htmlescape::encode_minimal;
+use std::fmt::Write;
+use uuid::Uuid;
+
+/// The issues sent with tracking turned on.
+pub async fn newsletter_stats(
+    pool: Data<DbPool>,
+) -> actix_web::Result<HttpResponse> {
+    let mut rows = String::new();
+    for issue in tracked_issues(&pool).await.map_err(e500)? {
+        writeln!(
+            rows,
+            r#"
+                    <tr>
+                        <td><a href="/admin/stats/{id}">{title}</a></td>
+                        <td>{published_at}</td>
+                        <td>{recipients}</td>
+                        <td>{unique_opens}</td>
+                        <td>{unique_clicks}</td>
+                    </tr>"#,
+            id = issue.newsletter_issue_id,
+            title = encode_minimal(&issue.title),
+            published_at = issue.published_at.format("%Y-%m-%d %H:%M"),
+            recipients = issue.recipients,
+            unique_opens = issue.unique_opens,
+            unique_clicks = issue.unique_clicks,
+        )
+        .unwrap();
+    }
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(format!(
+            r#"
+            <!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Newsletter stats</title>
+            </head>
+            <body>
+                <p>Issues published with open and click tracking:</p>
+                <table>
+                    <tr>
+                        <th>Issue</th>
+                        <th>Published</th>
+                        <th>Recipients</th>
+                        <th>Opened by</th>
+                        <th>Clicked by</th>
+                    </tr>
+                    {rows}
+                </table>
+                <p><a href="/admin/dashboard">&lt;- Back</a></p>
+            </body>
+            </html>
+            "#,
+        )))
+}
+
+pub async fn newsletter_issue_stats(
+    newsletter_issue_id: Path<Uuid>,
+    pool: Data<DbPool>,
+) -> actix_web::Result<HttpResponse> {
+    let stats = match issue_stats(&newsletter_issue_id, &pool)
+        .await
+        .map_err(e500)?
+    {
+        Some(stats) => stats,
+        None => return Ok(HttpResponse::NotFound().finish()),
+    };
+    let mut links = String::new();
+    for link in &stats.links {
+        writeln!(
+            links,
+            r#"
+                    <tr>
+                        <td>{url}</td>
+                        <td>{clicks}</td>
+                        <td>{unique_clicks}</td>
+                    </tr>"#,
+            url = encode_minimal(&link.url),
+            clicks = link.clicks,
+            unique_clicks = link.unique_clicks,
+        )
+        .unwrap();
+    }
+    let notice = if stats.tracking {
+        ""
+    } else {
+        "<p><i>This issue was published without tracking.</i></p>"
+    };
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(format!(
+            r#"
+            <!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Newsletter stats</title>
+            </head>
+            <body>
+                <h1>{title}</h1>
+                <p>Published {published_at}.</p>
+                {notice}
+                <ul>
+                    <li>Recipients: {recipients}</li>
+                    <li>Opens: {opens}, by {unique_opens} subscribers</li>
+                    <li>Clicks: {clicks}, by {unique_clicks} subscribers</li>
+                </ul>
+                <table>
+                    <tr>
+                        <th>Link</th>
+                        <th>Clicks</th>
+                        <th>Clicked by</th>
+                    </tr>
+                    {links}
+                </table>
+                <p><a href="/admin/stats">&lt;- Back</a></p>
+            </body>
+            </html>
+            "#,
+            title = encode_minimal(&stats.title),
+            published_at = stats.published_at.format("%Y-%m-%d %H:%M"),
+            recipients = stats.recipients,
+            opens = stats.opens,
+            unique_opens = stats.unique_opens,
+            clicks = stats.clicks,
+            unique_clicks = stats.unique_clicks,
+        )))
+}
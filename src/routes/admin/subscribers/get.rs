@@ -0,0 +1,186 @@
+use crate::{
+    auth::csrf_token,
+    domain::SubscriberStatus,
+    subscribers::{list_subscribers, Cursor, SubscriberFilter},
+    utils::{e400, e500},
+    DbPool, Session,
+};
+use actix_web::{
+    http::header::ContentType,
+    web::{Data, Query},
+    HttpResponse,
+};
+use actix_web_flash_messages::IncomingFlashMessages;
+use htmlescape::encode_minimal;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+const PAGE_SIZE: i64 = 25;
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct QueryParams {
+    search: Option<String>,
+    status: Option<String>,
+    cursor: Option<String>,
+}
+
+pub async fn subscribers(
+    query: Query<QueryParams>,
+    session: Session,
+    pool: Data<DbPool>,
+    flash_messages: IncomingFlashMessages,
+) -> actix_web::Result<HttpResponse> {
+    let csrf_token = csrf_token(&session)?;
+    let QueryParams {
+        search,
+        status,
+        cursor,
+    } = query.into_inner();
+    let search = search.filter(|s| !s.trim().is_empty());
+    let status = status.filter(|s| !s.is_empty());
+    let filter = SubscriberFilter {
+        search: search.as_ref().map(|s| s.trim().to_owned()),
+        status: status
+            .clone()
+            .map(SubscriberStatus::try_from)
+            .transpose()
+            .map_err(e400)?,
+        after: cursor.map(Cursor::try_from).transpose().map_err(e400)?,
+    };
+    let page = list_subscribers(&filter, PAGE_SIZE, &pool)
+        .await
+        .map_err(e500)?;
+
+    let mut msgs = String::new();
+    for m in flash_messages.iter() {
+        writeln!(msgs, "<p><i>{}</i></p>", m.content()).unwrap();
+    }
+    let mut status_options = String::from(r#"<option value="">All</option>"#);
+    for s in SubscriberStatus::ALL {
+        let selected = if filter.status == Some(s) {
+            " selected"
+        } else {
+            ""
+        };
+        write!(
+            status_options,
+            r#"<option value="{s}"{selected}>{s}</option>"#
+        )
+        .unwrap();
+    }
+    let mut rows = String::new();
+    for s in page.subscribers {
+        let mut actions = String::new();
+        if s.status == SubscriberStatus::PendingConfirmation {
+            actions.push_str(&action_form(
+                "resend_confirmation",
+                "Resend confirmation",
+                &s.id.to_string(),
+                &csrf_token,
+            ));
+        }
+        if matches!(
+            s.status,
+            SubscriberStatus::PendingConfirmation | SubscriberStatus::Confirmed
+        ) {
+            actions.push_str(&action_form(
+                "unsubscribe",
+                "Unsubscribe",
+                &s.id.to_string(),
+                &csrf_token,
+            ));
+        }
+        actions.push_str(&action_form(
+            "delete",
+            "Delete",
+            &s.id.to_string(),
+            &csrf_token,
+        ));
+        writeln!(
+            rows,
+            r#"
+                    <tr>
+                        <td>{name}</td>
+                        <td>{email}</td>
+                        <td>{status}</td>
+                        <td>{subscribed_at}</td>
+                        <td>{actions}</td>
+                    </tr>"#,
+            name = encode_minimal(s.name.as_ref()),
+            email = encode_minimal(s.email.as_ref()),
+            status = s.status,
+            subscribed_at = s.subscribed_at.format("%Y-%m-%d %H:%M:%S UTC"),
+        )
+        .unwrap();
+    }
+    let next_page = match page.next {
+        Some(next) => {
+            let params = QueryParams {
+                search: search.clone(),
+                status,
+                cursor: Some(next.to_string()),
+            };
+            let query = serde_urlencoded::to_string(params).map_err(e500)?;
+            format!(
+                r#"<p><a href="/admin/subscribers?{}">Next page -&gt;</a></p>"#,
+                encode_minimal(&query)
+            )
+        }
+        None => String::new(),
+    };
+    let search = encode_minimal(search.as_deref().unwrap_or(""));
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(format!(
+            r#"
+            <!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Subscribers</title>
+            </head>
+            <body>
+                {msgs}
+                <form action="/admin/subscribers" method="get">
+                    <label>Search
+                        <input type="text" placeholder="Email or name" name="search" value="{search}">
+                    </label>
+                    <label>Status
+                        <select name="status">{status_options}</select>
+                    </label>
+                    <button type="submit">Filter</button>
+                </form>
+                <table>
+                    <tr>
+                        <th>Name</th>
+                        <th>Email</th>
+                        <th>Status</th>
+                        <th>Subscribed</th>
+                        <th></th>
+                    </tr>
+                    {rows}
+                </table>
+                {next_page}
+                <p><a href="/admin/subscribers/import">Import from CSV</a></p>
+                <p><a href="/admin/dashboard">&lt;- Back</a></p>
+            </body>
+            </html>
+            "#,
+        )))
+}
+
+fn action_form(
+    action: &str,
+    label: &str,
+    subscriber_id: &str,
+    csrf_token: &str,
+) -> String {
+    format!(
+        r#"
+                            <form action="/admin/subscribers/{action}" method="post">
+                                <input hidden type="text" name="subscriber_id" value="{subscriber_id}">
+                                <input hidden type="text" name="csrf_token" value="{csrf_token}">
+                                <button type="submit">{label}</button>
+                            </form>"#
+    )
+}
@@ -0,0 +1,69 @@
+use crate::{auth::csrf_token, lists::all_lists, utils::e500, DbPool, Session};
+use actix_web::{http::header::ContentType, web::Data, HttpResponse};
+use actix_web_flash_messages::IncomingFlashMessages;
+use htmlescape::encode_minimal;
+use std::fmt::Write;
+
+pub async fn import_subscribers_form(
+    session: Session,
+    pool: Data<DbPool>,
+    flash_messages: IncomingFlashMessages,
+) -> actix_web::Result<HttpResponse> {
+    let csrf_token = csrf_token(&session)?;
+    let mut msgs = String::new();
+    for m in flash_messages.iter() {
+        writeln!(msgs, "<p><i>{}</i></p>", m.content()).unwrap();
+    }
+    let mut list_options = String::new();
+    for list in all_lists(&pool).await.map_err(e500)? {
+        let selected = if list.slug == "default" {
+            " selected"
+        } else {
+            ""
+        };
+        write!(
+            list_options,
+            r#"<option value="{slug}"{selected}>{name}</option>"#,
+            slug = list.slug,
+            name = encode_minimal(&list.name),
+        )
+        .unwrap();
+    }
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(format!(
+            r#"
+            <!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Import subscribers</title>
+            </head>
+            <body>
+                {msgs}
+                <p>Upload a CSV file with one <code>name,email[,status]</code> line per subscriber.</p>
+                <form action="/admin/subscribers/import" method="post" enctype="multipart/form-data">
+                    <input hidden type="text" name="csrf_token" value="{csrf_token}">
+                    <label>CSV file
+                        <input type="file" accept=".csv,text/csv" name="file">
+                    </label>
+                    <br>
+                    <label>Add to list
+                        <select name="list">{list_options}</select>
+                    </label>
+                    <br>
+                    <label>
+                        <input type="checkbox" name="send_confirmation">
+                        Send a confirmation email to subscribers pending confirmation
+                    </label>
+                    <br>
+                    <small>Subscribers imported into a single opt-in list are confirmed right away.</small>
+                    <br>
+                    <button type="submit">Import</button>
+                </form>
+                <p><a href="/admin/subscribers">&lt;- Back</a></p>
+            </body>
+            </html>
+            "#,
+        )))
+}
@@ -0,0 +1,232 @@
+use crate::{
+    auth::check_csrf_field,
+    domain::{ListSlug, NewSubscriber, OptInMode, SubscriberStatus},
+    lists::{list_opt_in, resolve_lists, ListError},
+    routes::{
+        generate_subscription_token, send_confirmation_email, store_token,
+    },
+    server::AppBaseUrl,
+    subscribers::{import_subscribers, parse_subscribers_csv, ImportOutcome},
+    utils::{e500, see_other},
+    DbPool, EmailClient, EmailTemplates, Locales, Session,
+};
+use actix_multipart::Multipart;
+use actix_web::{http::header::ContentType, web::Data, HttpResponse};
+use actix_web_flash_messages::FlashMessage;
+use anyhow::Context;
+use futures_util::TryStreamExt;
+use htmlescape::encode_minimal;
+use std::fmt::Write;
+use uuid::Uuid;
+
+const MAX_FILE_SIZE: usize = 5 * 1024 * 1024;
+
+#[tracing::instrument(name = "Import subscribers from CSV", skip_all)]
+#[allow(clippy::too_many_arguments)]
+pub async fn import_subscribers_csv(
+    mut payload: Multipart,
+    session: Session,
+    pool: Data<DbPool>,
+    email_client: Data<EmailClient>,
+    templates: Data<EmailTemplates>,
+    locales: Data<Locales>,
+    base_url: Data<AppBaseUrl>,
+    default_opt_in: Data<OptInMode>,
+) -> actix_web::Result<HttpResponse> {
+    let mut csrf_token = Vec::new();
+    let mut data = Vec::new();
+    let mut send_confirmation = false;
+    let mut list = Vec::new();
+    while let Some(mut field) = payload.try_next().await? {
+        let name = field.name().map(String::from);
+        if name.as_deref() == Some("file") {
+            // The token comes first, no need to read files without it.
+            check_csrf_field(&session, &csrf_token)?;
+        }
+        while let Some(chunk) = field.try_next().await? {
+            match name.as_deref() {
+                Some("csrf_token") => csrf_token.extend_from_slice(&chunk),
+                Some("file") => {
+                    if data.len() + chunk.len() > MAX_FILE_SIZE {
+                        FlashMessage::error(
+                            "The file is too large. The limit is 5 MB.",
+                        )
+                        .send();
+                        return Ok(see_other("/admin/subscribers/import"));
+                    }
+                    data.extend_from_slice(&chunk);
+                }
+                Some("send_confirmation") => send_confirmation = true,
+                Some("list") => list.extend_from_slice(&chunk),
+                _ => {}
+            }
+        }
+    }
+    check_csrf_field(&session, &csrf_token)?;
+    if data.is_empty() {
+        FlashMessage::error("Please choose a non-empty CSV file to import.")
+            .send();
+        return Ok(see_other("/admin/subscribers/import"));
+    }
+
+    let list = match String::from_utf8(list) {
+        Ok(list) if !list.is_empty() => ListSlug::try_from(list).ok(),
+        Ok(_) => Some(ListSlug::default_list()),
+        Err(_) => None,
+    };
+    let list_id = match list {
+        Some(list) => match resolve_lists(&[list], &pool).await {
+            Ok(list_ids) => list_ids[0],
+            Err(ListError::Unexpected(e)) => return Err(e500(e)),
+            Err(e) => {
+                FlashMessage::error(e.to_string()).send();
+                return Ok(see_other("/admin/subscribers/import"));
+            }
+        },
+        None => {
+            FlashMessage::error("Please pick a valid list.").send();
+            return Ok(see_other("/admin/subscribers/import"));
+        }
+    };
+
+    let opt_in = list_opt_in(&list_id, &pool)
+        .await
+        .map_err(e500)?
+        .unwrap_or(**default_opt_in);
+    let mut rows = parse_subscribers_csv(&data);
+    if opt_in == OptInMode::Single {
+        // Nobody joining a single opt-in list waits for confirmation.
+        for imported in rows.iter_mut().filter_map(|(_, i)| i.as_mut()) {
+            if imported.status == SubscriberStatus::PendingConfirmation {
+                imported.status = SubscriberStatus::Confirmed;
+            }
+        }
+    }
+    // There is nothing to negotiate the language of imported subscribers with.
+    let locale = locales.default_locale();
+    let pending = import_subscribers(&mut rows, &list_id, &locale, &pool)
+        .await
+        .map_err(e500)?;
+    let mut failed_emails = 0;
+    if send_confirmation {
+        for (subscriber_id, subscriber) in pending {
+            if let Err(e) = confirm_imported_subscriber(
+                &subscriber_id,
+                &subscriber,
+                &pool,
+                &email_client,
+                &templates,
+                &locale,
+                &base_url,
+            )
+            .await
+            {
+                tracing::warn!(
+                    error.cause_chain = ?e,
+                    %subscriber_id,
+                    "Failed to send a confirmation email to an imported subscriber",
+                );
+                failed_emails += 1;
+            }
+        }
+    }
+
+    let accepted = rows
+        .iter()
+        .filter(|(row, _)| row.outcome == ImportOutcome::Accepted)
+        .count();
+    let skipped = rows
+        .iter()
+        .filter(|(row, _)| row.outcome == ImportOutcome::Skipped)
+        .count();
+    let mut msgs =
+        format!("<p>Imported {accepted} of {} lines.</p>", rows.len());
+    if skipped > 0 {
+        write!(msgs, "<p>Skipped {skipped} already subscribed.</p>").unwrap();
+    }
+    if failed_emails > 0 {
+        write!(
+            msgs,
+            "<p><i>{failed_emails} confirmation emails could not be sent.</i></p>"
+        )
+        .unwrap();
+    }
+    let mut report = String::new();
+    for (row, _) in &rows {
+        let outcome = match &row.outcome {
+            ImportOutcome::Accepted => "Accepted".to_owned(),
+            ImportOutcome::Rejected(reason) => {
+                format!("Rejected: {}", encode_minimal(reason))
+            }
+            ImportOutcome::Skipped => "Skipped: Already subscribed".to_owned(),
+        };
+        writeln!(
+            report,
+            r#"
+                    <tr>
+                        <td>{line}</td>
+                        <td>{email}</td>
+                        <td>{outcome}</td>
+                    </tr>"#,
+            line = row.line,
+            email = encode_minimal(&row.email),
+        )
+        .unwrap();
+    }
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(format!(
+            r#"
+            <!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Import report</title>
+            </head>
+            <body>
+                {msgs}
+                <table>
+                    <tr>
+                        <th>Line</th>
+                        <th>Email</th>
+                        <th>Result</th>
+                    </tr>
+                    {report}
+                </table>
+                <p><a href="/admin/subscribers/import">Import another file</a></p>
+                <p><a href="/admin/subscribers">&lt;- Back</a></p>
+            </body>
+            </html>
+            "#,
+        )))
+}
+
+async fn confirm_imported_subscriber(
+    subscriber_id: &Uuid,
+    subscriber: &NewSubscriber,
+    pool: &DbPool,
+    email_client: &EmailClient,
+    templates: &EmailTemplates,
+    locale: &str,
+    base_url: &AppBaseUrl,
+) -> anyhow::Result<()> {
+    let subscription_token = generate_subscription_token();
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire connection from the pool")?;
+    store_token(&mut transaction, subscriber_id, &subscription_token).await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit transaction")?;
+    send_confirmation_email(
+        email_client,
+        templates,
+        subscriber,
+        locale,
+        base_url.as_ref(),
+        &subscription_token,
+    )
+    .await
+}
@@ -0,0 +1,7 @@
+mod get;
+mod import;
+mod post;
+
+pub use get::*;
+pub use import::*;
+pub use post::*;
@@ -0,0 +1,135 @@
+use crate::{
+    domain::{NewSubscriber, SubscriberStatus},
+    routes::{
+        generate_subscription_token, send_confirmation_email, store_token,
+    },
+    server::AppBaseUrl,
+    subscribers::{
+        delete_subscriber, get_subscriber, update_subscriber_status,
+    },
+    utils::{e500, see_other},
+    DbPool, EmailClient, EmailTemplates,
+};
+use actix_web::{
+    web::{Data, Form},
+    HttpResponse,
+};
+use actix_web_flash_messages::FlashMessage;
+use anyhow::Context;
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct FormData {
+    subscriber_id: Uuid,
+}
+
+#[tracing::instrument(
+    name = "Resend a confirmation email",
+    skip_all,
+    fields(subscriber_id = %form.subscriber_id)
+)]
+pub async fn resend_confirmation(
+    form: Form<FormData>,
+    pool: Data<DbPool>,
+    email_client: Data<EmailClient>,
+    templates: Data<EmailTemplates>,
+    base_url: Data<AppBaseUrl>,
+) -> actix_web::Result<HttpResponse> {
+    let subscriber = match get_subscriber(&form.subscriber_id, &pool)
+        .await
+        .map_err(e500)?
+    {
+        Some(subscriber) => subscriber,
+        None => {
+            FlashMessage::error("The subscriber does not exist.").send();
+            return Ok(see_other("/admin/subscribers"));
+        }
+    };
+    if subscriber.status != SubscriberStatus::PendingConfirmation {
+        FlashMessage::error(
+            "Only subscribers pending confirmation can be sent a \
+            confirmation email.",
+        )
+        .send();
+        return Ok(see_other("/admin/subscribers"));
+    }
+    let subscription_token = generate_subscription_token();
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire connection from the pool")
+        .map_err(e500)?;
+    store_token(&mut transaction, &subscriber.id, &subscription_token)
+        .await
+        .map_err(e500)?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit transaction")
+        .map_err(e500)?;
+    let new_subscriber = NewSubscriber {
+        name: subscriber.name,
+        email: subscriber.email,
+    };
+    send_confirmation_email(
+        &email_client,
+        &templates,
+        &new_subscriber,
+        &subscriber.locale,
+        base_url.as_ref().as_ref(),
+        &subscription_token,
+    )
+    .await
+    .map_err(e500)?;
+    FlashMessage::info(format!(
+        "A confirmation email has been sent to {}.",
+        new_subscriber.email.as_ref()
+    ))
+    .send();
+    Ok(see_other("/admin/subscribers"))
+}
+
+#[tracing::instrument(
+    name = "Unsubscribe a subscriber",
+    skip_all,
+    fields(subscriber_id = %form.subscriber_id)
+)]
+pub async fn unsubscribe_subscriber(
+    form: Form<FormData>,
+    pool: Data<DbPool>,
+) -> actix_web::Result<HttpResponse> {
+    let updated = update_subscriber_status(
+        &form.subscriber_id,
+        SubscriberStatus::Unsubscribed,
+        &pool,
+    )
+    .await
+    .map_err(e500)?;
+    if updated {
+        FlashMessage::info("The subscriber has been unsubscribed.").send();
+    } else {
+        FlashMessage::error("The subscriber does not exist.").send();
+    }
+    Ok(see_other("/admin/subscribers"))
+}
+
+#[tracing::instrument(
+    name = "Delete a subscriber",
+    skip_all,
+    fields(subscriber_id = %form.subscriber_id)
+)]
+pub async fn remove_subscriber(
+    form: Form<FormData>,
+    pool: Data<DbPool>,
+) -> actix_web::Result<HttpResponse> {
+    let deleted = delete_subscriber(&form.subscriber_id, &pool)
+        .await
+        .map_err(e500)?;
+    if deleted {
+        FlashMessage::info("The subscriber has been deleted.").send();
+    } else {
+        FlashMessage::error("The subscriber does not exist.").send();
+    }
+    Ok(see_other("/admin/subscribers"))
+}
@@ -0,0 +1,75 @@
+use crate::{auth::csrf_token, tags::all_tags, utils::e500, DbPool, Session};
+use actix_web::{http::header::ContentType, web::Data, HttpResponse};
+use actix_web_flash_messages::IncomingFlashMessages;
+use std::fmt::Write;
+
+pub async fn tags(
+    session: Session,
+    pool: Data<DbPool>,
+    flash_messages: IncomingFlashMessages,
+) -> actix_web::Result<HttpResponse> {
+    let csrf_token = csrf_token(&session)?;
+    let mut msgs = String::new();
+    for m in flash_messages.iter() {
+        writeln!(msgs, "<p><i>{}</i></p>", m.content()).unwrap();
+    }
+    let mut rows = String::new();
+    for tag in all_tags(&pool).await.map_err(e500)? {
+        writeln!(
+            rows,
+            r#"
+                    <tr>
+                        <td>{tag}</td>
+                        <td>{subscribers}</td>
+                        <td>
+                            <form action="/admin/tags/delete" method="post">
+                                <input hidden type="text" name="tag" value="{tag}">
+                                <input hidden type="text" name="csrf_token" value="{csrf_token}">
+                                <button type="submit">Delete</button>
+                            </form>
+                        </td>
+                    </tr>"#,
+            tag = tag.tag,
+            subscribers = tag.subscribers,
+        )
+        .unwrap();
+    }
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(format!(
+            r#"
+            <!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Tags</title>
+            </head>
+            <body>
+                {msgs}
+                <table>
+                    <tr>
+                        <th>Tag</th>
+                        <th>Subscribers</th>
+                        <th></th>
+                    </tr>
+                    {rows}
+                </table>
+                <form action="/admin/tags/add" method="post">
+                    <label>Tag
+                        <input type="text" placeholder="e.g. beta" name="tag">
+                    </label>
+                    <br>
+                    <label>Subscriber emails, one per line:<br>
+                        <textarea name="emails" rows="10" cols="50"></textarea>
+                    </label>
+                    <br>
+                    <input hidden type="text" name="csrf_token" value="{csrf_token}">
+                    <button type="submit">Add tag</button>
+                    <button type="submit" formaction="/admin/tags/remove">Remove tag</button>
+                </form>
+                <p><a href="/admin/dashboard">&lt;- Back</a></p>
+            </body>
+            </html>
+            "#,
+        )))
+}
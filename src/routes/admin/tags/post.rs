@@ -0,0 +1,97 @@
+use crate::{
+    domain::Tag,
+    tags::{delete_tag, tag_subscribers, untag_subscribers},
+    utils::{e500, see_other},
+    DbPool,
+};
+use actix_web::{
+    web::{Data, Form},
+    HttpResponse,
+};
+use actix_web_flash_messages::FlashMessage;
+use htmlescape::encode_minimal;
+use serde::Deserialize;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct FormData {
+    tag: String,
+    emails: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct DeleteFormData {
+    tag: String,
+}
+
+/// Flashes the reason and returns `None` if the form is unusable.
+fn parse_form(form: FormData) -> Option<(Tag, Vec<String>)> {
+    let tag = match Tag::try_from(form.tag.trim().to_owned()) {
+        Ok(tag) => tag,
+        Err(e) => {
+            FlashMessage::error(encode_minimal(&e)).send();
+            return None;
+        }
+    };
+    let emails: Vec<String> = form
+        .emails
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|e| !e.is_empty())
+        .map(str::to_owned)
+        .collect();
+    if emails.is_empty() {
+        FlashMessage::error("Please enter at least one email address.").send();
+        return None;
+    }
+    Some((tag, emails))
+}
+
+#[tracing::instrument(name = "Add a tag", skip_all, fields(tag = %form.tag))]
+pub async fn add_tag(
+    form: Form<FormData>,
+    pool: Data<DbPool>,
+) -> actix_web::Result<HttpResponse> {
+    if let Some((tag, emails)) = parse_form(form.0) {
+        let tagged =
+            tag_subscribers(&tag, &emails, &pool).await.map_err(e500)?;
+        FlashMessage::info(format!("Tagged {tagged} subscribers with {tag}."))
+            .send();
+    }
+    Ok(see_other("/admin/tags"))
+}
+
+#[tracing::instrument(name = "Remove a tag", skip_all, fields(tag = %form.tag))]
+pub async fn remove_tag(
+    form: Form<FormData>,
+    pool: Data<DbPool>,
+) -> actix_web::Result<HttpResponse> {
+    if let Some((tag, emails)) = parse_form(form.0) {
+        let untagged = untag_subscribers(&tag, &emails, &pool)
+            .await
+            .map_err(e500)?;
+        FlashMessage::info(format!(
+            "Removed {tag} from {untagged} subscribers."
+        ))
+        .send();
+    }
+    Ok(see_other("/admin/tags"))
+}
+
+#[tracing::instrument(name = "Drop a tag", skip_all, fields(tag = %form.tag))]
+pub async fn drop_tag(
+    form: Form<DeleteFormData>,
+    pool: Data<DbPool>,
+) -> actix_web::Result<HttpResponse> {
+    let tag = match Tag::try_from(form.0.tag) {
+        Ok(tag) => tag,
+        Err(e) => {
+            FlashMessage::error(encode_minimal(&e)).send();
+            return Ok(see_other("/admin/tags"));
+        }
+    };
+    let untagged = delete_tag(&tag, &pool).await.map_err(e500)?;
+    FlashMessage::info(format!(
+        "The tag {tag} has been removed from {untagged} subscribers."
+    ))
+    .send();
+    Ok(see_other("/admin/tags"))
+}
@@ -0,0 +1,31 @@
+mod newsletters;
+mod subscribers;
+
+pub use newsletters::*;
+pub use subscribers::*;
+
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    HttpResponse,
+};
+use actix_web_lab::middleware::Next;
+
+/// Turns the errors of API handlers and extractors into JSON bodies, keeping
+/// the status code their `ResponseError` implementation picked.
+pub async fn json_errors(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> actix_web::Result<ServiceResponse<impl MessageBody>> {
+    let res = next.call(req).await?;
+    let message = match res.response().error() {
+        None => return Ok(res.map_into_left_body()),
+        Some(_) if res.status().is_server_error() => {
+            "Something went wrong".to_owned()
+        }
+        Some(e) => e.to_string(),
+    };
+    let response = HttpResponse::build(res.status())
+        .json(serde_json::json!({ "error": message }));
+    Ok(res.into_response(response).map_into_right_body())
+}
@@ -0,0 +1,148 @@
+use crate::{
+    assets::{attach_assets, resolve_issue_assets},
+    auth::UserId,
+    domain::ListSlug,
+    email_templates::validate_merge_fields,
+    idempotency::{store_response, try_process, IdempotencyKey, NextAction},
+    lists::resolve_lists,
+    routes::{
+        enqueue_delivery_tasks, insert_newsletter_issues, IssueContent,
+        PublishError,
+    },
+    segments::Segment,
+    DbPool, EmailClient,
+};
+use actix_web::{
+    web::{Data, Json, ReqData},
+    HttpRequest, HttpResponse,
+};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct NewsletterIssue {
+    title: String,
+    text_content: Option<String>,
+    html_content: Option<String>,
+    /// Replaces both bodies, which are generated from it.
+    markdown_content: Option<String>,
+    /// Target lists, the default list if left empty.
+    #[serde(default)]
+    lists: Vec<String>,
+    /// Optional segment filter, see [`Segment`].
+    segment: Option<String>,
+    /// Names of uploaded assets to attach, on top of the images the HTML
+    /// shows inline.
+    #[serde(default)]
+    assets: Vec<String>,
+    /// Records opens and clicks, see the admin stats pages.
+    #[serde(default)]
+    tracking: bool,
+}
+
+#[derive(Serialize)]
+struct PublishedIssue {
+    newsletter_issue_id: Uuid,
+}
+
+#[tracing::instrument(
+    name = "Publishing a newsletter through the API",
+    skip_all,
+    fields(email_subject = %body.title, user_id = %*user_id)
+)]
+pub async fn publish_newsletter_issue(
+    request: HttpRequest,
+    user_id: ReqData<UserId>,
+    body: Json<NewsletterIssue>,
+    pool: Data<DbPool>,
+    email_client: Data<EmailClient>,
+) -> Result<HttpResponse, PublishError> {
+    let user_id = *user_id.into_inner();
+    let idempotency_key: IdempotencyKey = request
+        .headers()
+        .get(IDEMPOTENCY_KEY_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .ok_or_else(|| {
+            PublishError::Validation(format!(
+                "The {IDEMPOTENCY_KEY_HEADER} header is missing"
+            ))
+        })?
+        .to_owned()
+        .try_into()
+        .map_err(|e: anyhow::Error| PublishError::Validation(e.to_string()))?;
+    let NewsletterIssue {
+        title,
+        text_content,
+        html_content,
+        markdown_content,
+        lists,
+        segment,
+        assets,
+        tracking,
+    } = body.0;
+    let content = match (markdown_content, text_content, html_content) {
+        (Some(markdown), None, None) => IssueContent::from_markdown(markdown),
+        (None, Some(text), Some(html)) => IssueContent::new(text, html),
+        _ => {
+            return Err(PublishError::Validation(
+                "Provide either markdown_content or both text_content and \
+                html_content"
+                    .into(),
+            ))
+        }
+    }
+    .map_err(|e| PublishError::Validation(e.to_string()))?;
+    validate_merge_fields(&content.text_content, &content.html_content)
+        .map_err(PublishError::Validation)?;
+    let lists = if lists.is_empty() {
+        vec![ListSlug::default_list()]
+    } else {
+        lists
+            .into_iter()
+            .map(ListSlug::try_from)
+            .collect::<Result<_, _>>()
+            .map_err(PublishError::Validation)?
+    };
+    let list_ids = resolve_lists(&lists, &pool).await?;
+    let segment = segment
+        .filter(|s| !s.trim().is_empty())
+        .map(|s| Segment::parse(&s))
+        .transpose()
+        .map_err(|e| {
+            PublishError::Validation(format!("The segment is invalid: {e}"))
+        })?;
+    let assets = resolve_issue_assets(
+        &assets,
+        &content.html_content,
+        email_client.max_attachments_size(),
+        &pool,
+    )
+    .await?;
+    let mut transaction =
+        match try_process(&user_id, &idempotency_key, &pool).await? {
+            NextAction::StartProcessing(t) => t,
+            NextAction::ReturnSavedResponse(response) => return Ok(response),
+        };
+    let newsletter_issue_id =
+        insert_newsletter_issues(&title, &content, tracking, &mut transaction)
+            .await
+            .context("Failed to store newsletter issue details")?;
+    attach_assets(&newsletter_issue_id, &assets, &mut transaction).await?;
+    enqueue_delivery_tasks(
+        &newsletter_issue_id,
+        &list_ids,
+        segment.as_ref(),
+        &mut transaction,
+    )
+    .await
+    .context("Failed to enqueue delivery tasks")?;
+    let response = HttpResponse::Accepted().json(PublishedIssue {
+        newsletter_issue_id,
+    });
+    store_response(&user_id, &idempotency_key, response, transaction)
+        .await
+        .map_err(PublishError::from)
+}
@@ -0,0 +1,112 @@
+use crate::{
+    domain::SubscriberStatus,
+    routes::SubscribeError,
+    subscribers::{
+        delete_subscriber, get_subscriber, list_subscribers,
+        update_subscriber_status, Cursor, Subscriber, SubscriberFilter,
+    },
+    DbPool,
+};
+use actix_web::{
+    web::{Data, Json, Path, Query},
+    HttpResponse,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct ListParameters {
+    status: Option<SubscriberStatus>,
+    limit: Option<i64>,
+    cursor: Option<String>,
+}
+
+#[derive(Serialize)]
+struct SubscribersList {
+    subscribers: Vec<Subscriber>,
+    next_cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatusUpdate {
+    status: SubscriberStatus,
+}
+
+#[tracing::instrument(name = "Listing subscribers through the API", skip(pool))]
+pub async fn api_list_subscribers(
+    parameters: Query<ListParameters>,
+    pool: Data<DbPool>,
+) -> Result<HttpResponse, SubscribeError> {
+    let ListParameters {
+        status,
+        limit,
+        cursor,
+    } = parameters.into_inner();
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(SubscribeError::Validation(format!(
+            "limit must be between 1 and {MAX_PAGE_SIZE}"
+        )));
+    }
+    let after = cursor
+        .map(Cursor::try_from)
+        .transpose()
+        .map_err(SubscribeError::Validation)?;
+    let filter = SubscriberFilter {
+        search: None,
+        status,
+        after,
+    };
+    let page = list_subscribers(&filter, limit, &pool).await?;
+    Ok(HttpResponse::Ok().json(SubscribersList {
+        subscribers: page.subscribers,
+        next_cursor: page.next.map(|c| c.to_string()),
+    }))
+}
+
+#[tracing::instrument(
+    name = "Getting a subscriber through the API",
+    skip(pool)
+)]
+pub async fn api_get_subscriber(
+    subscriber_id: Path<Uuid>,
+    pool: Data<DbPool>,
+) -> Result<HttpResponse, SubscribeError> {
+    let subscriber = get_subscriber(&subscriber_id, &pool)
+        .await?
+        .ok_or(SubscribeError::NotFound)?;
+    Ok(HttpResponse::Ok().json(subscriber))
+}
+
+#[tracing::instrument(
+    name = "Updating a subscriber through the API",
+    skip(body, pool),
+    fields(status = %body.status)
+)]
+pub async fn api_update_subscriber(
+    subscriber_id: Path<Uuid>,
+    body: Json<StatusUpdate>,
+    pool: Data<DbPool>,
+) -> Result<HttpResponse, SubscribeError> {
+    if !update_subscriber_status(&subscriber_id, body.0.status, &pool).await? {
+        return Err(SubscribeError::NotFound);
+    }
+    api_get_subscriber(subscriber_id, pool).await
+}
+
+#[tracing::instrument(
+    name = "Deleting a subscriber through the API",
+    skip(pool)
+)]
+pub async fn api_delete_subscriber(
+    subscriber_id: Path<Uuid>,
+    pool: Data<DbPool>,
+) -> Result<HttpResponse, SubscribeError> {
+    if !delete_subscriber(&subscriber_id, &pool).await? {
+        return Err(SubscribeError::NotFound);
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -1,13 +1,18 @@
+use crate::{auth::csrf_token, Session};
 use actix_web::{http::header::ContentType, HttpResponse};
 use actix_web_flash_messages::IncomingFlashMessages;
 use std::fmt::Write;
 
-pub async fn login_form(flash_messages: IncomingFlashMessages) -> HttpResponse {
+pub async fn login_form(
+    flash_messages: IncomingFlashMessages,
+    session: Session,
+) -> actix_web::Result<HttpResponse> {
+    let csrf_token = csrf_token(&session)?;
     let mut msgs = String::new();
     flash_messages
         .iter()
         .for_each(|m| writeln!(msgs, "<p><i>{}</i></p>", m.content()).unwrap());
-    HttpResponse::Ok()
+    Ok(HttpResponse::Ok()
         .content_type(ContentType::html())
         .body(format!(
             r#"
@@ -28,10 +33,15 @@ pub async fn login_form(flash_messages: IncomingFlashMessages) -> HttpResponse {
                     Password
                     <input type="password" placeholder="Enter Password" name="password" />
                   </label>
+                  <label>
+                    <input type="checkbox" name="remember_me" />
+                    Remember me
+                  </label>
+                  <input hidden type="text" name="csrf_token" value="{csrf_token}" />
                   <button type="submit">Login</button>
                 </form>
               </body>
             </html>
             "#
-        ))
+        )))
 }
@@ -2,15 +2,19 @@ use crate::{utils::see_other, Session};
 use actix_web::{
     error::InternalError,
     web::{Data, Form},
-    HttpResponse,
+    HttpRequest, HttpResponse,
 };
 use actix_web_flash_messages::FlashMessage;
 use anyhow::Context;
+use chrono::Utc;
 use secrecy::Secret;
 use serde::Deserialize;
 
 use crate::{
-    auth::{validate_credentials, AuthError, Credentials},
+    auth::{
+        register_session, rotate_csrf_token, validate_credentials, AuthError,
+        Credentials,
+    },
     DbPool,
 };
 
@@ -26,22 +30,25 @@ pub enum LoginError {
 pub struct FormData {
     username: String,
     password: Secret<String>,
+    remember_me: Option<String>,
 }
 
 #[tracing::instrument(skip_all,
     fields(username=tracing::field::Empty, user_id=tracing::field::Empty)
 )]
 pub async fn login(
+    request: HttpRequest,
     session: Session,
     form: Form<FormData>,
     pool: Data<DbPool>,
 ) -> Result<HttpResponse, InternalError<LoginError>> {
+    let remember_me = form.remember_me.is_some();
     let credentials = Credentials {
         username: form.0.username,
         password: form.0.password,
     };
     tracing::Span::current()
-        .record("username", &tracing::field::display(&credentials.username));
+        .record("username", tracing::field::display(&credentials.username));
     let user_id =
         validate_credentials(credentials, &pool)
             .await
@@ -56,13 +63,23 @@ pub async fn login(
                 };
                 login_redirect(e)
             })?;
+    let session_id =
+        register_session(&user_id, (&request).into(), remember_me, &pool)
+            .await
+            .map_err(|e| login_redirect(e.into()))?;
     session.renew();
+    let now = Utc::now();
     session
         .insert_user_id(user_id)
+        .and_then(|_| session.insert_session_id(session_id))
+        .and_then(|_| session.insert_remember_me(remember_me))
+        .and_then(|_| session.insert_logged_in_at(now))
+        .and_then(|_| session.insert_last_seen_at(now))
+        .and_then(|_| rotate_csrf_token(&session).map(|_| ()))
         .context("Failed to persist user session")
         .map_err(|e| login_redirect(e.into()))?;
     tracing::Span::current()
-        .record("user_id", &tracing::field::display(&user_id));
+        .record("user_id", tracing::field::display(&user_id));
     Ok(see_other("/admin/dashboard"))
 }
 
@@ -1,11 +1,17 @@
 mod admin;
+mod api;
 mod health_check;
 mod home;
 mod login;
 mod subscriptions;
+mod tracking;
+mod webhooks;
 
 pub use admin::*;
+pub use api::*;
 pub use health_check::*;
 pub use home::*;
 pub use login::*;
 pub use subscriptions::*;
+pub use tracking::*;
+pub use webhooks::*;
@@ -1,11 +1,15 @@
 use actix_web::{
+    http::StatusCode,
     web::{Data, Query},
-    HttpResponse, ResponseError,
+    HttpRequest, HttpResponse, ResponseError,
 };
+use htmlescape::encode_minimal;
 use serde::Deserialize;
+use sqlx::PgExecutor;
 use uuid::Uuid;
 
-use crate::DbPool;
+use super::{invalid_link_page, subscriber_page};
+use crate::{subscribers::get_subscriber, DbPool, Locales};
 
 #[derive(Clone, Debug, Deserialize)]
 pub struct Parameters {
@@ -14,8 +18,6 @@ pub struct Parameters {
 
 #[derive(Debug, thiserror::Error)]
 pub enum ConfirmError {
-    #[error("Failed to identify user")]
-    UnknownUser,
     #[error(transparent)]
     Unexpected(#[from] anyhow::Error),
 }
@@ -23,35 +25,50 @@ pub enum ConfirmError {
 impl ResponseError for ConfirmError {
     fn status_code(&self) -> reqwest::StatusCode {
         match self {
-            Self::UnknownUser => reqwest::StatusCode::UNAUTHORIZED,
             Self::Unexpected(_) => reqwest::StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 }
 
-#[tracing::instrument(
-    name = "Confirm a pending subscriber",
-    skip(params, pool)
-)]
+#[tracing::instrument(name = "Confirm a pending subscriber", skip_all)]
 pub async fn confirm_subscription(
+    request: HttpRequest,
     params: Query<Parameters>,
     pool: Data<DbPool>,
+    locales: Data<Locales>,
 ) -> Result<HttpResponse, ConfirmError> {
-    let subscriber_id =
-        get_subscriber_id_from_token(&params.subscription_token, &pool)
+    let subscriber =
+        match get_subscriber_id_from_token(&params.subscription_token, &pool)
             .await?
-            .ok_or(ConfirmError::UnknownUser)?;
-    confirm_subscriber(&subscriber_id, &pool)
-        .await
-        .map(|_| HttpResponse::Ok().finish())
-        .map_err(ConfirmError::from)
+        {
+            Some(subscriber_id) => {
+                get_subscriber(&subscriber_id, &pool).await?
+            }
+            None => None,
+        };
+    let subscriber = match subscriber {
+        Some(subscriber) => subscriber,
+        None => {
+            let locale = locales.for_request(&request);
+            return Ok(invalid_link_page(&locales, &locale));
+        }
+    };
+    confirm_subscriber(&subscriber.id, pool.get_ref()).await?;
+    let locale = &subscriber.locale;
+    let t = |id| encode_minimal(&locales.message(locale, id, &[]));
+    Ok(subscriber_page(
+        StatusCode::OK,
+        locale,
+        &t("confirmed-title"),
+        &format!("<p>{}</p>", t("confirmed")),
+    ))
 }
 
 #[tracing::instrument(
     name = "Getting subscriber id from token",
     skip(subscription_token, pool)
 )]
-async fn get_subscriber_id_from_token(
+pub async fn get_subscriber_id_from_token(
     subscription_token: &str,
     pool: &DbPool,
 ) -> anyhow::Result<Option<Uuid>> {
@@ -70,21 +87,21 @@ async fn get_subscriber_id_from_token(
 
 #[tracing::instrument(
     name = "Confirming a pending subscriber",
-    skip(subscriber_id, pool)
+    skip(subscriber_id, executor)
 )]
-async fn confirm_subscriber(
+pub async fn confirm_subscriber(
     subscriber_id: &Uuid,
-    pool: &DbPool,
+    executor: impl PgExecutor<'_>,
 ) -> anyhow::Result<()> {
     sqlx::query!(
         r#"
         update subscriptions
         set status = 'confirmed'
-        where id = $1;
+        where id = $1 and status = 'pending_confirmation';
         "#,
         subscriber_id
     )
-    .execute(pool)
+    .execute(executor)
     .await
     .map(|_| ())
     .map_err(anyhow::Error::from)
@@ -0,0 +1,206 @@
+use super::{
+    generate_subscription_token, get_subscriber_id_from_token,
+    invalid_link_page, preferences_url, subscriber_page,
+};
+use crate::{
+    domain::SubscriberEmail,
+    server::AppBaseUrl,
+    subscribers::{
+        confirm_email_change, get_subscriber, request_email_change,
+        EmailChangeError,
+    },
+    utils::see_other,
+    DbPool, EmailClient, EmailTemplate, EmailTemplates, Locales,
+};
+use actix_web::{
+    http::StatusCode,
+    web::{Data, Form, Query},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use actix_web_flash_messages::FlashMessage;
+use htmlescape::encode_minimal;
+use minijinja::context;
+use serde::Deserialize;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ChangeEmailError {
+    #[error("Failed to identify user")]
+    UnknownUser,
+    #[error("{0}")]
+    AddressTaken(String),
+    #[error(transparent)]
+    Unexpected(#[from] anyhow::Error),
+}
+
+impl From<EmailChangeError> for ChangeEmailError {
+    fn from(e: EmailChangeError) -> Self {
+        match e {
+            EmailChangeError::Unexpected(e) => Self::Unexpected(e),
+            e => Self::AddressTaken(e.to_string()),
+        }
+    }
+}
+
+impl ResponseError for ChangeEmailError {
+    fn status_code(&self) -> reqwest::StatusCode {
+        match self {
+            Self::UnknownUser => reqwest::StatusCode::UNAUTHORIZED,
+            Self::AddressTaken(_) => reqwest::StatusCode::CONFLICT,
+            Self::Unexpected(_) => reqwest::StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct FormData {
+    subscription_token: String,
+    email: String,
+}
+
+/// Nothing changes until the new address is confirmed.
+#[tracing::instrument(
+    name = "Request a change of email",
+    skip_all,
+    fields(new_email = %form.email)
+)]
+pub async fn request_change_of_email(
+    form: Form<FormData>,
+    pool: Data<DbPool>,
+    email_client: Data<EmailClient>,
+    templates: Data<EmailTemplates>,
+    base_url: Data<AppBaseUrl>,
+) -> Result<HttpResponse, ChangeEmailError> {
+    let FormData {
+        subscription_token,
+        email,
+    } = form.0;
+    let subscriber_id =
+        get_subscriber_id_from_token(&subscription_token, &pool)
+            .await?
+            .ok_or(ChangeEmailError::UnknownUser)?;
+    let subscriber = get_subscriber(&subscriber_id, &pool)
+        .await?
+        .ok_or(ChangeEmailError::UnknownUser)?;
+    let back = see_other(&preferences_url(&subscription_token));
+    let new_email = match SubscriberEmail::try_from(email) {
+        Ok(email) => email,
+        Err(e) => {
+            FlashMessage::error(encode_minimal(&e)).send();
+            return Ok(back);
+        }
+    };
+    let change_token = generate_subscription_token();
+    match request_email_change(&subscriber_id, &new_email, &change_token, &pool)
+        .await
+    {
+        Ok(()) => {}
+        Err(EmailChangeError::Unexpected(e)) => return Err(e.into()),
+        Err(e) => {
+            FlashMessage::error(encode_minimal(&e.to_string())).send();
+            return Ok(back);
+        }
+    }
+    send_email_change_confirmation(
+        &email_client,
+        &templates,
+        &new_email,
+        &subscriber.locale,
+        base_url.as_ref().as_ref(),
+        &change_token,
+    )
+    .await?;
+    FlashMessage::info(format!(
+        "We sent a confirmation link to {}.",
+        encode_minimal(new_email.as_ref())
+    ))
+    .send();
+    Ok(back)
+}
+
+#[tracing::instrument(
+    name = "Sending an email change confirmation",
+    skip(email_client, templates, change_token)
+)]
+pub async fn send_email_change_confirmation(
+    email_client: &EmailClient,
+    templates: &EmailTemplates,
+    new_email: &SubscriberEmail,
+    locale: &str,
+    base_url: &str,
+    change_token: &str,
+) -> anyhow::Result<()> {
+    let confirmation_link = format!(
+        "{base_url}/subscriptions/email_change/confirm?change_token={change_token}"
+    );
+    let email = templates.render(
+        EmailTemplate::EmailChange,
+        context! { confirmation_link, locale },
+    )?;
+    email_client
+        .send_email(
+            new_email,
+            &email.subject,
+            &email.text_body,
+            &email.html_body,
+        )
+        .await
+        .map(|_| ())
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Parameters {
+    change_token: String,
+}
+
+#[tracing::instrument(name = "Confirm a change of email", skip_all)]
+pub async fn confirm_change_of_email(
+    request: HttpRequest,
+    params: Query<Parameters>,
+    pool: Data<DbPool>,
+    locales: Data<Locales>,
+) -> Result<HttpResponse, ChangeEmailError> {
+    let subscriber_id =
+        match confirm_email_change(&params.change_token, &pool).await {
+            Ok(subscriber_id) => subscriber_id,
+            Err(EmailChangeError::AddressTaken(email)) => {
+                let locale = locales.for_request(&request);
+                let t = |id, args: &[(&str, &str)]| {
+                    encode_minimal(&locales.message(&locale, id, args))
+                };
+                return Ok(subscriber_page(
+                    StatusCode::CONFLICT,
+                    &locale,
+                    &t("email-change-failed-title", &[]),
+                    &format!(
+                        "<p>{}</p>",
+                        t("email-change-taken", &[("email", &email)])
+                    ),
+                ));
+            }
+            Err(e) => return Err(e.into()),
+        };
+    let subscriber = match subscriber_id {
+        Some(subscriber_id) => get_subscriber(&subscriber_id, &pool).await?,
+        None => None,
+    };
+    let subscriber = match subscriber {
+        Some(subscriber) => subscriber,
+        None => {
+            let locale = locales.for_request(&request);
+            return Ok(invalid_link_page(&locales, &locale));
+        }
+    };
+    let locale = &subscriber.locale;
+    let t = |id, args: &[(&str, &str)]| {
+        encode_minimal(&locales.message(locale, id, args))
+    };
+    Ok(subscriber_page(
+        StatusCode::OK,
+        locale,
+        &t("email-changed-title", &[]),
+        &format!(
+            "<p>{}</p>",
+            t("email-changed", &[("email", subscriber.email.as_ref())])
+        ),
+    ))
+}
@@ -1,18 +1,31 @@
 mod confirm;
+mod email_change;
+mod preferences;
+mod unsubscribe;
 use anyhow::Context;
 pub use confirm::*;
+pub use email_change::*;
+pub use preferences::*;
 use sqlx::Transaction;
+pub use unsubscribe::*;
 
 use crate::{
-    domain::{NewSubscriber, SubscriberEmail, SubscriberName},
+    domain::{
+        ListSlug, NewSubscriber, OptInMode, SubscriberEmail, SubscriberName,
+        SubscriberStatus,
+    },
+    lists::{add_to_list, list_opt_in, resolve_lists, ListError},
     server::AppBaseUrl,
-    Database, DbPool, EmailClient,
+    Database, DbPool, EmailClient, EmailTemplate, EmailTemplates, Locales,
 };
 use actix_web::{
+    http::{header::ContentType, StatusCode},
     web::{Data, Form},
-    HttpResponse, ResponseError,
+    HttpRequest, HttpResponse, ResponseError,
 };
 use chrono::Utc;
+use htmlescape::encode_minimal;
+use minijinja::context;
 use serde::Deserialize;
 use uuid::Uuid;
 
@@ -20,14 +33,26 @@ use uuid::Uuid;
 pub enum SubscribeError {
     #[error("{0}")]
     Validation(String),
+    #[error("Not found")]
+    NotFound,
     #[error(transparent)]
     Unexpected(#[from] anyhow::Error),
 }
 
+impl From<ListError> for SubscribeError {
+    fn from(e: ListError) -> Self {
+        match e {
+            ListError::Unexpected(e) => Self::Unexpected(e),
+            e => Self::Validation(e.to_string()),
+        }
+    }
+}
+
 impl ResponseError for SubscribeError {
     fn status_code(&self) -> reqwest::StatusCode {
         match self {
             Self::Validation(_) => reqwest::StatusCode::BAD_REQUEST,
+            Self::NotFound => reqwest::StatusCode::NOT_FOUND,
             Self::Unexpected(_) => reqwest::StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
@@ -37,6 +62,11 @@ impl ResponseError for SubscribeError {
 pub struct FormData {
     name: String,
     email: String,
+    /// The list to join, the default list if missing.
+    list: Option<String>,
+    /// The language to write to the subscriber in, negotiated from the
+    /// `Accept-Language` header if missing.
+    locale: Option<String>,
 }
 
 impl TryInto<NewSubscriber> for FormData {
@@ -51,41 +81,136 @@ impl TryInto<NewSubscriber> for FormData {
 
 #[tracing::instrument(
     name = "Adding a new subscriber",
-    skip(form, pool, email_client, base_url),
+    skip_all,
     fields(
         subscriber_name = %form.name,
         subscriber_email = %form.email,
     )
 )]
+#[allow(clippy::too_many_arguments)]
 pub async fn subscribe(
-    Form(form): Form<FormData>,
+    request: HttpRequest,
+    Form(mut form): Form<FormData>,
     pool: Data<DbPool>,
     email_client: Data<EmailClient>,
+    templates: Data<EmailTemplates>,
+    locales: Data<Locales>,
     base_url: Data<AppBaseUrl>,
+    default_opt_in: Data<OptInMode>,
 ) -> Result<HttpResponse, SubscribeError> {
-    let subscriber = form.try_into().map_err(SubscribeError::Validation)?;
+    let locale = match form.locale.take().filter(|l| !l.is_empty()) {
+        Some(locale) => locales.negotiate(&locale),
+        None => locales.for_request(&request),
+    };
+    let list = match form.list.take().filter(|l| !l.is_empty()) {
+        Some(list) => {
+            ListSlug::try_from(list).map_err(SubscribeError::Validation)?
+        }
+        None => ListSlug::default_list(),
+    };
+    let subscriber: NewSubscriber =
+        form.try_into().map_err(SubscribeError::Validation)?;
+    let list_id = resolve_lists(&[list], &pool).await?[0];
+    let opt_in = list_opt_in(&list_id, &pool)
+        .await?
+        .unwrap_or(**default_opt_in);
     let mut transaction = pool
         .begin()
         .await
         .context("Failed to acquire connection from the pool")?;
-    let subscriber_id =
-        insert_subscriber(&mut transaction, &subscriber).await?;
+    let (subscriber_id, status, locale) =
+        match find_subscriber(&mut transaction, &subscriber.email).await? {
+            Some(existing) => existing,
+            None => (
+                insert_subscriber(&mut transaction, &subscriber, &locale)
+                    .await?,
+                SubscriberStatus::PendingConfirmation,
+                locale,
+            ),
+        };
+    if status.is_suppressed() {
+        tracing::info!("Not resubscribing a suppressed address");
+        return Ok(HttpResponse::Ok().finish());
+    }
+    let joined =
+        add_to_list(&list_id, &subscriber_id, &mut transaction).await?;
+    // Confirmed subscribers are welcomed to lists that skip confirmation,
+    // like new subscribers, and have nothing to confirm for the others.
+    if status == SubscriberStatus::Confirmed
+        && !(joined && opt_in == OptInMode::Single)
+    {
+        transaction
+            .commit()
+            .await
+            .context("Failed to commit transaction")?;
+        return Ok(HttpResponse::Ok().finish());
+    }
+    if status == SubscriberStatus::Unsubscribed {
+        reopen_subscription(&subscriber_id, &mut transaction).await?;
+    }
     let subscription_token = generate_subscription_token();
     store_token(&mut transaction, &subscriber_id, &subscription_token).await?;
+    if opt_in == OptInMode::Single {
+        confirm_subscriber(&subscriber_id, &mut transaction).await?;
+    }
     transaction
         .commit()
         .await
         .context("Failed to commit transaction")?;
-    send_confirmation_email(
-        &email_client,
-        &subscriber,
-        base_url.as_ref().as_ref(),
-        &subscription_token,
-    )
-    .await?;
+    match opt_in {
+        OptInMode::Double => {
+            send_confirmation_email(
+                &email_client,
+                &templates,
+                &subscriber,
+                &locale,
+                base_url.as_ref().as_ref(),
+                &subscription_token,
+            )
+            .await?
+        }
+        OptInMode::Single => {
+            send_welcome_email(
+                &email_client,
+                &templates,
+                &subscriber,
+                &locale,
+                base_url.as_ref().as_ref(),
+                &subscription_token,
+            )
+            .await?
+        }
+    }
     Ok(HttpResponse::Ok().finish())
 }
 
+#[tracing::instrument(
+    name = "Looking for an existing subscriber",
+    skip(transaction, email)
+)]
+async fn find_subscriber(
+    transaction: &mut Transaction<'_, Database>,
+    email: &SubscriberEmail,
+) -> anyhow::Result<Option<(Uuid, SubscriberStatus, String)>> {
+    let row = sqlx::query!(
+        r#"
+        select id, status, locale
+        from subscriptions
+        where lower(email) = lower($1);
+        "#,
+        email.as_ref(),
+    )
+    .fetch_optional(transaction)
+    .await
+    .context("Failed to query for an existing subscriber")?;
+    row.map(|r| {
+        let status =
+            SubscriberStatus::try_from(r.status).map_err(anyhow::Error::msg)?;
+        Ok((r.id, status, r.locale))
+    })
+    .transpose()
+}
+
 #[tracing::instrument(
     name = "Saving new subscriber details in the database",
     skip(subscriber, transaction)
@@ -93,28 +218,54 @@ pub async fn subscribe(
 async fn insert_subscriber(
     transaction: &mut Transaction<'_, Database>,
     subscriber: &NewSubscriber,
+    locale: &str,
 ) -> anyhow::Result<Uuid> {
     let subscriber_id = Uuid::new_v4();
     sqlx::query!(
         r#"
-        insert into subscriptions (id, name, email, subscribed_at, status)
-        values ($1, $2, $3, $4, 'pending_confirmation');
+        insert into subscriptions
+            (id, name, email, subscribed_at, status, locale)
+        values ($1, $2, $3, $4, 'pending_confirmation', $5);
         "#,
         subscriber_id,
         subscriber.name.as_ref(),
         subscriber.email.as_ref(),
         Utc::now(),
+        locale,
     )
     .execute(transaction)
     .await?;
     Ok(subscriber_id)
 }
 
+/// Puts a subscriber who unsubscribed back to awaiting confirmation.
+#[tracing::instrument(
+    name = "Reopening a subscription",
+    skip(subscriber_id, transaction)
+)]
+async fn reopen_subscription(
+    subscriber_id: &Uuid,
+    transaction: &mut Transaction<'_, Database>,
+) -> anyhow::Result<()> {
+    sqlx::query!(
+        r#"
+        update subscriptions
+        set status = 'pending_confirmation'
+        where id = $1 and status = 'unsubscribed';
+        "#,
+        subscriber_id,
+    )
+    .execute(transaction)
+    .await
+    .context("Failed to reopen a subscription")
+    .map(|_| ())
+}
+
 #[tracing::instrument(
     name = "Storing subscription token in the database",
     skip(subscriber_id, subscription_token, transaction)
 )]
-async fn store_token(
+pub async fn store_token(
     transaction: &mut Transaction<'_, Database>,
     subscriber_id: &Uuid,
     subscription_token: &str,
@@ -135,37 +286,107 @@ async fn store_token(
 
 #[tracing::instrument(
     name = "Sending a confirmation email to a new subscriber",
-    skip(email_client, subscriber)
+    skip(email_client, templates, subscriber)
 )]
-async fn send_confirmation_email(
+pub async fn send_confirmation_email(
     email_client: &EmailClient,
+    templates: &EmailTemplates,
     subscriber: &NewSubscriber,
+    locale: &str,
     base_url: &str,
     subscription_token: &str,
 ) -> anyhow::Result<()> {
     let confirmation_link =
         format!("{base_url}/subscriptions/confirm?subscription_token={subscription_token}");
-    let subject = "Subject";
-    let text_body = &format!(
-        r#"
-        Welcome to the newsletter.
-        Visit {confirmation_link} to confirm you subscription.
-        "#
-    );
-    let html_body = &format!(
-        r#"
-        Welcome to the newsletter.
-        <br>
-        Click <a href="{confirmation_link}">here</a> to confirm your subscription.
-        "#
-    );
+    let email = templates.render(
+        EmailTemplate::Confirmation,
+        context! {
+            name => subscriber.name.as_ref(),
+            confirmation_link,
+            locale,
+        },
+    )?;
+    email_client
+        .send_email(
+            &subscriber.email,
+            &email.subject,
+            &email.text_body,
+            &email.html_body,
+        )
+        .await
+        .map(|_| ())
+}
+
+#[tracing::instrument(
+    name = "Sending a welcome email to a new subscriber",
+    skip(email_client, templates, subscriber, subscription_token)
+)]
+pub async fn send_welcome_email(
+    email_client: &EmailClient,
+    templates: &EmailTemplates,
+    subscriber: &NewSubscriber,
+    locale: &str,
+    base_url: &str,
+    subscription_token: &str,
+) -> anyhow::Result<()> {
+    let preferences_link =
+        format!("{base_url}{}", preferences_url(subscription_token));
+    let email = templates.render(
+        EmailTemplate::Welcome,
+        context! {
+            name => subscriber.name.as_ref(),
+            preferences_link,
+            locale,
+        },
+    )?;
     email_client
-        .send_email(&subscriber.email, subject, text_body, html_body)
+        .send_email(
+            &subscriber.email,
+            &email.subject,
+            &email.text_body,
+            &email.html_body,
+        )
         .await
-        .map_err(anyhow::Error::from)
+        .map(|_| ())
+}
+
+/// A page shown to subscribers, `body` is inserted as is.
+fn subscriber_page(
+    status: StatusCode,
+    locale: &str,
+    title: &str,
+    body: &str,
+) -> HttpResponse {
+    HttpResponse::build(status)
+        .content_type(ContentType::html())
+        .body(format!(
+            r#"
+            <!DOCTYPE html>
+            <html lang="{locale}">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>{title}</title>
+            </head>
+            <body>
+                {body}
+            </body>
+            </html>
+            "#,
+        ))
+}
+
+/// Shown when a subscription token doesn't match anybody.
+fn invalid_link_page(locales: &Locales, locale: &str) -> HttpResponse {
+    let t = |id| encode_minimal(&locales.message(locale, id, &[]));
+    subscriber_page(
+        StatusCode::UNAUTHORIZED,
+        locale,
+        &t("invalid-link-title"),
+        &format!("<p>{}</p>", t("invalid-link")),
+    )
 }
 
-fn generate_subscription_token() -> String {
+pub fn generate_subscription_token() -> String {
     use rand::{distributions::Alphanumeric, thread_rng, Rng};
     let mut rng = thread_rng();
     std::iter::repeat_with(|| rng.sample(Alphanumeric))
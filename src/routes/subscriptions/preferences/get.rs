@@ -0,0 +1,111 @@
+use super::PreferencesError;
+use crate::{
+    lists::all_lists, routes::get_subscriber_id_from_token,
+    subscribers::get_preferences, DbPool,
+};
+use actix_web::{
+    http::header::ContentType,
+    web::{Data, Query},
+    HttpResponse,
+};
+use actix_web_flash_messages::IncomingFlashMessages;
+use htmlescape::encode_minimal;
+use serde::Deserialize;
+use std::fmt::Write;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Parameters {
+    subscription_token: String,
+}
+
+#[tracing::instrument(name = "Show subscriber preferences", skip_all)]
+pub async fn preferences_form(
+    params: Query<Parameters>,
+    pool: Data<DbPool>,
+    flash_messages: IncomingFlashMessages,
+) -> Result<HttpResponse, PreferencesError> {
+    let subscriber_id =
+        get_subscriber_id_from_token(&params.subscription_token, &pool)
+            .await?
+            .ok_or(PreferencesError::UnknownUser)?;
+    let preferences = get_preferences(&subscriber_id, &pool).await?;
+    let mut msgs = String::new();
+    for m in flash_messages.iter() {
+        writeln!(msgs, "<p><i>{}</i></p>", m.content()).unwrap();
+    }
+    let mut lists = String::new();
+    for list in all_lists(&pool).await? {
+        let checked = if preferences.lists.contains(&list.slug) {
+            " checked"
+        } else {
+            ""
+        };
+        writeln!(
+            lists,
+            r#"
+                    <label>
+                        <input type="checkbox" name="lists" value="{slug}"{checked}>
+                        {name}
+                    </label>
+                    <br>"#,
+            slug = list.slug,
+            name = encode_minimal(&list.name),
+        )
+        .unwrap();
+    }
+    let email = encode_minimal(&preferences.email);
+    let name = encode_minimal(&preferences.name);
+    let plain_text_only = if preferences.plain_text_only {
+        " checked"
+    } else {
+        ""
+    };
+    let paused_until = preferences
+        .paused_until
+        .map(|d| d.to_string())
+        .unwrap_or_default();
+    let subscription_token = encode_minimal(&params.subscription_token);
+    Ok(HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(format!(
+            r#"
+            <!DOCTYPE html>
+            <html lang="en">
+            <head>
+                <meta http-equiv="content-type" content="text/html; charset=utf-8">
+                <title>Your preferences</title>
+            </head>
+            <body>
+                {msgs}
+                <p>Preferences for {email}</p>
+                <form action="/subscriptions/preferences" method="post">
+                    <label>Name
+                        <input type="text" name="name" value="{name}">
+                    </label>
+                    <br>
+                    <p>Send me:</p>
+                    {lists}
+                    <label>
+                        <input type="checkbox" name="plain_text_only" value="on"{plain_text_only}>
+                        Plain-text emails only
+                    </label>
+                    <br>
+                    <label>Pause delivery until (issues published meanwhile are skipped)
+                        <input type="date" name="paused_until" value="{paused_until}">
+                    </label>
+                    <br>
+                    <input hidden type="text" name="subscription_token" value="{subscription_token}">
+                    <button type="submit">Save preferences</button>
+                </form>
+                <form action="/subscriptions/email_change" method="post">
+                    <label>New email address
+                        <input type="email" name="email">
+                    </label>
+                    <input hidden type="text" name="subscription_token" value="{subscription_token}">
+                    <button type="submit">Change email</button>
+                </form>
+            </body>
+            </html>
+            "#,
+        )))
+}
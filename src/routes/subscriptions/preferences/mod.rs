@@ -0,0 +1,32 @@
+mod get;
+mod post;
+
+pub use get::*;
+pub use post::*;
+
+use actix_web::ResponseError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PreferencesError {
+    #[error("Failed to identify user")]
+    UnknownUser,
+    #[error(transparent)]
+    Unexpected(#[from] anyhow::Error),
+}
+
+impl ResponseError for PreferencesError {
+    fn status_code(&self) -> reqwest::StatusCode {
+        match self {
+            Self::UnknownUser => reqwest::StatusCode::UNAUTHORIZED,
+            Self::Unexpected(_) => reqwest::StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Where the preference center of the token's owner lives.
+pub(crate) fn preferences_url(subscription_token: &str) -> String {
+    format!(
+        "/subscriptions/preferences?subscription_token={}",
+        urlencoding::encode(subscription_token)
+    )
+}
@@ -0,0 +1,91 @@
+use super::{preferences_url, PreferencesError};
+use crate::{
+    domain::{DeliveryPause, ListSlug, SubscriberName},
+    lists::{resolve_lists, ListError},
+    routes::get_subscriber_id_from_token,
+    subscribers::{update_preferences, PreferencesUpdate},
+    utils::see_other,
+    DbPool,
+};
+use actix_web::{web::Data, HttpResponse};
+use actix_web_flash_messages::FlashMessage;
+use actix_web_lab::extract::UrlEncodedForm;
+use htmlescape::encode_minimal;
+use serde::Deserialize;
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct FormData {
+    subscription_token: String,
+    name: String,
+    #[serde(default)]
+    lists: Vec<String>,
+    /// Present only when the checkbox is ticked.
+    plain_text_only: Option<String>,
+    /// Empty to keep delivery going.
+    #[serde(default)]
+    paused_until: String,
+}
+
+#[tracing::instrument(name = "Update subscriber preferences", skip_all)]
+pub async fn update_subscriber_preferences(
+    form: UrlEncodedForm<FormData>,
+    pool: Data<DbPool>,
+) -> Result<HttpResponse, PreferencesError> {
+    let FormData {
+        subscription_token,
+        name,
+        lists,
+        plain_text_only,
+        paused_until,
+    } = form.0;
+    let subscriber_id =
+        get_subscriber_id_from_token(&subscription_token, &pool)
+            .await?
+            .ok_or(PreferencesError::UnknownUser)?;
+    let back = see_other(&preferences_url(&subscription_token));
+    let update = match validate(name, lists, plain_text_only, paused_until) {
+        Ok(update) => update,
+        Err(e) => {
+            FlashMessage::error(encode_minimal(&e)).send();
+            return Ok(back);
+        }
+    };
+    let (name, lists, plain_text_only, pause) = update;
+    let list_ids = match resolve_lists(&lists, &pool).await {
+        Ok(list_ids) => list_ids,
+        Err(ListError::Unexpected(e)) => return Err(e.into()),
+        Err(e) => {
+            FlashMessage::error(e.to_string()).send();
+            return Ok(back);
+        }
+    };
+    let update = PreferencesUpdate {
+        name,
+        list_ids,
+        plain_text_only,
+        pause,
+    };
+    update_preferences(&subscriber_id, &update, &pool).await?;
+    FlashMessage::info("Your preferences have been saved.").send();
+    Ok(back)
+}
+
+type ValidForm = (SubscriberName, Vec<ListSlug>, bool, Option<DeliveryPause>);
+
+fn validate(
+    name: String,
+    lists: Vec<String>,
+    plain_text_only: Option<String>,
+    paused_until: String,
+) -> Result<ValidForm, String> {
+    let name = SubscriberName::try_from(name)?;
+    let lists = lists
+        .into_iter()
+        .map(ListSlug::try_from)
+        .collect::<Result<_, _>>()?;
+    let pause = match paused_until.trim() {
+        "" => None,
+        date => Some(DeliveryPause::try_from(date.to_owned())?),
+    };
+    Ok((name, lists, plain_text_only.is_some(), pause))
+}
@@ -0,0 +1,160 @@
+use super::{generate_subscription_token, invalid_link_page, subscriber_page};
+use crate::{
+    domain::SubscriberStatus,
+    subscribers::{get_subscriber, update_subscriber_status, Subscriber},
+    DbPool, Locales,
+};
+use actix_web::{
+    http::StatusCode,
+    web::{Data, Form, Query},
+    HttpRequest, HttpResponse, ResponseError,
+};
+use anyhow::Context;
+use htmlescape::encode_minimal;
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Debug, thiserror::Error)]
+pub enum UnsubscribeError {
+    #[error(transparent)]
+    Unexpected(#[from] anyhow::Error),
+}
+
+impl ResponseError for UnsubscribeError {
+    fn status_code(&self) -> reqwest::StatusCode {
+        match self {
+            Self::Unexpected(_) => reqwest::StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Parameters {
+    unsubscribe_token: String,
+}
+
+/// Where the token's owner can stop receiving issues.
+pub(crate) fn unsubscribe_url(unsubscribe_token: &str) -> String {
+    format!(
+        "/subscriptions/unsubscribe?unsubscribe_token={}",
+        urlencoding::encode(unsubscribe_token)
+    )
+}
+
+/// The subscriber's unsubscribe token, issuing it the first time. It is
+/// good for nothing but unsubscribing, so it can go in every issue.
+#[tracing::instrument(name = "Getting an unsubscribe token", skip(pool))]
+pub async fn unsubscribe_token(
+    subscriber_id: &Uuid,
+    pool: &DbPool,
+) -> anyhow::Result<String> {
+    sqlx::query!(
+        r#"
+        insert into unsubscribe_tokens (unsubscribe_token, subscriber_id)
+        values ($1, $2)
+        on conflict (subscriber_id) do update
+        set subscriber_id = excluded.subscriber_id
+        returning unsubscribe_token;
+        "#,
+        generate_subscription_token(),
+        subscriber_id,
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to issue an unsubscribe token")
+    .map(|r| r.unsubscribe_token)
+}
+
+/// The owner of the token, if any.
+async fn token_owner(
+    unsubscribe_token: &str,
+    pool: &DbPool,
+) -> anyhow::Result<Option<Subscriber>> {
+    let subscriber_id = sqlx::query!(
+        r#"
+        select subscriber_id from unsubscribe_tokens
+        where unsubscribe_token = $1;
+        "#,
+        unsubscribe_token,
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to query for an unsubscribe token")?;
+    match subscriber_id {
+        Some(r) => get_subscriber(&r.subscriber_id, pool).await,
+        None => Ok(None),
+    }
+}
+
+/// Asks before unsubscribing, so that mail scanners following the link
+/// don't unsubscribe anybody.
+#[tracing::instrument(name = "Show the unsubscribe page", skip_all)]
+pub async fn unsubscribe_form(
+    request: HttpRequest,
+    params: Query<Parameters>,
+    pool: Data<DbPool>,
+    locales: Data<Locales>,
+) -> Result<HttpResponse, UnsubscribeError> {
+    let subscriber = match token_owner(&params.unsubscribe_token, &pool).await?
+    {
+        Some(subscriber) => subscriber,
+        None => {
+            let locale = locales.for_request(&request);
+            return Ok(invalid_link_page(&locales, &locale));
+        }
+    };
+    let locale = &subscriber.locale;
+    let t = |id, args: &[(&str, &str)]| {
+        encode_minimal(&locales.message(locale, id, args))
+    };
+    let question = t(
+        "unsubscribe-question",
+        &[("email", subscriber.email.as_ref())],
+    );
+    let button = t("unsubscribe-button", &[]);
+    let unsubscribe_token = encode_minimal(&params.unsubscribe_token);
+    Ok(subscriber_page(
+        StatusCode::OK,
+        locale,
+        &t("unsubscribe-title", &[]),
+        &format!(
+            r#"
+                <p>{question}</p>
+                <form action="/subscriptions/unsubscribe" method="post">
+                    <input hidden type="text" name="unsubscribe_token" value="{unsubscribe_token}">
+                    <button type="submit">{button}</button>
+                </form>
+            "#,
+        ),
+    ))
+}
+
+#[tracing::instrument(name = "Unsubscribe a subscriber", skip_all)]
+pub async fn unsubscribe(
+    request: HttpRequest,
+    form: Form<Parameters>,
+    pool: Data<DbPool>,
+    locales: Data<Locales>,
+) -> Result<HttpResponse, UnsubscribeError> {
+    let subscriber = match token_owner(&form.unsubscribe_token, &pool).await? {
+        Some(subscriber) => subscriber,
+        None => {
+            let locale = locales.for_request(&request);
+            return Ok(invalid_link_page(&locales, &locale));
+        }
+    };
+    update_subscriber_status(
+        &subscriber.id,
+        SubscriberStatus::Unsubscribed,
+        &pool,
+    )
+    .await?;
+    let locale = &subscriber.locale;
+    let t = |id| encode_minimal(&locales.message(locale, id, &[]));
+    Ok(subscriber_page(
+        StatusCode::OK,
+        locale,
+        &t("unsubscribed-title"),
+        &format!("<p>{}</p>", t("unsubscribed")),
+    ))
+}
@@ -0,0 +1,44 @@
+use crate::{
+    tracking::{record_click, record_open},
+    utils::e500,
+    DbPool,
+};
+use actix_web::{
+    http::header::{CacheControl, CacheDirective, LOCATION},
+    web::{Data, Path},
+    HttpResponse,
+};
+
+/// A transparent 1x1 GIF.
+const PIXEL: &[u8] = b"GIF89a\x01\x00\x01\x00\x80\x00\x00\xff\xff\xff\x00\x00\x00\
+    !\xf9\x04\x01\x00\x00\x00\x00,\x00\x00\x00\x00\x01\x00\x01\x00\x00\x02\x02D\x01\x00;";
+
+/// Serves the open tracking pixel. Unknown tokens get the pixel too, so
+/// that the email still renders.
+#[tracing::instrument(name = "Tracking an open", skip_all)]
+pub async fn track_open(
+    token: Path<String>,
+    pool: Data<DbPool>,
+) -> actix_web::Result<HttpResponse> {
+    record_open(&token, &pool).await.map_err(e500)?;
+    Ok(HttpResponse::Ok()
+        .content_type("image/gif")
+        .insert_header(CacheControl(vec![
+            CacheDirective::NoStore,
+            CacheDirective::Private,
+        ]))
+        .body(PIXEL))
+}
+
+#[tracing::instrument(name = "Tracking a click", skip_all)]
+pub async fn track_click(
+    token: Path<String>,
+    pool: Data<DbPool>,
+) -> actix_web::Result<HttpResponse> {
+    match record_click(&token, &pool).await.map_err(e500)? {
+        Some(url) => Ok(HttpResponse::Found()
+            .insert_header((LOCATION, url))
+            .finish()),
+        None => Ok(HttpResponse::NotFound().finish()),
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{
+    deliveries::{update_delivery, DeliveryStatus},
+    domain::SubscriberStatus,
+    subscribers::suppress_subscriber,
+    utils::e500,
+    DbPool,
+};
+use actix_web::{
+    web::{Data, Json},
+    HttpResponse,
+};
+use serde::Deserialize;
+
+/// The fields we use of Postmark's delivery, bounce and spam complaint
+/// webhooks. Other kinds of events are accepted and ignored.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct PostmarkEvent {
+    record_type: String,
+    #[serde(rename = "MessageID", default)]
+    message_id: String,
+    /// The kind of bounce, e.g. `HardBounce` or `SoftBounce`.
+    #[serde(rename = "Type", default)]
+    kind: String,
+    #[serde(default)]
+    email: String,
+    /// Whether Postmark stopped sending to the address.
+    #[serde(default)]
+    inactive: bool,
+}
+
+impl PostmarkEvent {
+    fn delivery_status(&self) -> Option<DeliveryStatus> {
+        match self.record_type.as_str() {
+            "Delivery" => Some(DeliveryStatus::Delivered),
+            "Bounce" => Some(DeliveryStatus::Bounced),
+            "SpamComplaint" => Some(DeliveryStatus::Complained),
+            _ => None,
+        }
+    }
+
+    /// What the subscriber's status becomes, if anything changes.
+    fn subscriber_status(&self) -> Option<SubscriberStatus> {
+        match self.record_type.as_str() {
+            "Bounce" if self.kind == "HardBounce" || self.inactive => {
+                Some(SubscriberStatus::Bounced)
+            }
+            "SpamComplaint" => Some(SubscriberStatus::Complained),
+            _ => None,
+        }
+    }
+}
+
+#[tracing::instrument(
+    name = "Handling a Postmark webhook",
+    skip_all,
+    fields(
+        record_type = %event.record_type,
+        kind = %event.kind,
+        message_id = %event.message_id
+    )
+)]
+pub async fn postmark_webhook(
+    event: Json<PostmarkEvent>,
+    pool: Data<DbPool>,
+) -> actix_web::Result<HttpResponse> {
+    if let Some(status) = event.delivery_status() {
+        let detail = Some(event.kind.as_str()).filter(|k| !k.is_empty());
+        if !update_delivery(&event.message_id, status, detail, &pool)
+            .await
+            .map_err(e500)?
+        {
+            tracing::debug!("The event doesn't change any issue delivery");
+        }
+    }
+    let status = match event.subscriber_status() {
+        Some(status) => status,
+        None => return Ok(HttpResponse::Ok().finish()),
+    };
+    if !suppress_subscriber(&event.email, status, &pool)
+        .await
+        .map_err(e500)?
+    {
+        tracing::warn!("The event is about an address nobody subscribed with");
+    }
+    // Postmark retries anything but a success, which wouldn't help here.
+    Ok(HttpResponse::Ok().finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PostmarkEvent;
+    use crate::domain::SubscriberStatus;
+
+    fn event(record_type: &str, kind: &str, inactive: bool) -> PostmarkEvent {
+        PostmarkEvent {
+            record_type: record_type.into(),
+            message_id: "b7bc2f4a-e38e-4336-af7d-e6c392c2f817".into(),
+            kind: kind.into(),
+            email: "ursula@example.com".into(),
+            inactive,
+        }
+    }
+
+    #[test]
+    fn only_permanent_failures_suppress_subscribers() {
+        let cases = [
+            (
+                event("Bounce", "HardBounce", true),
+                Some(SubscriberStatus::Bounced),
+            ),
+            (event("Bounce", "SoftBounce", false), None),
+            (
+                event("Bounce", "SpamNotification", true),
+                Some(SubscriberStatus::Bounced),
+            ),
+            (
+                event("SpamComplaint", "SpamComplaint", true),
+                Some(SubscriberStatus::Complained),
+            ),
+            (event("Delivery", "", false), None),
+        ];
+        for (event, status) in cases {
+            assert_eq!(event.subscriber_status(), status, "{event:?}");
+        }
+    }
+}
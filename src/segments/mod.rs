@@ -0,0 +1,49 @@
+//! Segments narrow the recipients of an issue down with a small filter
+//! language, e.g. `tag = beta and subscribed before 2026-01-01`.
+//!
+//! ```text
+//! segment   := or
+//! or        := and ("or" and)*
+//! and       := unary ("and" unary)*
+//! unary     := "not" unary | "(" or ")" | condition
+//! condition := "tag" ("=" | "!=") TAG
+//!            | "list" ("=" | "!=") LIST
+//!            | "domain" ("=" | "!=") DOMAIN
+//!            | "subscribed" ("before" | "after") YYYY-MM-DD
+//! ```
+//!
+//! Keywords are case-insensitive and values may be double-quoted. Every
+//! `not`, parenthesis and chained `and` or `or` nests one level deeper, up
+//! to 32 levels.
+mod parser;
+mod sql;
+
+pub use parser::SegmentError;
+pub use sql::*;
+
+use crate::domain::{ListSlug, Tag};
+use chrono::NaiveDate;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Segment {
+    And(Box<Segment>, Box<Segment>),
+    Or(Box<Segment>, Box<Segment>),
+    Not(Box<Segment>),
+    Condition(Condition),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Condition {
+    Tag(Tag),
+    List(ListSlug),
+    /// The part of the email address after the `@`, lowercase.
+    Domain(String),
+    SubscribedBefore(NaiveDate),
+    SubscribedAfter(NaiveDate),
+}
+
+impl Segment {
+    pub fn parse(input: &str) -> Result<Self, SegmentError> {
+        parser::parse(input)
+    }
+}
@@ -0,0 +1,417 @@
+use super::{Condition, Segment};
+use chrono::NaiveDate;
+
+/// How deep segments may nest, since they are parsed, turned into SQL and
+/// dropped recursively.
+const MAX_DEPTH: usize = 32;
+
+#[derive(Debug, thiserror::Error)]
+#[error("{message} at character {position}")]
+pub struct SegmentError {
+    message: String,
+    /// 1-based, pointing at the offending token or past the end.
+    position: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    LeftParen,
+    RightParen,
+    Equal,
+    NotEqual,
+    Word(String),
+    Quoted(String),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::LeftParen => write!(f, "'('"),
+            Token::RightParen => write!(f, "')'"),
+            Token::Equal => write!(f, "'='"),
+            Token::NotEqual => write!(f, "'!='"),
+            Token::Word(word) => write!(f, "'{word}'"),
+            Token::Quoted(value) => write!(f, "\"{value}\""),
+        }
+    }
+}
+
+fn error(message: impl Into<String>, position: usize) -> SegmentError {
+    SegmentError {
+        message: message.into(),
+        position,
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, SegmentError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().enumerate().peekable();
+    while let Some((i, c)) = chars.next() {
+        let position = i + 1;
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            '=' => Token::Equal,
+            '!' => match chars.next() {
+                Some((_, '=')) => Token::NotEqual,
+                _ => return Err(error("Expected '=' after '!'", position)),
+            },
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, c)) => value.push(c),
+                        None => {
+                            return Err(error(
+                                "Unterminated quoted value",
+                                position,
+                            ))
+                        }
+                    }
+                }
+                Token::Quoted(value)
+            }
+            c => {
+                let mut word = c.to_string();
+                while let Some((_, c)) = chars.peek() {
+                    if c.is_whitespace() || "()=!\"".contains(*c) {
+                        break;
+                    }
+                    word.push(*c);
+                    chars.next();
+                }
+                Token::Word(word)
+            }
+        };
+        tokens.push((position, token));
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+    end: usize,
+    /// Grows with every `not`, `(` and chained `and` or `or`.
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(_, t)| t)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens.get(self.next).map_or(self.end, |(p, _)| *p)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.next).map(|(_, t)| t.clone());
+        self.next += 1;
+        token
+    }
+
+    fn expected(&self, what: &str) -> SegmentError {
+        let found = match self.peek() {
+            Some(token) => format!("found {token}"),
+            None => "found the end of the segment".into(),
+        };
+        error(format!("Expected {what}, {found}"), self.position())
+    }
+
+    /// Goes one level deeper, before consuming the token that does so.
+    fn nest(&mut self) -> Result<(), SegmentError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(error("Segment nested too deeply", self.position()));
+        }
+        Ok(())
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(
+            self.peek(),
+            Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword)
+        )
+    }
+
+    fn or(&mut self) -> Result<Segment, SegmentError> {
+        let depth = self.depth;
+        let mut segment = self.and()?;
+        while self.is_keyword("or") {
+            self.nest()?;
+            self.advance();
+            segment = Segment::Or(Box::new(segment), Box::new(self.and()?));
+        }
+        self.depth = depth;
+        Ok(segment)
+    }
+
+    fn and(&mut self) -> Result<Segment, SegmentError> {
+        let depth = self.depth;
+        let mut segment = self.unary()?;
+        while self.is_keyword("and") {
+            self.nest()?;
+            self.advance();
+            segment = Segment::And(Box::new(segment), Box::new(self.unary()?));
+        }
+        self.depth = depth;
+        Ok(segment)
+    }
+
+    fn unary(&mut self) -> Result<Segment, SegmentError> {
+        let depth = self.depth;
+        if self.is_keyword("not") {
+            self.nest()?;
+            self.advance();
+            let segment = Segment::Not(Box::new(self.unary()?));
+            self.depth = depth;
+            return Ok(segment);
+        }
+        if self.peek() == Some(&Token::LeftParen) {
+            self.nest()?;
+            self.advance();
+            let segment = self.or()?;
+            if self.peek() != Some(&Token::RightParen) {
+                return Err(self.expected("')'"));
+            }
+            self.advance();
+            self.depth = depth;
+            return Ok(segment);
+        }
+        self.condition()
+    }
+
+    fn condition(&mut self) -> Result<Segment, SegmentError> {
+        const FIELDS: &str = "tag, list, domain or subscribed";
+        let field = match self.peek() {
+            Some(Token::Word(w)) => w.to_lowercase(),
+            _ => return Err(self.expected(FIELDS)),
+        };
+        if field == "subscribed" {
+            self.advance();
+            let before = if self.is_keyword("before") {
+                true
+            } else if self.is_keyword("after") {
+                false
+            } else {
+                return Err(self.expected("'before' or 'after'"));
+            };
+            self.advance();
+            let position = self.position();
+            let date = NaiveDate::parse_from_str(&self.value()?, "%Y-%m-%d")
+                .map_err(|_| error("Expected a YYYY-MM-DD date", position))?;
+            let condition = if before {
+                Condition::SubscribedBefore(date)
+            } else {
+                Condition::SubscribedAfter(date)
+            };
+            return Ok(Segment::Condition(condition));
+        }
+        if !["tag", "list", "domain"].contains(&field.as_str()) {
+            return Err(self.expected(FIELDS));
+        }
+        self.advance();
+        let negated = match self.peek() {
+            Some(Token::Equal) => false,
+            Some(Token::NotEqual) => true,
+            _ => return Err(self.expected("'=' or '!='")),
+        };
+        self.advance();
+        let position = self.position();
+        let value = self.value()?;
+        let condition = match field.as_str() {
+            "tag" => Condition::Tag(
+                value.try_into().map_err(|e| error(e, position))?,
+            ),
+            "list" => Condition::List(
+                value.try_into().map_err(|e| error(e, position))?,
+            ),
+            _ => Condition::Domain(value.to_lowercase()),
+        };
+        let segment = Segment::Condition(condition);
+        if negated {
+            Ok(Segment::Not(Box::new(segment)))
+        } else {
+            Ok(segment)
+        }
+    }
+
+    fn value(&mut self) -> Result<String, SegmentError> {
+        match self.peek() {
+            Some(Token::Word(value)) | Some(Token::Quoted(value)) => {
+                let value = value.clone();
+                self.advance();
+                Ok(value)
+            }
+            _ => Err(self.expected("a value")),
+        }
+    }
+}
+
+pub(super) fn parse(input: &str) -> Result<Segment, SegmentError> {
+    let mut parser = Parser {
+        tokens: tokenize(input)?,
+        next: 0,
+        end: input.chars().count() + 1,
+        depth: 0,
+    };
+    let segment = parser.or()?;
+    if parser.peek().is_some() {
+        return Err(parser.expected("'and', 'or' or the end of the segment"));
+    }
+    Ok(segment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+    use crate::segments::{Condition, Segment};
+    use chrono::NaiveDate;
+
+    fn tag(tag: &str) -> Segment {
+        Segment::Condition(Condition::Tag(tag.to_string().try_into().unwrap()))
+    }
+
+    fn list(list: &str) -> Segment {
+        Segment::Condition(Condition::List(
+            list.to_string().try_into().unwrap(),
+        ))
+    }
+
+    fn not(segment: Segment) -> Segment {
+        Segment::Not(Box::new(segment))
+    }
+
+    fn and(left: Segment, right: Segment) -> Segment {
+        Segment::And(Box::new(left), Box::new(right))
+    }
+
+    fn or(left: Segment, right: Segment) -> Segment {
+        Segment::Or(Box::new(left), Box::new(right))
+    }
+
+    fn error_of(input: &str) -> String {
+        parse(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn a_single_condition_is_parsed() {
+        assert_eq!(parse("tag = beta").unwrap(), tag("beta"));
+        assert_eq!(
+            parse("list=product-updates").unwrap(),
+            list("product-updates")
+        );
+        assert_eq!(
+            parse(r#"domain = "Example.COM""#).unwrap(),
+            Segment::Condition(Condition::Domain("example.com".into()))
+        );
+    }
+
+    #[test]
+    fn subscription_dates_are_parsed() {
+        let date = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        assert_eq!(
+            parse("subscribed before 2026-01-01").unwrap(),
+            Segment::Condition(Condition::SubscribedBefore(date))
+        );
+        assert_eq!(
+            parse("SUBSCRIBED AFTER 2026-01-01").unwrap(),
+            Segment::Condition(Condition::SubscribedAfter(date))
+        );
+    }
+
+    #[test]
+    fn not_equal_negates_the_condition() {
+        assert_eq!(parse("tag != beta").unwrap(), not(tag("beta")));
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(
+            parse("tag = a or tag = b and not tag = c").unwrap(),
+            or(tag("a"), and(tag("b"), not(tag("c"))))
+        );
+    }
+
+    #[test]
+    fn parentheses_group_conditions() {
+        assert_eq!(
+            parse("(tag = a or tag = b) and tag = c").unwrap(),
+            and(or(tag("a"), tag("b")), tag("c"))
+        );
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_token() {
+        assert_eq!(
+            error_of("tag = beta and age = 3"),
+            "Expected tag, list, domain or subscribed, found 'age' \
+            at character 16"
+        );
+        assert_eq!(
+            error_of("tag beta"),
+            "Expected '=' or '!=', found 'beta' at character 5"
+        );
+        assert_eq!(
+            error_of("(tag = a"),
+            "Expected ')', found the end of the segment at character 9"
+        );
+        assert_eq!(
+            error_of("tag = a tag = b"),
+            "Expected 'and', 'or' or the end of the segment, found 'tag' \
+            at character 9"
+        );
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        assert_eq!(
+            error_of("tag = Beta"),
+            "Beta is not a valid tag at character 7"
+        );
+        assert_eq!(
+            error_of("subscribed before 2026-13-01"),
+            "Expected a YYYY-MM-DD date at character 19"
+        );
+        assert_eq!(
+            error_of(r#"tag = "beta"#),
+            "Unterminated quoted value at character 7"
+        );
+        assert_eq!(
+            error_of("tag ! beta"),
+            "Expected '=' after '!' at character 5"
+        );
+    }
+
+    #[test]
+    fn deeply_nested_segments_are_rejected() {
+        let nested = |prefix: &str, suffix: &str, depth: usize| {
+            format!("{}tag = a{}", prefix.repeat(depth), suffix.repeat(depth))
+        };
+        assert!(parse(&nested("(", ")", 32)).is_ok());
+        assert_eq!(
+            error_of(&nested("(", ")", 33)),
+            "Segment nested too deeply at character 33"
+        );
+        assert_eq!(
+            error_of(&nested("not ", "", 10_000)),
+            "Segment nested too deeply at character 129"
+        );
+        assert_eq!(
+            error_of(&nested("", " or tag = a", 10_000)),
+            "Segment nested too deeply at character 361"
+        );
+    }
+
+    #[test]
+    fn an_empty_segment_is_rejected() {
+        assert_eq!(
+            error_of("  "),
+            "Expected tag, list, domain or subscribed, found the end of the \
+            segment at character 3"
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use super::{Condition, Segment};
+use crate::{Database, DbPool};
+use anyhow::Context;
+use sqlx::{QueryBuilder, Row};
+use uuid::Uuid;
+
+impl Segment {
+    /// Appends the segment as a boolean SQL expression over the
+    /// subscriptions row aliased `s`, binding every value.
+    pub fn push_sql(&self, builder: &mut QueryBuilder<'_, Database>) {
+        match self {
+            Segment::And(left, right) | Segment::Or(left, right) => {
+                let operator = match self {
+                    Segment::And(..) => " and ",
+                    _ => " or ",
+                };
+                builder.push("(");
+                left.push_sql(builder);
+                builder.push(operator);
+                right.push_sql(builder);
+                builder.push(")");
+            }
+            Segment::Not(segment) => {
+                builder.push("not ");
+                segment.push_sql(builder);
+            }
+            Segment::Condition(condition) => condition.push_sql(builder),
+        }
+    }
+}
+
+impl Condition {
+    fn push_sql(&self, builder: &mut QueryBuilder<'_, Database>) {
+        match self {
+            Condition::Tag(tag) => {
+                builder
+                    .push(
+                        "exists (select 1 from subscriber_tags t \
+                        where t.subscriber_id = s.id and t.tag = ",
+                    )
+                    .push_bind(tag.to_string())
+                    .push(")");
+            }
+            Condition::List(list) => {
+                builder
+                    .push(
+                        "exists (select 1 from list_memberships lm \
+                        join lists l using (list_id) \
+                        where lm.subscriber_id = s.id and l.slug = ",
+                    )
+                    .push_bind(list.to_string())
+                    .push(")");
+            }
+            Condition::Domain(domain) => {
+                builder
+                    .push("lower(split_part(s.email, '@', 2)) = ")
+                    .push_bind(domain.clone());
+            }
+            Condition::SubscribedBefore(date) => {
+                builder.push("s.subscribed_at < ").push_bind(*date);
+            }
+            Condition::SubscribedAfter(date) => {
+                builder.push("s.subscribed_at >= ").push_bind(*date);
+            }
+        }
+    }
+}
+
+/// Appends a `from … where …` clause selecting, as `s`, the confirmed
+/// subscribers who belong to any of the lists and match the segment,
+/// leaving out those who paused delivery.
+pub fn push_recipients(
+    builder: &mut QueryBuilder<'_, Database>,
+    list_ids: &[Uuid],
+    segment: Option<&Segment>,
+) {
+    builder
+        .push(
+            " from subscriptions s \
+            where s.status = 'confirmed' \
+            and (s.paused_until is null or s.paused_until <= current_date) \
+            and exists (select 1 from list_memberships m \
+            where m.subscriber_id = s.id and m.list_id = any(",
+        )
+        .push_bind(list_ids.to_vec())
+        .push("))");
+    if let Some(segment) = segment {
+        builder.push(" and ");
+        segment.push_sql(builder);
+    }
+}
+
+/// How many subscribers an issue sent to the lists and segment would reach.
+#[tracing::instrument(name = "Counting recipients", skip(pool))]
+pub async fn count_recipients(
+    list_ids: &[Uuid],
+    segment: Option<&Segment>,
+    pool: &DbPool,
+) -> anyhow::Result<i64> {
+    let mut builder = QueryBuilder::new("select count(*)");
+    push_recipients(&mut builder, list_ids, segment);
+    builder
+        .build()
+        .fetch_one(pool)
+        .await
+        .and_then(|row| row.try_get(0))
+        .context("Failed to count recipients")
+}
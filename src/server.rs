@@ -1,18 +1,30 @@
 use crate::{
-    auth::reject_anonynous_users, routes::*, Config, DbPool, EmailClient,
+    auth::{
+        reject_anonynous_users, reject_forged_requests,
+        reject_unauthenticated_clients, reject_unauthenticated_webhooks,
+    },
+    configuration::ApplicationConfig,
+    routes::*,
+    session::{mark_remembered_sessions, persist_remembered_sessions},
+    session_store::SessionStoreBackend,
+    utils::e400,
+    Config, DbPool, EmailClient, EmailTemplates, Locales,
 };
-use actix_session::{storage::RedisSessionStore, SessionMiddleware};
+use actix_session::{config::BrowserSession, SessionMiddleware};
 use actix_web::{
-    cookie::Key,
+    cookie::{time, Key},
     dev::Server as ActixServer,
-    web::{self, get, post, Data},
+    web::{
+        self, delete, get, patch, post, Data, JsonConfig, PathConfig,
+        QueryConfig,
+    },
     App, HttpServer,
 };
 use actix_web_flash_messages::{
     storage::CookieMessageStore, FlashMessagesFramework,
 };
 use actix_web_lab::middleware::from_fn;
-use secrecy::{ExposeSecret, Secret};
+use secrecy::ExposeSecret;
 use std::net::TcpListener;
 use tracing_actix_web::TracingLogger;
 
@@ -34,18 +46,19 @@ impl Server {
     pub async fn build(config: Config) -> anyhow::Result<Self> {
         let db_pool = DbPool::connect_lazy_with(config.database.with_db());
         let email_client = EmailClient::new(config.email_client);
+        let locales = Locales::load(config.locales)?;
+        let templates = EmailTemplates::load(config.email_templates, &locales)?;
         let addr =
             format!("{}:{}", config.application.host, config.application.port);
         let listener = TcpListener::bind(addr)?;
         let port = listener.local_addr().unwrap().port();
-        let base_url = AppBaseUrl(config.application.base_url);
         let server = Self::http_server(
             listener,
             db_pool,
             email_client,
-            base_url,
-            config.application.redis_url,
-            config.application.hmac_secret,
+            templates,
+            locales,
+            config.application,
         )
         .await?;
         Ok(Self { port, server })
@@ -59,34 +72,65 @@ impl Server {
         listener: TcpListener,
         db_pool: DbPool,
         email_client: EmailClient,
-        base_url: AppBaseUrl,
-        redis_url: Secret<String>,
-        hmac_secret: Secret<String>,
+        templates: EmailTemplates,
+        locales: Locales,
+        application: ApplicationConfig,
     ) -> anyhow::Result<ActixServer> {
+        let session_config = application.session;
+        let session_store = SessionStoreBackend::build(
+            session_config.store,
+            application.redis_url,
+            db_pool.clone(),
+        )
+        .await?;
         let db_pool = Data::new(db_pool);
         let email_client = Data::new(email_client);
-        let base_url = Data::new(base_url);
-        let secret_key = Key::from(hmac_secret.expose_secret().as_bytes());
-        let redis_store =
-            RedisSessionStore::new(redis_url.expose_secret()).await?;
+        let templates = Data::new(templates);
+        let locales = Data::new(locales);
+        let base_url = Data::new(AppBaseUrl(application.base_url));
+        let opt_in = Data::new(application.opt_in);
+        let webhooks = Data::new(application.webhooks);
+        let secret_key =
+            Key::from(application.hmac_secret.expose_secret().as_bytes());
         let message_store =
             CookieMessageStore::builder(secret_key.clone()).build();
         let message_framework =
             FlashMessagesFramework::builder(message_store).build();
+        // Kept as long as a remembered session may last, the middleware
+        // ends shorter ones.
+        let session_ttl =
+            time::Duration::try_from(session_config.remember_me_max_age)?;
+        let session_config = Data::new(session_config);
         HttpServer::new(move || {
             App::new()
                 .wrap(message_framework.clone())
-                .wrap(SessionMiddleware::new(
-                    redis_store.clone(),
-                    secret_key.clone(),
-                ))
+                .wrap(from_fn(mark_remembered_sessions))
+                .wrap(
+                    SessionMiddleware::builder(
+                        session_store.clone(),
+                        secret_key.clone(),
+                    )
+                    .cookie_name(session_config.cookie_name.clone())
+                    .cookie_secure(session_config.cookie_secure)
+                    .cookie_same_site(session_config.cookie_same_site.into())
+                    .session_lifecycle(
+                        BrowserSession::default().state_ttl(session_ttl),
+                    )
+                    .build(),
+                )
+                .wrap(from_fn(persist_remembered_sessions))
                 .wrap(TracingLogger::default())
                 .route("/", get().to(home))
                 .route("/health_check", get().to(health_check))
-                .route("/login", get().to(login_form))
-                .route("/login", post().to(login))
+                .service(
+                    web::resource("/login")
+                        .wrap(from_fn(reject_forged_requests))
+                        .route(get().to(login_form))
+                        .route(post().to(login)),
+                )
                 .service(
                     web::scope("/admin")
+                        .wrap(from_fn(reject_forged_requests))
                         .wrap(from_fn(reject_anonynous_users))
                         .route("/logout", post().to(logout))
                         .route("/dashboard", get().to(admin_dashboard))
@@ -96,13 +140,135 @@ impl Server {
                             "/newsletters",
                             get().to(publish_newsletter_form),
                         )
-                        .route("/newsletters", post().to(publish_newsletter)),
+                        .route("/newsletters", post().to(publish_newsletter))
+                        .route(
+                            "/newsletters/preview",
+                            post().to(preview_recipients),
+                        )
+                        .route("/assets", get().to(assets))
+                        .route("/stats", get().to(newsletter_stats))
+                        .route(
+                            "/stats/{newsletter_issue_id}",
+                            get().to(newsletter_issue_stats),
+                        )
+                        .route("/assets", post().to(upload_asset))
+                        .route("/sessions", get().to(active_sessions))
+                        .route(
+                            "/sessions/revoke",
+                            post().to(revoke_active_session),
+                        )
+                        .route(
+                            "/sessions/revoke_all",
+                            post().to(revoke_all_active_sessions),
+                        )
+                        .route("/api_tokens", get().to(api_tokens))
+                        .route("/api_tokens", post().to(create_token))
+                        .route("/api_tokens/revoke", post().to(revoke_token))
+                        .route("/exports", get().to(exports))
+                        .route(
+                            "/exports/subscriptions",
+                            get().to(subscriptions_export),
+                        )
+                        .route(
+                            "/exports/newsletter_issues",
+                            get().to(newsletter_issues_export),
+                        )
+                        .route("/gdpr", get().to(data_requests_form))
+                        .route("/gdpr/export", post().to(export_data_subject))
+                        .route("/gdpr/erase", post().to(erase_data_subject))
+                        .route("/lists", get().to(mailing_lists))
+                        .route("/lists", post().to(create_mailing_list))
+                        .route("/lists/opt_in", post().to(change_list_opt_in))
+                        .route("/tags", get().to(tags))
+                        .route("/tags/add", post().to(add_tag))
+                        .route("/tags/remove", post().to(remove_tag))
+                        .route("/tags/delete", post().to(drop_tag))
+                        .route("/subscribers", get().to(subscribers))
+                        .route(
+                            "/subscribers/import",
+                            get().to(import_subscribers_form),
+                        )
+                        .route(
+                            "/subscribers/import",
+                            post().to(import_subscribers_csv),
+                        )
+                        .route(
+                            "/subscribers/resend_confirmation",
+                            post().to(resend_confirmation),
+                        )
+                        .route(
+                            "/subscribers/unsubscribe",
+                            post().to(unsubscribe_subscriber),
+                        )
+                        .route(
+                            "/subscribers/delete",
+                            post().to(remove_subscriber),
+                        ),
+                )
+                .service(
+                    web::scope("/api/v1")
+                        .wrap(from_fn(json_errors))
+                        .wrap(from_fn(reject_unauthenticated_clients))
+                        .app_data(
+                            JsonConfig::default().error_handler(|e, _| e400(e)),
+                        )
+                        .app_data(
+                            QueryConfig::default()
+                                .error_handler(|e, _| e400(e)),
+                        )
+                        .app_data(
+                            PathConfig::default().error_handler(|e, _| e400(e)),
+                        )
+                        .route(
+                            "/newsletters",
+                            post().to(publish_newsletter_issue),
+                        )
+                        .route("/subscribers", get().to(api_list_subscribers))
+                        .route(
+                            "/subscribers/{subscriber_id}",
+                            get().to(api_get_subscriber),
+                        )
+                        .route(
+                            "/subscribers/{subscriber_id}",
+                            patch().to(api_update_subscriber),
+                        )
+                        .route(
+                            "/subscribers/{subscriber_id}",
+                            delete().to(api_delete_subscriber),
+                        ),
                 )
+                .service(
+                    web::scope("/webhooks")
+                        .wrap(from_fn(reject_unauthenticated_webhooks))
+                        .route("/postmark", post().to(postmark_webhook)),
+                )
+                .route("/t/o/{token}", get().to(track_open))
+                .route("/t/c/{token}", get().to(track_click))
                 .route("/subscriptions", post().to(subscribe))
                 .route("/subscriptions/confirm", get().to(confirm_subscription))
+                .route("/subscriptions/preferences", get().to(preferences_form))
+                .route(
+                    "/subscriptions/preferences",
+                    post().to(update_subscriber_preferences),
+                )
+                .route("/subscriptions/unsubscribe", get().to(unsubscribe_form))
+                .route("/subscriptions/unsubscribe", post().to(unsubscribe))
+                .route(
+                    "/subscriptions/email_change",
+                    post().to(request_change_of_email),
+                )
+                .route(
+                    "/subscriptions/email_change/confirm",
+                    get().to(confirm_change_of_email),
+                )
                 .app_data(db_pool.clone())
                 .app_data(email_client.clone())
+                .app_data(templates.clone())
+                .app_data(locales.clone())
                 .app_data(base_url.clone())
+                .app_data(opt_in.clone())
+                .app_data(session_config.clone())
+                .app_data(webhooks.clone())
         })
         .listen(listener)
         .map(|s| s.run())
@@ -1,5 +1,15 @@
+use crate::{configuration::SessionConfig, utils::e500};
 use actix_session::{Session as ActixSession, SessionExt};
-use actix_web::FromRequest;
+use actix_web::{
+    body::MessageBody,
+    cookie::{time, Cookie},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{HeaderValue, SET_COOKIE},
+    web::Data,
+    FromRequest,
+};
+use actix_web_lab::middleware::Next;
+use chrono::{DateTime, TimeZone, Utc};
 use std::future::{ready, Ready};
 use uuid::Uuid;
 
@@ -7,6 +17,11 @@ pub struct Session(ActixSession);
 
 impl Session {
     const USER_ID_KEY: &'static str = "USER_ID";
+    const SESSION_ID_KEY: &'static str = "SESSION_ID";
+    const REMEMBER_ME_KEY: &'static str = "REMEMBER_ME";
+    const LOGGED_IN_AT_KEY: &'static str = "LOGGED_IN_AT";
+    const LAST_SEEN_AT_KEY: &'static str = "LAST_SEEN_AT";
+    const CSRF_TOKEN_KEY: &'static str = "CSRF_TOKEN";
 
     pub fn renew(&self) {
         self.0.renew();
@@ -28,6 +43,80 @@ impl Session {
     ) -> Result<Option<Uuid>, actix_session::SessionGetError> {
         self.0.get(Self::USER_ID_KEY)
     }
+
+    pub fn insert_session_id(
+        &self,
+        session_id: Uuid,
+    ) -> Result<(), actix_session::SessionInsertError> {
+        self.0.insert(Self::SESSION_ID_KEY, session_id)
+    }
+
+    pub fn get_session_id(
+        &self,
+    ) -> Result<Option<Uuid>, actix_session::SessionGetError> {
+        self.0.get(Self::SESSION_ID_KEY)
+    }
+
+    pub fn insert_remember_me(
+        &self,
+        remember_me: bool,
+    ) -> Result<(), actix_session::SessionInsertError> {
+        self.0.insert(Self::REMEMBER_ME_KEY, remember_me)
+    }
+
+    pub fn get_remember_me(
+        &self,
+    ) -> Result<Option<bool>, actix_session::SessionGetError> {
+        self.0.get(Self::REMEMBER_ME_KEY)
+    }
+
+    pub fn insert_logged_in_at(
+        &self,
+        timestamp: DateTime<Utc>,
+    ) -> Result<(), actix_session::SessionInsertError> {
+        self.0.insert(Self::LOGGED_IN_AT_KEY, timestamp.timestamp())
+    }
+
+    pub fn get_logged_in_at(
+        &self,
+    ) -> Result<Option<DateTime<Utc>>, actix_session::SessionGetError> {
+        self.get_timestamp(Self::LOGGED_IN_AT_KEY)
+    }
+
+    pub fn insert_last_seen_at(
+        &self,
+        timestamp: DateTime<Utc>,
+    ) -> Result<(), actix_session::SessionInsertError> {
+        self.0.insert(Self::LAST_SEEN_AT_KEY, timestamp.timestamp())
+    }
+
+    pub fn get_last_seen_at(
+        &self,
+    ) -> Result<Option<DateTime<Utc>>, actix_session::SessionGetError> {
+        self.get_timestamp(Self::LAST_SEEN_AT_KEY)
+    }
+
+    pub fn insert_csrf_token(
+        &self,
+        token: String,
+    ) -> Result<(), actix_session::SessionInsertError> {
+        self.0.insert(Self::CSRF_TOKEN_KEY, token)
+    }
+
+    pub fn get_csrf_token(
+        &self,
+    ) -> Result<Option<String>, actix_session::SessionGetError> {
+        self.0.get(Self::CSRF_TOKEN_KEY)
+    }
+
+    fn get_timestamp(
+        &self,
+        key: &str,
+    ) -> Result<Option<DateTime<Utc>>, actix_session::SessionGetError> {
+        self.0.get::<i64>(key).map(|timestamp| {
+            timestamp.and_then(|t| Utc.timestamp_opt(t, 0).single())
+        })
+    }
 }
 
 impl FromRequest for Session {
@@ -40,3 +129,62 @@ impl FromRequest for Session {
         ready(Ok(Self(req.get_session())))
     }
 }
+
+/// Set on the responses to users who asked to be remembered.
+struct RememberedSession;
+
+/// Marks the responses of remembered sessions for
+/// [`persist_remembered_sessions`]. Goes inside the session middleware,
+/// while the session state can still be read.
+pub async fn mark_remembered_sessions(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> actix_web::Result<ServiceResponse<impl MessageBody>> {
+    let session = Session(req.get_session());
+    let mut res = next.call(req).await?;
+    if session.get_remember_me()?.unwrap_or(false) {
+        res.response_mut()
+            .extensions_mut()
+            .insert(RememberedSession);
+    }
+    Ok(res)
+}
+
+/// Session cookies end with the browser session, unless the user asked to
+/// be remembered: theirs last as long as the session may. Goes outside the
+/// session middleware, once it has set the cookie.
+pub async fn persist_remembered_sessions(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> actix_web::Result<ServiceResponse<impl MessageBody>> {
+    let config = req
+        .app_data::<Data<SessionConfig>>()
+        .ok_or_else(|| e500("Session is not configured"))?
+        .clone();
+    let mut res = next.call(req).await?;
+    if res
+        .response()
+        .extensions()
+        .get::<RememberedSession>()
+        .is_none()
+    {
+        return Ok(res);
+    }
+    let max_age =
+        time::Duration::try_from(config.remember_me_max_age).map_err(e500)?;
+    let headers = res.headers_mut();
+    let cookies = headers
+        .get_all(SET_COOKIE)
+        .filter_map(|h| h.to_str().ok())
+        .filter_map(|h| Cookie::parse(h.to_owned()).ok())
+        .collect::<Vec<_>>();
+    headers.remove(SET_COOKIE);
+    for mut cookie in cookies {
+        if cookie.name() == config.cookie_name && cookie.max_age().is_none() {
+            cookie.set_max_age(max_age);
+        }
+        let value = HeaderValue::from_str(&cookie.to_string()).map_err(e500)?;
+        headers.append(SET_COOKIE, value);
+    }
+    Ok(res)
+}
@@ -0,0 +1,175 @@
+use super::{generate_session_key, SessionState};
+use actix_session::storage::{
+    LoadError, SaveError, SessionKey, SessionStore, UpdateError,
+};
+use actix_web::cookie::time::Duration;
+use anyhow::anyhow;
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Instant,
+};
+
+/// Keeps session state in the process memory.
+/// Sessions do not survive restarts and are not shared between instances,
+/// so it is only suitable for local development and tests.
+#[derive(Clone, Default)]
+pub struct InMemorySessionStore {
+    sessions: Arc<RwLock<HashMap<String, Entry>>>,
+}
+
+struct Entry {
+    state: SessionState,
+    expires_at: Instant,
+}
+
+impl Entry {
+    fn new(state: SessionState, ttl: &Duration) -> Self {
+        let ttl = std::time::Duration::try_from(*ttl).unwrap_or_default();
+        Self {
+            state,
+            expires_at: Instant::now() + ttl,
+        }
+    }
+
+    fn is_expired(&self) -> bool {
+        self.expires_at <= Instant::now()
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl SessionStore for InMemorySessionStore {
+    async fn load(
+        &self,
+        session_key: &SessionKey,
+    ) -> Result<Option<SessionState>, LoadError> {
+        let sessions = self.sessions.read().map_err(|_| {
+            LoadError::Other(anyhow!("Session store is poisoned"))
+        })?;
+        let state = sessions
+            .get(session_key.as_ref())
+            .filter(|e| !e.is_expired())
+            .map(|e| e.state.clone());
+        Ok(state)
+    }
+
+    async fn save(
+        &self,
+        session_state: SessionState,
+        ttl: &Duration,
+    ) -> Result<SessionKey, SaveError> {
+        let session_key = generate_session_key();
+        let mut sessions = self.sessions.write().map_err(|_| {
+            SaveError::Other(anyhow!("Session store is poisoned"))
+        })?;
+        sessions.retain(|_, e| !e.is_expired());
+        sessions.insert(
+            session_key.as_ref().to_owned(),
+            Entry::new(session_state, ttl),
+        );
+        Ok(session_key)
+    }
+
+    async fn update(
+        &self,
+        session_key: SessionKey,
+        session_state: SessionState,
+        ttl: &Duration,
+    ) -> Result<SessionKey, UpdateError> {
+        let is_live = self
+            .sessions
+            .read()
+            .map_err(|_| {
+                UpdateError::Other(anyhow!("Session store is poisoned"))
+            })?
+            .get(session_key.as_ref())
+            .is_some_and(|e| !e.is_expired());
+        if !is_live {
+            return self
+                .save(session_state, ttl)
+                .await
+                .map_err(|e| UpdateError::Other(anyhow!(e)));
+        }
+        self.sessions
+            .write()
+            .map_err(|_| {
+                UpdateError::Other(anyhow!("Session store is poisoned"))
+            })?
+            .insert(
+                session_key.as_ref().to_owned(),
+                Entry::new(session_state, ttl),
+            );
+        Ok(session_key)
+    }
+
+    async fn update_ttl(
+        &self,
+        session_key: &SessionKey,
+        ttl: &Duration,
+    ) -> anyhow::Result<()> {
+        let mut sessions = self
+            .sessions
+            .write()
+            .map_err(|_| anyhow!("Session store is poisoned"))?;
+        if let Some(entry) = sessions.get_mut(session_key.as_ref()) {
+            let state = std::mem::take(&mut entry.state);
+            *entry = Entry::new(state, ttl);
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, session_key: &SessionKey) -> anyhow::Result<()> {
+        self.sessions
+            .write()
+            .map_err(|_| anyhow!("Session store is poisoned"))?
+            .remove(session_key.as_ref());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state() -> SessionState {
+        HashMap::from([("key".to_string(), "value".to_string())])
+    }
+
+    #[tokio::test]
+    async fn saved_state_can_be_loaded() {
+        let store = InMemorySessionStore::default();
+        let key = store.save(state(), &Duration::minutes(1)).await.unwrap();
+        let loaded = store.load(&key).await.unwrap();
+        assert_eq!(loaded, Some(state()));
+    }
+
+    #[tokio::test]
+    async fn expired_state_is_not_loaded() {
+        let store = InMemorySessionStore::default();
+        let key = store.save(state(), &Duration::ZERO).await.unwrap();
+        let loaded = store.load(&key).await.unwrap();
+        assert_eq!(loaded, None);
+    }
+
+    #[tokio::test]
+    async fn deleted_state_is_not_loaded() {
+        let store = InMemorySessionStore::default();
+        let key = store.save(state(), &Duration::minutes(1)).await.unwrap();
+        store.delete(&key).await.unwrap();
+        let loaded = store.load(&key).await.unwrap();
+        assert_eq!(loaded, None);
+    }
+
+    #[tokio::test]
+    async fn updating_an_unknown_session_creates_a_new_one() {
+        let store = InMemorySessionStore::default();
+        let unknown = SessionKey::try_from("unknown".to_string()).unwrap();
+        let key = store
+            .update(unknown, state(), &Duration::minutes(1))
+            .await
+            .unwrap();
+        assert_ne!(key.as_ref(), "unknown");
+        let loaded = store.load(&key).await.unwrap();
+        assert_eq!(loaded, Some(state()));
+    }
+}
@@ -0,0 +1,126 @@
+mod memory;
+mod postgres;
+
+pub use memory::InMemorySessionStore;
+pub use postgres::PostgresSessionStore;
+
+use crate::{configuration::SessionStoreKind, DbPool};
+use actix_session::storage::{
+    LoadError, RedisSessionStore, SaveError, SessionKey, SessionStore,
+    UpdateError,
+};
+use actix_web::cookie::time::Duration;
+use anyhow::Context;
+use secrecy::{ExposeSecret, Secret};
+use std::collections::HashMap;
+
+type SessionState = HashMap<String, String>;
+
+#[derive(Clone)]
+pub enum SessionStoreBackend {
+    Redis(RedisSessionStore),
+    Postgres(PostgresSessionStore),
+    Memory(InMemorySessionStore),
+}
+
+impl SessionStoreBackend {
+    pub async fn build(
+        kind: SessionStoreKind,
+        redis_url: Option<Secret<String>>,
+        db_pool: DbPool,
+    ) -> anyhow::Result<Self> {
+        let store = match kind {
+            SessionStoreKind::Redis => {
+                let redis_url = redis_url.context(
+                    "redis_url must be set to use the redis session store",
+                )?;
+                Self::Redis(
+                    RedisSessionStore::new(redis_url.expose_secret()).await?,
+                )
+            }
+            SessionStoreKind::Postgres => {
+                Self::Postgres(PostgresSessionStore::new(db_pool))
+            }
+            SessionStoreKind::Memory => {
+                Self::Memory(InMemorySessionStore::default())
+            }
+        };
+        Ok(store)
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl SessionStore for SessionStoreBackend {
+    async fn load(
+        &self,
+        session_key: &SessionKey,
+    ) -> Result<Option<SessionState>, LoadError> {
+        match self {
+            Self::Redis(store) => store.load(session_key).await,
+            Self::Postgres(store) => store.load(session_key).await,
+            Self::Memory(store) => store.load(session_key).await,
+        }
+    }
+
+    async fn save(
+        &self,
+        session_state: SessionState,
+        ttl: &Duration,
+    ) -> Result<SessionKey, SaveError> {
+        match self {
+            Self::Redis(store) => store.save(session_state, ttl).await,
+            Self::Postgres(store) => store.save(session_state, ttl).await,
+            Self::Memory(store) => store.save(session_state, ttl).await,
+        }
+    }
+
+    async fn update(
+        &self,
+        session_key: SessionKey,
+        session_state: SessionState,
+        ttl: &Duration,
+    ) -> Result<SessionKey, UpdateError> {
+        match self {
+            Self::Redis(store) => {
+                store.update(session_key, session_state, ttl).await
+            }
+            Self::Postgres(store) => {
+                store.update(session_key, session_state, ttl).await
+            }
+            Self::Memory(store) => {
+                store.update(session_key, session_state, ttl).await
+            }
+        }
+    }
+
+    async fn update_ttl(
+        &self,
+        session_key: &SessionKey,
+        ttl: &Duration,
+    ) -> anyhow::Result<()> {
+        match self {
+            Self::Redis(store) => store.update_ttl(session_key, ttl).await,
+            Self::Postgres(store) => store.update_ttl(session_key, ttl).await,
+            Self::Memory(store) => store.update_ttl(session_key, ttl).await,
+        }
+    }
+
+    async fn delete(&self, session_key: &SessionKey) -> anyhow::Result<()> {
+        match self {
+            Self::Redis(store) => store.delete(session_key).await,
+            Self::Postgres(store) => store.delete(session_key).await,
+            Self::Memory(store) => store.delete(session_key).await,
+        }
+    }
+}
+
+fn generate_session_key() -> SessionKey {
+    use rand::{distributions::Alphanumeric, thread_rng, Rng};
+    let mut rng = thread_rng();
+    std::iter::repeat_with(|| rng.sample(Alphanumeric))
+        .map(char::from)
+        .take(64)
+        .collect::<String>()
+        .try_into()
+        .expect("A 64 characters long key is a valid session key")
+}
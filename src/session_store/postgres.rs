@@ -0,0 +1,158 @@
+use super::{generate_session_key, SessionState};
+use crate::DbPool;
+use actix_session::storage::{
+    LoadError, SaveError, SessionKey, SessionStore, UpdateError,
+};
+use actix_web::cookie::time::Duration;
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+
+/// Keeps session state in the `sessions` table of the application database.
+#[derive(Clone)]
+pub struct PostgresSessionStore {
+    pool: DbPool,
+}
+
+impl PostgresSessionStore {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+}
+
+fn expires_at(ttl: &Duration) -> DateTime<Utc> {
+    Utc::now() + chrono::Duration::seconds(ttl.whole_seconds())
+}
+
+#[async_trait::async_trait(?Send)]
+impl SessionStore for PostgresSessionStore {
+    async fn load(
+        &self,
+        session_key: &SessionKey,
+    ) -> Result<Option<SessionState>, LoadError> {
+        let row = sqlx::query!(
+            r#"
+            select session_state from sessions
+            where session_key = $1
+            and expires_at > now();
+            "#,
+            session_key.as_ref(),
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Failed to load session state")
+        .map_err(LoadError::Other)?;
+        row.map(|r| serde_json::from_str(&r.session_state))
+            .transpose()
+            .context("Failed to deserialize session state")
+            .map_err(LoadError::Deserialization)
+    }
+
+    async fn save(
+        &self,
+        session_state: SessionState,
+        ttl: &Duration,
+    ) -> Result<SessionKey, SaveError> {
+        let session_state = serde_json::to_string(&session_state)
+            .context("Failed to serialize session state")
+            .map_err(SaveError::Serialization)?;
+        let session_key = generate_session_key();
+        let mut transaction = self
+            .pool
+            .begin()
+            .await
+            .context("Failed to acquire connection from the pool")
+            .map_err(SaveError::Other)?;
+        sqlx::query!(r#"delete from sessions where expires_at <= now();"#)
+            .execute(&mut transaction)
+            .await
+            .context("Failed to delete expired sessions")
+            .map_err(SaveError::Other)?;
+        sqlx::query!(
+            r#"
+            insert into sessions (session_key, session_state, expires_at)
+            values ($1, $2, $3);
+            "#,
+            session_key.as_ref(),
+            session_state,
+            expires_at(ttl),
+        )
+        .execute(&mut transaction)
+        .await
+        .context("Failed to save session state")
+        .map_err(SaveError::Other)?;
+        transaction
+            .commit()
+            .await
+            .context("Failed to commit transaction")
+            .map_err(SaveError::Other)?;
+        Ok(session_key)
+    }
+
+    async fn update(
+        &self,
+        session_key: SessionKey,
+        session_state: SessionState,
+        ttl: &Duration,
+    ) -> Result<SessionKey, UpdateError> {
+        let serialized_state = serde_json::to_string(&session_state)
+            .context("Failed to serialize session state")
+            .map_err(UpdateError::Serialization)?;
+        let rows_affected = sqlx::query!(
+            r#"
+            update sessions
+            set session_state = $1, expires_at = $2
+            where session_key = $3
+            and expires_at > now();
+            "#,
+            serialized_state,
+            expires_at(ttl),
+            session_key.as_ref(),
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to update session state")
+        .map_err(UpdateError::Other)?
+        .rows_affected();
+        if rows_affected == 1 {
+            return Ok(session_key);
+        }
+        self.save(session_state, ttl).await.map_err(|e| match e {
+            SaveError::Serialization(e) => UpdateError::Serialization(e),
+            SaveError::Other(e) => UpdateError::Other(e),
+        })
+    }
+
+    async fn update_ttl(
+        &self,
+        session_key: &SessionKey,
+        ttl: &Duration,
+    ) -> anyhow::Result<()> {
+        sqlx::query!(
+            r#"
+            update sessions
+            set expires_at = $1
+            where session_key = $2;
+            "#,
+            expires_at(ttl),
+            session_key.as_ref(),
+        )
+        .execute(&self.pool)
+        .await
+        .map(|_| ())
+        .context("Failed to update session expiration")
+    }
+
+    async fn delete(&self, session_key: &SessionKey) -> anyhow::Result<()> {
+        sqlx::query!(
+            r#"
+            delete from sessions
+            where session_key = $1;
+            "#,
+            session_key.as_ref(),
+        )
+        .execute(&self.pool)
+        .await
+        .map(|_| ())
+        .context("Failed to delete session")
+    }
+}
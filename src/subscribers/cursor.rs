@@ -0,0 +1,61 @@
+use chrono::{DateTime, TimeZone, Utc};
+use uuid::Uuid;
+
+/// Position of a subscriber in the `(subscribed_at, id)` ordering,
+/// encoded as an opaque string for keyset pagination.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cursor {
+    pub subscribed_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl std::fmt::Display for Cursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let raw =
+            format!("{}:{}", self.subscribed_at.timestamp_micros(), self.id);
+        base64::encode_config(raw, base64::URL_SAFE_NO_PAD).fmt(f)
+    }
+}
+
+impl TryFrom<String> for Cursor {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let invalid = || format!("{value} is not a valid cursor");
+        let raw = base64::decode_config(&value, base64::URL_SAFE_NO_PAD)
+            .ok()
+            .and_then(|r| String::from_utf8(r).ok())
+            .ok_or_else(invalid)?;
+        let (micros, id) = raw.split_once(':').ok_or_else(invalid)?;
+        let subscribed_at = micros
+            .parse()
+            .ok()
+            .and_then(|m| Utc.timestamp_micros(m).single())
+            .ok_or_else(invalid)?;
+        let id = id.parse().map_err(|_| invalid())?;
+        Ok(Self { subscribed_at, id })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Cursor;
+    use chrono::{SubsecRound, Utc};
+    use uuid::Uuid;
+
+    #[test]
+    fn cursor_round_trips_through_strings() {
+        let cursor = Cursor {
+            subscribed_at: Utc::now().trunc_subsecs(6),
+            id: Uuid::new_v4(),
+        };
+        let parsed = Cursor::try_from(cursor.to_string());
+        assert_eq!(parsed, Ok(cursor));
+    }
+
+    #[test]
+    fn garbage_is_rejected() {
+        let cursor = "not-a-cursor".to_string();
+        assert!(Cursor::try_from(cursor).is_err());
+    }
+}
@@ -0,0 +1,189 @@
+use crate::{domain::SubscriberEmail, DbPool};
+use anyhow::Context;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::PgExecutor;
+use uuid::Uuid;
+
+/// How long the link sent to the new address stays valid.
+const CHANGE_TOKEN_LIFETIME: Duration = Duration::hours(24);
+
+#[derive(Debug, thiserror::Error)]
+pub enum EmailChangeError {
+    #[error("{0} is already subscribed")]
+    AddressTaken(String),
+    #[error(transparent)]
+    Unexpected(#[from] anyhow::Error),
+}
+
+/// One requested change of address, confirmed or not.
+#[derive(Clone, Debug, Serialize)]
+pub struct EmailChange {
+    pub old_email: String,
+    pub new_email: String,
+    pub requested_at: DateTime<Utc>,
+    pub confirmed_at: Option<DateTime<Utc>>,
+}
+
+async fn ensure_address_is_free(
+    email: &str,
+    executor: impl PgExecutor<'_>,
+) -> Result<(), EmailChangeError> {
+    let taken = sqlx::query!(
+        r#"
+        select id
+        from subscriptions
+        where lower(email) = lower($1);
+        "#,
+        email,
+    )
+    .fetch_optional(executor)
+    .await
+    .context("Failed to query for an existing subscriber")?
+    .is_some();
+    if taken {
+        return Err(EmailChangeError::AddressTaken(email.to_owned()));
+    }
+    Ok(())
+}
+
+/// Records the request, to be applied once the new address is confirmed
+/// through the change token.
+#[tracing::instrument(
+    name = "Requesting a change of email",
+    skip(change_token, pool)
+)]
+pub async fn request_email_change(
+    subscriber_id: &Uuid,
+    new_email: &SubscriberEmail,
+    change_token: &str,
+    pool: &DbPool,
+) -> Result<(), EmailChangeError> {
+    ensure_address_is_free(new_email.as_ref(), pool).await?;
+    sqlx::query!(
+        r#"
+        insert into email_changes (
+            change_token,
+            subscriber_id,
+            old_email,
+            new_email,
+            requested_at
+        )
+        select $1, id, email, $3, now()
+        from subscriptions
+        where id = $2;
+        "#,
+        change_token,
+        subscriber_id,
+        new_email.as_ref(),
+    )
+    .execute(pool)
+    .await
+    .context("Failed to store the email change")?;
+    Ok(())
+}
+
+/// Moves the subscriber, and their queued deliveries, to the new address.
+/// Returns who moved, or `None` for unknown, expired or used tokens.
+/// Other pending changes of the subscriber are superseded, so that older
+/// links can't switch the address again.
+/// The address may have been taken since the change was requested, even
+/// while confirming it: that is reported as `AddressTaken` too.
+#[tracing::instrument(
+    name = "Confirming a change of email",
+    skip(change_token, pool)
+)]
+pub async fn confirm_email_change(
+    change_token: &str,
+    pool: &DbPool,
+) -> Result<Option<Uuid>, EmailChangeError> {
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire connection from the pool")?;
+    let change = sqlx::query!(
+        r#"
+        select subscriber_id, new_email
+        from email_changes
+        where change_token = $1
+        and confirmed_at is null
+        and superseded_at is null
+        and requested_at > $2
+        for update;
+        "#,
+        change_token,
+        Utc::now() - CHANGE_TOKEN_LIFETIME,
+    )
+    .fetch_optional(&mut transaction)
+    .await
+    .context("Failed to query for an email change")?;
+    let change = match change {
+        Some(change) => change,
+        None => return Ok(None),
+    };
+    ensure_address_is_free(&change.new_email, &mut transaction).await?;
+    sqlx::query!(
+        r#"
+        update issue_delivery_queue
+        set subscriber_email = $2
+        where subscriber_email = (
+            select email from subscriptions where id = $1
+        );
+        "#,
+        change.subscriber_id,
+        change.new_email,
+    )
+    .execute(&mut transaction)
+    .await
+    .context("Failed to move queued deliveries")?;
+    sqlx::query!(
+        r#"
+        update subscriptions
+        set email = $2
+        where id = $1;
+        "#,
+        change.subscriber_id,
+        change.new_email,
+    )
+    .execute(&mut transaction)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(e) if e.code().as_deref() == Some("23505") => {
+            EmailChangeError::AddressTaken(change.new_email.clone())
+        }
+        e => anyhow::Error::new(e)
+            .context("Failed to update the subscriber email")
+            .into(),
+    })?;
+    sqlx::query!(
+        r#"
+        update email_changes
+        set confirmed_at = now()
+        where change_token = $1;
+        "#,
+        change_token,
+    )
+    .execute(&mut transaction)
+    .await
+    .context("Failed to mark the email change as confirmed")?;
+    sqlx::query!(
+        r#"
+        update email_changes
+        set superseded_at = now()
+        where subscriber_id = $1
+        and change_token <> $2
+        and confirmed_at is null
+        and superseded_at is null;
+        "#,
+        change.subscriber_id,
+        change_token,
+    )
+    .execute(&mut transaction)
+    .await
+    .context("Failed to supersede other email changes")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit transaction")?;
+    Ok(Some(change.subscriber_id))
+}
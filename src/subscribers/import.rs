@@ -0,0 +1,247 @@
+use crate::{
+    domain::{NewSubscriber, SubscriberStatus},
+    lists::add_to_list,
+    DbPool,
+};
+use anyhow::Context;
+use chrono::Utc;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// A CSV line that passed validation and is ready to be stored.
+#[derive(Clone, Debug)]
+pub struct ImportedSubscriber {
+    pub subscriber: NewSubscriber,
+    pub status: SubscriberStatus,
+}
+
+#[derive(Clone, Debug)]
+pub struct ImportRow {
+    pub line: u64,
+    /// The email as it appears in the file, for reporting.
+    pub email: String,
+    pub outcome: ImportOutcome,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImportOutcome {
+    Accepted,
+    Rejected(String),
+    /// The email was already subscribed, the line was left out.
+    Skipped,
+}
+
+/// Parses `name,email[,status]` lines, validating each of them.
+/// A leading `name,email[,status]` header line is skipped.
+/// Lines repeating an email seen earlier in the file are rejected.
+pub fn parse_subscribers_csv(
+    data: &[u8],
+) -> Vec<(ImportRow, Option<ImportedSubscriber>)> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(data);
+    let mut seen = HashMap::new();
+    let mut rows = Vec::new();
+    for (index, record) in reader.records().enumerate() {
+        let (line, parsed) = match record {
+            Ok(record) => {
+                if index == 0 && is_header(&record) {
+                    continue;
+                }
+                let line = record.position().map_or(0, |p| p.line());
+                (line, parse_record(&record))
+            }
+            Err(e) => {
+                let line = e.position().map_or(0, |p| p.line());
+                (line, Err((String::new(), e.to_string())))
+            }
+        };
+        let row = match parsed {
+            Ok(imported) => {
+                let email = imported.subscriber.email.as_ref().to_owned();
+                match seen.insert(email.to_lowercase(), line) {
+                    Some(first) => {
+                        let reason = format!("Duplicate of line {first}");
+                        (rejected(line, email, reason), None)
+                    }
+                    None => {
+                        let row = ImportRow {
+                            line,
+                            email,
+                            outcome: ImportOutcome::Accepted,
+                        };
+                        (row, Some(imported))
+                    }
+                }
+            }
+            Err((email, reason)) => (rejected(line, email, reason), None),
+        };
+        rows.push(row);
+    }
+    rows
+}
+
+fn is_header(record: &csv::StringRecord) -> bool {
+    record
+        .get(0)
+        .is_some_and(|f| f.eq_ignore_ascii_case("name"))
+        && record
+            .get(1)
+            .is_some_and(|f| f.eq_ignore_ascii_case("email"))
+}
+
+fn parse_record(
+    record: &csv::StringRecord,
+) -> Result<ImportedSubscriber, (String, String)> {
+    let email = record.get(1).unwrap_or_default().to_owned();
+    if !(2..=3).contains(&record.len()) {
+        let reason = "Expected name,email[,status]".to_owned();
+        return Err((email, reason));
+    }
+    let invalid = |reason| (email.clone(), reason);
+    let name = record[0].to_owned().try_into().map_err(invalid)?;
+    let parsed_email = email.clone().try_into().map_err(invalid)?;
+    let status = match record.get(2).filter(|s| !s.is_empty()) {
+        Some(status) => status.to_owned().try_into().map_err(invalid)?,
+        None => SubscriberStatus::PendingConfirmation,
+    };
+    Ok(ImportedSubscriber {
+        subscriber: NewSubscriber {
+            name,
+            email: parsed_email,
+        },
+        status,
+    })
+}
+
+fn rejected(line: u64, email: String, reason: String) -> ImportRow {
+    ImportRow {
+        line,
+        email,
+        outcome: ImportOutcome::Rejected(reason),
+    }
+}
+
+/// Stores the accepted rows as members of the list, rejecting those whose
+/// email is already subscribed, even if only while importing. Returns the ids of the stored subscribers
+/// pending confirmation.
+#[tracing::instrument(name = "Importing subscribers", skip_all)]
+pub async fn import_subscribers(
+    rows: &mut [(ImportRow, Option<ImportedSubscriber>)],
+    list_id: &Uuid,
+    locale: &str,
+    pool: &DbPool,
+) -> anyhow::Result<Vec<(Uuid, NewSubscriber)>> {
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire connection from the pool")?;
+    let mut pending = Vec::new();
+    for (row, imported) in rows.iter_mut() {
+        let imported = match imported {
+            Some(imported) => imported,
+            None => continue,
+        };
+        let subscriber_id = Uuid::new_v4();
+        let inserted = sqlx::query!(
+            r#"
+            insert into subscriptions
+                (id, name, email, subscribed_at, status, locale)
+            values ($1, $2, $3, $4, $5, $6)
+            on conflict do nothing;
+            "#,
+            subscriber_id,
+            imported.subscriber.name.as_ref(),
+            imported.subscriber.email.as_ref(),
+            Utc::now(),
+            imported.status.as_ref(),
+            locale,
+        )
+        .execute(&mut transaction)
+        .await
+        .context("Failed to insert an imported subscriber")?
+        .rows_affected()
+            == 1;
+        if !inserted {
+            row.outcome = ImportOutcome::Skipped;
+            continue;
+        }
+        add_to_list(list_id, &subscriber_id, &mut transaction).await?;
+        if imported.status == SubscriberStatus::PendingConfirmation {
+            pending.push((subscriber_id, imported.subscriber.clone()));
+        }
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit transaction")?;
+    Ok(pending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_subscribers_csv, ImportOutcome};
+    use crate::domain::SubscriberStatus;
+
+    fn outcomes(data: &str) -> Vec<(u64, ImportOutcome)> {
+        parse_subscribers_csv(data.as_bytes())
+            .into_iter()
+            .map(|(row, _)| (row.line, row.outcome))
+            .collect()
+    }
+
+    #[test]
+    fn header_line_is_skipped() {
+        let rows = outcomes("name,email\nUrsula,ursula@example.com\n");
+        assert_eq!(rows, vec![(2, ImportOutcome::Accepted)]);
+    }
+
+    #[test]
+    fn status_column_is_optional() {
+        let rows = parse_subscribers_csv(
+            b"Ursula,ursula@example.com\nOctavia,octavia@example.com,confirmed",
+        );
+        let statuses: Vec<_> = rows
+            .into_iter()
+            .map(|(_, imported)| imported.unwrap().status)
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                SubscriberStatus::PendingConfirmation,
+                SubscriberStatus::Confirmed
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_lines_are_rejected_with_a_reason() {
+        let rows = outcomes(
+            "Ursula,not-an-email\n\
+            ,octavia@example.com\n\
+            Octavia,octavia@example.com,sleeping\n\
+            just-one-field\n",
+        );
+        assert_eq!(rows.len(), 4);
+        for (line, (row_line, outcome)) in (1..).zip(rows) {
+            assert_eq!(row_line, line);
+            assert!(matches!(outcome, ImportOutcome::Rejected(_)));
+        }
+    }
+
+    #[test]
+    fn repeated_emails_are_rejected() {
+        let rows = outcomes(
+            "Ursula,ursula@example.com\nUrsula K.,URSULA@example.com\n",
+        );
+        assert_eq!(
+            rows,
+            vec![
+                (1, ImportOutcome::Accepted),
+                (2, ImportOutcome::Rejected("Duplicate of line 1".into())),
+            ]
+        );
+    }
+}
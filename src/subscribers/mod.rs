@@ -0,0 +1,11 @@
+mod cursor;
+mod email_change;
+mod import;
+mod persistence;
+mod preferences;
+
+pub use cursor::Cursor;
+pub use email_change::*;
+pub use import::*;
+pub use persistence::*;
+pub use preferences::*;
@@ -0,0 +1,263 @@
+use super::Cursor;
+use crate::{
+    domain::{SubscriberEmail, SubscriberName, SubscriberStatus},
+    DbPool,
+};
+use anyhow::{anyhow, Context};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Subscriber {
+    pub id: Uuid,
+    pub name: SubscriberName,
+    pub email: SubscriberEmail,
+    pub status: SubscriberStatus,
+    pub subscribed_at: DateTime<Utc>,
+    /// The language subscriber-facing emails and pages are shown in.
+    pub locale: String,
+}
+
+struct SubscriberRow {
+    id: Uuid,
+    name: String,
+    email: String,
+    status: String,
+    subscribed_at: DateTime<Utc>,
+    locale: String,
+}
+
+impl TryFrom<SubscriberRow> for Subscriber {
+    type Error = String;
+
+    fn try_from(row: SubscriberRow) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: row.id,
+            name: row.name.try_into()?,
+            email: row.email.try_into()?,
+            status: row.status.try_into()?,
+            subscribed_at: row.subscribed_at,
+            locale: row.locale,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SubscriberFilter {
+    /// Case-insensitive substring of the subscriber's email or name.
+    pub search: Option<String>,
+    pub status: Option<SubscriberStatus>,
+    pub after: Option<Cursor>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SubscribersPage {
+    pub subscribers: Vec<Subscriber>,
+    pub next: Option<Cursor>,
+}
+
+#[tracing::instrument(name = "Listing subscribers", skip(pool))]
+pub async fn list_subscribers(
+    filter: &SubscriberFilter,
+    limit: i64,
+    pool: &DbPool,
+) -> anyhow::Result<SubscribersPage> {
+    let (after_subscribed_at, after_id) = filter
+        .after
+        .as_ref()
+        .map(|c| (c.subscribed_at, c.id))
+        .unzip();
+    let mut rows = sqlx::query_as!(
+        SubscriberRow,
+        r#"
+        select id, name, email, status, subscribed_at, locale
+        from subscriptions
+        where ($1::text is null or status = $1)
+        and (
+            $2::text is null
+            or strpos(lower(email), lower($2)) > 0
+            or strpos(lower(name), lower($2)) > 0
+        )
+        and (
+            $3::timestamptz is null
+            or (subscribed_at, id) > ($3, $4::uuid)
+        )
+        order by subscribed_at, id
+        limit $5;
+        "#,
+        filter.status.as_ref().map(AsRef::as_ref),
+        filter.search,
+        after_subscribed_at,
+        after_id,
+        limit + 1,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query for subscribers")?;
+    let next = if rows.len() as i64 > limit {
+        rows.truncate(limit as usize);
+        rows.last().map(|r| Cursor {
+            subscribed_at: r.subscribed_at,
+            id: r.id,
+        })
+    } else {
+        None
+    };
+    let subscribers = rows
+        .into_iter()
+        .filter_map(|row| {
+            let id = row.id;
+            Subscriber::try_from(row)
+                .map_err(|e| {
+                    tracing::warn!(
+                        subscriber_id = %id,
+                        error.message = %e,
+                        "Skipping a subscriber with invalid stored details",
+                    )
+                })
+                .ok()
+        })
+        .collect();
+    Ok(SubscribersPage { subscribers, next })
+}
+
+#[tracing::instrument(name = "Getting a subscriber", skip(pool))]
+pub async fn get_subscriber(
+    subscriber_id: &Uuid,
+    pool: &DbPool,
+) -> anyhow::Result<Option<Subscriber>> {
+    sqlx::query_as!(
+        SubscriberRow,
+        r#"
+        select id, name, email, status, subscribed_at, locale
+        from subscriptions
+        where id = $1;
+        "#,
+        subscriber_id,
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to query for a subscriber")?
+    .map(|row| Subscriber::try_from(row).map_err(|e| anyhow!(e)))
+    .transpose()
+    .context("Stored subscriber details are invalid")
+}
+
+/// Returns `false` if there is no such subscriber.
+#[tracing::instrument(name = "Updating subscriber status", skip(pool))]
+pub async fn update_subscriber_status(
+    subscriber_id: &Uuid,
+    status: SubscriberStatus,
+    pool: &DbPool,
+) -> anyhow::Result<bool> {
+    sqlx::query!(
+        r#"
+        update subscriptions
+        set status = $1
+        where id = $2;
+        "#,
+        status.as_ref(),
+        subscriber_id,
+    )
+    .execute(pool)
+    .await
+    .context("Failed to update subscriber status")
+    .map(|r| r.rows_affected() == 1)
+}
+
+/// Stops sending to an address the provider reported as undeliverable,
+/// dropping the deliveries already queued for it. Emails are matched
+/// case-insensitively.
+/// Returns `false` if no subscriber has the address.
+#[tracing::instrument(name = "Suppressing a subscriber", skip(pool))]
+pub async fn suppress_subscriber(
+    email: &str,
+    status: SubscriberStatus,
+    pool: &DbPool,
+) -> anyhow::Result<bool> {
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire connection from the pool")?;
+    let updated = sqlx::query!(
+        r#"
+        update subscriptions
+        set status = $1
+        where lower(email) = lower($2);
+        "#,
+        status.as_ref(),
+        email,
+    )
+    .execute(&mut transaction)
+    .await
+    .context("Failed to update subscriber status")?
+    .rows_affected();
+    sqlx::query!(
+        r#"
+        delete from issue_delivery_queue
+        where lower(subscriber_email) = lower($1);
+        "#,
+        email,
+    )
+    .execute(&mut transaction)
+    .await
+    .context("Failed to delete pending deliveries")?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit transaction")?;
+    Ok(updated > 0)
+}
+
+/// Deletes the subscriber along with their tokens and pending deliveries.
+/// Returns `false` if there is no such subscriber.
+#[tracing::instrument(name = "Deleting a subscriber", skip(pool))]
+pub async fn delete_subscriber(
+    subscriber_id: &Uuid,
+    pool: &DbPool,
+) -> anyhow::Result<bool> {
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire connection from the pool")?;
+    sqlx::query!(
+        r#"
+        delete from subscription_tokens
+        where subscriber_id = $1;
+        "#,
+        subscriber_id,
+    )
+    .execute(&mut transaction)
+    .await
+    .context("Failed to delete subscription tokens")?;
+    sqlx::query!(
+        r#"
+        delete from issue_delivery_queue
+        where subscriber_email = (
+            select email from subscriptions where id = $1
+        );
+        "#,
+        subscriber_id,
+    )
+    .execute(&mut transaction)
+    .await
+    .context("Failed to delete pending deliveries")?;
+    let deleted = sqlx::query!(
+        r#"
+        delete from subscriptions
+        where id = $1;
+        "#,
+        subscriber_id,
+    )
+    .execute(&mut transaction)
+    .await
+    .context("Failed to delete a subscriber")?
+    .rows_affected()
+        == 1;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit transaction")?;
+    Ok(deleted)
+}
@@ -0,0 +1,115 @@
+use crate::{
+    domain::{DeliveryPause, SubscriberName},
+    lists::add_to_list,
+    DbPool,
+};
+use anyhow::Context;
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+/// What a subscriber can change from the preference center.
+#[derive(Clone, Debug)]
+pub struct Preferences {
+    pub name: String,
+    pub email: String,
+    /// Slugs of the lists they belong to.
+    pub lists: Vec<String>,
+    pub plain_text_only: bool,
+    pub paused_until: Option<NaiveDate>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PreferencesUpdate {
+    pub name: SubscriberName,
+    /// The lists they should belong to from now on.
+    pub list_ids: Vec<Uuid>,
+    pub plain_text_only: bool,
+    pub pause: Option<DeliveryPause>,
+}
+
+#[tracing::instrument(name = "Getting subscriber preferences", skip(pool))]
+pub async fn get_preferences(
+    subscriber_id: &Uuid,
+    pool: &DbPool,
+) -> anyhow::Result<Preferences> {
+    let row = sqlx::query!(
+        r#"
+        select name, email, plain_text_only, paused_until
+        from subscriptions
+        where id = $1;
+        "#,
+        subscriber_id,
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to query for subscriber preferences")?;
+    let lists = sqlx::query!(
+        r#"
+        select l.slug
+        from list_memberships m
+        join lists l using (list_id)
+        where m.subscriber_id = $1
+        order by l.slug;
+        "#,
+        subscriber_id,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query for list memberships")?
+    .into_iter()
+    .map(|r| r.slug)
+    .collect();
+    Ok(Preferences {
+        name: row.name,
+        email: row.email,
+        lists,
+        plain_text_only: row.plain_text_only,
+        paused_until: row.paused_until,
+    })
+}
+
+/// Saves the preferences, joining and leaving lists as needed.
+#[tracing::instrument(name = "Updating subscriber preferences", skip(pool))]
+pub async fn update_preferences(
+    subscriber_id: &Uuid,
+    update: &PreferencesUpdate,
+    pool: &DbPool,
+) -> anyhow::Result<()> {
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire connection from the pool")?;
+    sqlx::query!(
+        r#"
+        update subscriptions
+        set name = $2, plain_text_only = $3, paused_until = $4
+        where id = $1;
+        "#,
+        subscriber_id,
+        update.name.as_ref(),
+        update.plain_text_only,
+        update.pause.map(|p| p.resumes_on()),
+    )
+    .execute(&mut transaction)
+    .await
+    .context("Failed to update subscriber preferences")?;
+    sqlx::query!(
+        r#"
+        delete from list_memberships
+        where subscriber_id = $1
+        and not (list_id = any($2));
+        "#,
+        subscriber_id,
+        &update.list_ids,
+    )
+    .execute(&mut transaction)
+    .await
+    .context("Failed to leave mailing lists")?;
+    for list_id in &update.list_ids {
+        add_to_list(list_id, subscriber_id, &mut transaction).await?;
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit transaction")
+}
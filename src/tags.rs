@@ -0,0 +1,91 @@
+use crate::{domain::Tag, DbPool};
+use anyhow::Context;
+
+#[derive(Clone, Debug)]
+pub struct TagSummary {
+    pub tag: String,
+    pub subscribers: i64,
+}
+
+#[tracing::instrument(name = "Listing tags", skip_all)]
+pub async fn all_tags(pool: &DbPool) -> anyhow::Result<Vec<TagSummary>> {
+    sqlx::query_as!(
+        TagSummary,
+        r#"
+        select tag, count(*) as "subscribers!"
+        from subscriber_tags
+        group by tag
+        order by tag;
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query for tags")
+}
+
+/// Tags the subscribers with any of the emails, matched case-insensitively.
+/// Returns how many of them were not tagged yet.
+#[tracing::instrument(name = "Tagging subscribers", skip(pool))]
+pub async fn tag_subscribers(
+    tag: &Tag,
+    emails: &[String],
+    pool: &DbPool,
+) -> anyhow::Result<u64> {
+    let emails: Vec<String> = emails.iter().map(|e| e.to_lowercase()).collect();
+    sqlx::query!(
+        r#"
+        insert into subscriber_tags (subscriber_id, tag, tagged_at)
+        select id, $1, now()
+        from subscriptions
+        where lower(email) = any($2)
+        on conflict do nothing;
+        "#,
+        tag.as_ref(),
+        &emails,
+    )
+    .execute(pool)
+    .await
+    .map(|r| r.rows_affected())
+    .context("Failed to tag subscribers")
+}
+
+/// Returns how many of the subscribers had the tag.
+#[tracing::instrument(name = "Untagging subscribers", skip(pool))]
+pub async fn untag_subscribers(
+    tag: &Tag,
+    emails: &[String],
+    pool: &DbPool,
+) -> anyhow::Result<u64> {
+    let emails: Vec<String> = emails.iter().map(|e| e.to_lowercase()).collect();
+    sqlx::query!(
+        r#"
+        delete from subscriber_tags
+        where tag = $1
+        and subscriber_id in (
+            select id from subscriptions where lower(email) = any($2)
+        );
+        "#,
+        tag.as_ref(),
+        &emails,
+    )
+    .execute(pool)
+    .await
+    .map(|r| r.rows_affected())
+    .context("Failed to untag subscribers")
+}
+
+/// Removes the tag from everybody, returning how many subscribers had it.
+#[tracing::instrument(name = "Deleting a tag", skip(pool))]
+pub async fn delete_tag(tag: &Tag, pool: &DbPool) -> anyhow::Result<u64> {
+    sqlx::query!(
+        r#"
+        delete from subscriber_tags
+        where tag = $1;
+        "#,
+        tag.as_ref(),
+    )
+    .execute(pool)
+    .await
+    .map(|r| r.rows_affected())
+    .context("Failed to delete a tag")
+}
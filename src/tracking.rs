@@ -0,0 +1,326 @@
+//! Open and click tracking for the issues that turn it on.
+use crate::{routes::generate_subscription_token, Database, DbPool};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use htmlescape::{decode_html, encode_minimal};
+use sqlx::Transaction;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// Points the links of an issue's HTML body at the click tracking
+/// endpoint and adds an open tracking pixel, issuing the recipient's
+/// tokens on the delivery's transaction. Links back to the application,
+/// e.g. to unsubscribe, are left alone.
+#[tracing::instrument(skip(html, transaction))]
+pub async fn track_html(
+    html: &str,
+    newsletter_issue_id: &Uuid,
+    subscriber_id: &Uuid,
+    base_url: &str,
+    transaction: &mut Transaction<'_, Database>,
+) -> anyhow::Result<String> {
+    let mut links = BTreeMap::new();
+    let html = rewrite_links(html, |url| {
+        let is_external = (url.starts_with("https://")
+            || url.starts_with("http://"))
+            && !url.starts_with(base_url);
+        if !is_external {
+            return None;
+        }
+        let token = links
+            .entry(url.to_owned())
+            .or_insert_with(generate_subscription_token);
+        Some(format!("{base_url}/t/c/{token}"))
+    });
+    let open_token = generate_subscription_token();
+    let (tokens, urls): (Vec<_>, Vec<_>) = links
+        .into_iter()
+        .map(|(url, token)| (token, Some(url)))
+        .chain([(open_token.clone(), None)])
+        .unzip();
+    sqlx::query!(
+        r#"
+        insert into tracking_tokens
+            (token, newsletter_issue_id, subscriber_id, url)
+        select token, $1, $2, url
+        from unnest($3::text[], $4::text[]) as t(token, url);
+        "#,
+        newsletter_issue_id,
+        subscriber_id,
+        &tokens,
+        &urls as &[Option<String>],
+    )
+    .execute(transaction)
+    .await
+    .context("Failed to store tracking tokens")?;
+    Ok(insert_pixel(&html, &format!("{base_url}/t/o/{open_token}")))
+}
+
+/// Calls `track` with every link of `html` and points the link at what it
+/// returns instead, if anything.
+fn rewrite_links(
+    html: &str,
+    mut track: impl FnMut(&str) -> Option<String>,
+) -> String {
+    const HREF: &str = "href=\"";
+    let mut rewritten = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find(HREF) {
+        let value_start = start + HREF.len();
+        let value_len = match rest[value_start..].find('"') {
+            Some(len) => len,
+            None => break,
+        };
+        let value = &rest[value_start..value_start + value_len];
+        rewritten.push_str(&rest[..value_start]);
+        let url = decode_html(value).unwrap_or_else(|_| value.to_owned());
+        match track(&url) {
+            Some(tracked) => rewritten.push_str(&encode_minimal(&tracked)),
+            None => rewritten.push_str(value),
+        }
+        rest = &rest[value_start + value_len..];
+    }
+    rewritten.push_str(rest);
+    rewritten
+}
+
+fn insert_pixel(html: &str, pixel_url: &str) -> String {
+    let pixel = format!(
+        r#"<img src="{}" width="1" height="1" alt="">"#,
+        encode_minimal(pixel_url)
+    );
+    match html.rfind("</body>") {
+        Some(end) => format!("{}{pixel}{}", &html[..end], &html[end..]),
+        None => format!("{html}{pixel}"),
+    }
+}
+
+/// Records an open, ignoring tokens that aren't for an open pixel.
+#[tracing::instrument(skip(pool))]
+pub async fn record_open(token: &str, pool: &DbPool) -> anyhow::Result<()> {
+    sqlx::query!(
+        r#"
+        insert into tracking_events (token, occurred_at)
+        select token, now()
+        from tracking_tokens
+        where token = $1 and url is null;
+        "#,
+        token,
+    )
+    .execute(pool)
+    .await
+    .context("Failed to record an open")
+    .map(|_| ())
+}
+
+/// Records a click and returns where the link leads, `None` if the token
+/// isn't for a link.
+#[tracing::instrument(skip(pool))]
+pub async fn record_click(
+    token: &str,
+    pool: &DbPool,
+) -> anyhow::Result<Option<String>> {
+    let url = sqlx::query!(
+        r#"
+        select url as "url!"
+        from tracking_tokens
+        where token = $1 and url is not null;
+        "#,
+        token,
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to query for a tracked link")?
+    .map(|r| r.url);
+    if url.is_some() {
+        sqlx::query!(
+            r#"
+            insert into tracking_events (token, occurred_at)
+            values ($1, now());
+            "#,
+            token,
+        )
+        .execute(pool)
+        .await
+        .context("Failed to record a click")?;
+    }
+    Ok(url)
+}
+
+#[derive(Clone, Debug)]
+pub struct TrackedIssue {
+    pub newsletter_issue_id: Uuid,
+    pub title: String,
+    pub published_at: DateTime<Utc>,
+    /// How many subscribers it was sent to so far.
+    pub recipients: i64,
+    pub unique_opens: i64,
+    pub unique_clicks: i64,
+}
+
+#[tracing::instrument(name = "Listing tracked issues", skip_all)]
+pub async fn tracked_issues(
+    pool: &DbPool,
+) -> anyhow::Result<Vec<TrackedIssue>> {
+    sqlx::query_as!(
+        TrackedIssue,
+        r#"
+        select
+            i.newsletter_issue_id,
+            i.title,
+            i.published_at::timestamptz as "published_at!",
+            count(distinct t.subscriber_id) as "recipients!",
+            count(distinct t.subscriber_id)
+                filter (where t.url is null and e.token is not null)
+                as "unique_opens!",
+            count(distinct t.subscriber_id)
+                filter (where t.url is not null and e.token is not null)
+                as "unique_clicks!"
+        from newsletter_issues i
+        left join tracking_tokens t using (newsletter_issue_id)
+        left join tracking_events e using (token)
+        where i.tracking
+        group by i.newsletter_issue_id
+        order by i.published_at desc;
+        "#,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to query for tracked issues")
+}
+
+#[derive(Clone, Debug)]
+pub struct IssueStats {
+    pub title: String,
+    pub published_at: DateTime<Utc>,
+    pub tracking: bool,
+    pub recipients: i64,
+    pub opens: i64,
+    pub unique_opens: i64,
+    pub clicks: i64,
+    pub unique_clicks: i64,
+    /// Most clicked first.
+    pub links: Vec<LinkStats>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LinkStats {
+    pub url: String,
+    pub clicks: i64,
+    pub unique_clicks: i64,
+}
+
+/// `None` if there is no such issue.
+#[tracing::instrument(name = "Computing issue stats", skip(pool))]
+pub async fn issue_stats(
+    newsletter_issue_id: &Uuid,
+    pool: &DbPool,
+) -> anyhow::Result<Option<IssueStats>> {
+    let issue = sqlx::query!(
+        r#"
+        select
+            title,
+            published_at::timestamptz as "published_at!",
+            tracking
+        from newsletter_issues
+        where newsletter_issue_id = $1;
+        "#,
+        newsletter_issue_id,
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to query for a newsletter issue")?;
+    let issue = match issue {
+        Some(issue) => issue,
+        None => return Ok(None),
+    };
+    let totals = sqlx::query!(
+        r#"
+        select
+            count(distinct t.subscriber_id) as "recipients!",
+            count(e.token) filter (where t.url is null) as "opens!",
+            count(distinct t.subscriber_id)
+                filter (where t.url is null and e.token is not null)
+                as "unique_opens!",
+            count(e.token) filter (where t.url is not null) as "clicks!",
+            count(distinct t.subscriber_id)
+                filter (where t.url is not null and e.token is not null)
+                as "unique_clicks!"
+        from tracking_tokens t
+        left join tracking_events e using (token)
+        where t.newsletter_issue_id = $1;
+        "#,
+        newsletter_issue_id,
+    )
+    .fetch_one(pool)
+    .await
+    .context("Failed to count opens and clicks")?;
+    let links = sqlx::query_as!(
+        LinkStats,
+        r#"
+        select
+            t.url as "url!",
+            count(e.token) as "clicks!",
+            count(distinct t.subscriber_id) filter (where e.token is not null)
+                as "unique_clicks!"
+        from tracking_tokens t
+        left join tracking_events e using (token)
+        where t.newsletter_issue_id = $1 and t.url is not null
+        group by t.url
+        order by 2 desc, 1;
+        "#,
+        newsletter_issue_id,
+    )
+    .fetch_all(pool)
+    .await
+    .context("Failed to count clicks per link")?;
+    Ok(Some(IssueStats {
+        title: issue.title,
+        published_at: issue.published_at,
+        tracking: issue.tracking,
+        recipients: totals.recipients,
+        opens: totals.opens,
+        unique_opens: totals.unique_opens,
+        clicks: totals.clicks,
+        unique_clicks: totals.unique_clicks,
+        links,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{insert_pixel, rewrite_links};
+
+    #[test]
+    fn links_are_rewritten_as_tracked() {
+        let html = r#"<a href="https://example.com/?a=1&amp;b=2">A</a>
+            <a href="mailto:ursula@example.com">B</a>"#;
+        let mut seen = Vec::new();
+        let rewritten = rewrite_links(html, |url| {
+            seen.push(url.to_owned());
+            url.starts_with("https://")
+                .then(|| "https://t/c/x".to_owned())
+        });
+        assert_eq!(
+            seen,
+            ["https://example.com/?a=1&b=2", "mailto:ursula@example.com"]
+        );
+        assert_eq!(
+            rewritten,
+            r#"<a href="https://t/c/x">A</a>
+            <a href="mailto:ursula@example.com">B</a>"#
+        );
+    }
+
+    #[test]
+    fn the_pixel_goes_at_the_end_of_the_body() {
+        assert_eq!(
+            insert_pixel("<body><p>Hi</p></body></html>", "https://t/o/x"),
+            r#"<body><p>Hi</p><img src="https://t/o/x" width="1" height="1" alt=""></body></html>"#
+        );
+        assert_eq!(
+            insert_pixel("<p>Hi</p>", "https://t/o/x"),
+            r#"<p>Hi</p><img src="https://t/o/x" width="1" height="1" alt="">"#
+        );
+    }
+}
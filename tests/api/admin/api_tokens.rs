@@ -0,0 +1,88 @@
+use crate::{TestServer, TestUser};
+use hashmap_macro::hashmap;
+use regex::Regex;
+use serde_json::json;
+use uuid::Uuid;
+use zero2prod::DbPool;
+
+#[sqlx::test]
+async fn created_token_is_shown_once(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let user = TestUser::stored(&server.db_pool).await;
+    user.login(&server).await;
+    let token = server.create_api_token().await;
+
+    let html_page = server.get_admin_api_tokens().await.text().await.unwrap();
+    assert!(html_page.contains("test token"));
+    assert!(!html_page.contains(&token));
+}
+
+#[sqlx::test]
+async fn created_tokens_are_not_put_in_cookies(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let user = TestUser::stored(&server.db_pool).await;
+    user.login(&server).await;
+    let body = hashmap!("name" => "test token");
+    let response = server.post_admin_api_tokens(&body).await;
+    let cookies = response
+        .headers()
+        .get_all("Set-Cookie")
+        .iter()
+        .map(|c| c.to_str().unwrap().to_owned())
+        .collect::<Vec<_>>();
+    let html_page = response.text().await.unwrap();
+    let token = Regex::new(r"<code>(\w+)</code>")
+        .unwrap()
+        .captures(&html_page)
+        .unwrap()[1]
+        .to_owned();
+    assert!(!cookies.iter().any(|c| c.contains(&token)));
+}
+
+#[sqlx::test]
+async fn tokens_are_stored_hashed(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let user = TestUser::stored(&server.db_pool).await;
+    user.login(&server).await;
+    let token = server.create_api_token().await;
+
+    let saved = sqlx::query!("select token_hash from api_tokens")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap();
+    assert_ne!(saved.token_hash, token);
+}
+
+#[sqlx::test]
+async fn revoked_token_is_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let user = TestUser::stored(&server.db_pool).await;
+    user.login(&server).await;
+    let token = server.create_api_token().await;
+    let api_token_id = sqlx::query!("select api_token_id from api_tokens")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap()
+        .api_token_id
+        .to_string();
+
+    let body = hashmap!("api_token_id" => api_token_id.as_str());
+    let response = server.post_admin_api_tokens_revoke(&body).await;
+    server.assert_is_redirect_to(&response, "/admin/api_tokens");
+
+    let key = Uuid::new_v4().to_string();
+    let body = json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+    });
+    let response = server.post_api_newsletters(&token, Some(&key), &body).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[sqlx::test]
+async fn unauthenticated_users_can_not_access_tokens(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let response = server.get_admin_api_tokens().await;
+    server.assert_is_redirect_to(&response, "/login");
+}
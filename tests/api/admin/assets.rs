@@ -0,0 +1,78 @@
+use crate::{TestServer, TestUser};
+use zero2prod::DbPool;
+
+#[sqlx::test]
+async fn unauthenticated_users_can_not_upload_assets(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let response = server
+        .post_admin_assets("logo.png", b"png", "image/png")
+        .await;
+    server.assert_is_redirect_to(&response, "/login");
+}
+
+#[sqlx::test]
+async fn uploaded_assets_are_listed(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let response = server
+        .post_admin_assets("logo.png", b"png", "image/png")
+        .await;
+    server.assert_is_redirect_to(&response, "/admin/assets");
+
+    let html_page = server.get_admin_assets().await.text().await.unwrap();
+    assert!(html_page
+        .contains("<p><i>The asset logo.png has been uploaded.</i></p>"));
+    assert!(html_page.contains("<td>logo.png</td>"));
+    assert!(html_page.contains("<td>image/png</td>"));
+    let saved = sqlx::query!("select content from newsletter_assets")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(saved.content, b"png");
+}
+
+#[sqlx::test]
+async fn asset_names_must_be_unique(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    server
+        .post_admin_assets("logo.png", b"png", "image/png")
+        .await;
+    server
+        .post_admin_assets("logo.png", b"other", "image/png")
+        .await;
+
+    let html_page = server.get_admin_assets().await.text().await.unwrap();
+    assert!(html_page.contains("An asset called logo.png already exists"));
+    let saved = sqlx::query!("select content from newsletter_assets")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(saved.content, b"png");
+}
+
+#[sqlx::test]
+async fn invalid_names_and_oversized_files_are_rejected(pool: DbPool) {
+    let server = TestServer::run_with_config(pool, |c| {
+        c.email_client.max_attachments_size = 4;
+    })
+    .await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    server
+        .post_admin_assets("my logo.png", b"png", "image/png")
+        .await;
+    let html_page = server.get_admin_assets().await.text().await.unwrap();
+    assert!(html_page.contains("my logo.png is not a valid asset name"));
+
+    server
+        .post_admin_assets("logo.png", b"12345", "image/png")
+        .await;
+    let html_page = server.get_admin_assets().await.text().await.unwrap();
+    assert!(html_page.contains("The file is too large. The limit is 4 bytes."));
+
+    let saved = sqlx::query!("select name from newsletter_assets")
+        .fetch_all(&server.db_pool)
+        .await
+        .unwrap();
+    assert!(saved.is_empty());
+}
@@ -0,0 +1,106 @@
+use crate::{
+    newsletter::{create_confirmed_subscriber, create_unconfirmed_subscriber},
+    TestServer, TestUser,
+};
+use serde_json::Value;
+use uuid::Uuid;
+use zero2prod::DbPool;
+
+#[sqlx::test]
+async fn unauthenticated_users_can_not_export(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let response = server.get_admin_export("subscriptions", "csv").await;
+    server.assert_is_redirect_to(&response, "/login");
+}
+
+#[sqlx::test]
+async fn subscriptions_are_exported_as_csv(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_confirmed_subscriber(&server).await;
+    create_unconfirmed_subscriber(&server).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+
+    let response = server.get_admin_export("subscriptions", "csv").await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        response.headers()["Content-Type"],
+        "text/csv; charset=utf-8"
+    );
+    let disposition = response.headers()["Content-Disposition"]
+        .to_str()
+        .unwrap()
+        .to_owned();
+    assert!(disposition.starts_with(r#"attachment; filename="subscriptions-"#));
+    assert!(disposition.ends_with(r#".csv""#));
+    let body = response.text().await.unwrap();
+    let lines: Vec<_> = body.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], "id,email,name,status,subscribed_at");
+    assert!(lines[1].contains(",confirmed,"));
+    assert!(lines[2].contains(",pending_confirmation,"));
+}
+
+#[sqlx::test]
+async fn subscriptions_are_exported_as_json_lines(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_confirmed_subscriber(&server).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+
+    let response = server.get_admin_export("subscriptions", "jsonl").await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()["Content-Type"], "application/x-ndjson");
+    let body = response.text().await.unwrap();
+    let records: Vec<Value> = body
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["status"], "confirmed");
+    assert!(records[0]["subscribed_at"].is_string());
+}
+
+#[sqlx::test]
+async fn newsletter_issues_are_exported(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    for title in ["First issue", "Second issue"] {
+        sqlx::query!(
+            r#"
+            insert into newsletter_issues (
+                newsletter_issue_id,
+                title,
+                text_content,
+                html_content,
+                published_at
+            )
+            values ($1, $2, 'Plain text', '<p>HTML, with a comma</p>', now());
+            "#,
+            Uuid::new_v4(),
+            title,
+        )
+        .execute(&server.db_pool)
+        .await
+        .unwrap();
+    }
+
+    let response = server.get_admin_export("newsletter_issues", "csv").await;
+    let body = response.text().await.unwrap();
+    let mut reader = csv::Reader::from_reader(body.as_bytes());
+    let titles: Vec<_> =
+        reader.records().map(|r| r.unwrap()[1].to_owned()).collect();
+    assert_eq!(titles, vec!["First issue", "Second issue"]);
+
+    let response = server.get_admin_export("newsletter_issues", "jsonl").await;
+    let body = response.text().await.unwrap();
+    let first: Value =
+        serde_json::from_str(body.lines().next().unwrap()).unwrap();
+    assert_eq!(first["html_content"], "<p>HTML, with a comma</p>");
+}
+
+#[sqlx::test]
+async fn unknown_formats_are_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let response = server.get_admin_export("subscriptions", "xml").await;
+    assert_eq!(response.status().as_u16(), 400);
+}
@@ -0,0 +1,179 @@
+use crate::{
+    newsletter::{create_confirmed_subscriber, create_unconfirmed_subscriber},
+    TestServer, TestUser,
+};
+use hashmap_macro::hashmap;
+use serde_json::Value;
+use uuid::Uuid;
+use zero2prod::DbPool;
+
+#[sqlx::test]
+async fn unauthenticated_users_can_not_export_data(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let response = server.post_admin_gdpr("export", "a@example.com").await;
+    server.assert_is_redirect_to(&response, "/login");
+}
+
+#[sqlx::test]
+async fn export_includes_everything_held_about_the_subscriber(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_unconfirmed_subscriber(&server).await;
+    create_confirmed_subscriber(&server).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let key = Uuid::new_v4().to_string();
+    let body = hashmap!(
+        "title" => "Newsletter title",
+        "textContent" => "Newsletter body as plain text",
+        "htmlContent" => "<p>Newsletter body as HTML</p>",
+        "idempotencyKey" => key.as_str(),
+        "lists" => "default"
+    );
+    server.post_admin_newsletters(&body).await;
+    let email = sqlx::query!(
+        "select email from subscriptions where status = 'confirmed'"
+    )
+    .fetch_one(&server.db_pool)
+    .await
+    .unwrap()
+    .email;
+
+    let response = server
+        .post_admin_gdpr("export", &email.to_uppercase())
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()["Content-Type"], "application/json");
+    let data: Value = response.json().await.unwrap();
+    assert_eq!(data["subscription"]["email"], email.as_str());
+    assert_eq!(data["subscription"]["status"], "confirmed");
+    assert_eq!(data["lists"], serde_json::json!(["default"]));
+    assert_eq!(data["tags"], serde_json::json!([]));
+    assert_eq!(data["email_changes"], serde_json::json!([]));
+    assert_eq!(data["subscription_tokens"].as_array().unwrap().len(), 1);
+    let pending = data["pending_deliveries"].as_array().unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(pending[0]["title"], "Newsletter title");
+    assert_eq!(data["deliveries"], serde_json::json!([]));
+}
+
+#[sqlx::test]
+async fn erase_removes_the_subscriber_everywhere(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_confirmed_subscriber(&server).await;
+    create_unconfirmed_subscriber(&server).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let key = Uuid::new_v4().to_string();
+    let body = hashmap!(
+        "title" => "Newsletter title",
+        "textContent" => "Newsletter body as plain text",
+        "htmlContent" => "<p>Newsletter body as HTML</p>",
+        "idempotencyKey" => key.as_str(),
+        "lists" => "default"
+    );
+    server.post_admin_newsletters(&body).await;
+    let email = sqlx::query!(
+        "select email from subscriptions where status = 'confirmed'"
+    )
+    .fetch_one(&server.db_pool)
+    .await
+    .unwrap()
+    .email;
+
+    let response = server.post_admin_gdpr("erase", &email).await;
+    server.assert_is_redirect_to(&response, "/admin/gdpr");
+    let html_page = server
+        .http_client
+        .get(format!("{}/gdpr", server.admin()))
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(html_page.contains("has been erased"));
+
+    let remaining = sqlx::query!(
+        r#"
+        select
+            (select count(*) from subscriptions) as "subscriptions!",
+            (select count(*) from subscription_tokens) as "tokens!",
+            (select count(*) from issue_delivery_queue) as "queue!"
+        "#
+    )
+    .fetch_one(&server.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(remaining.subscriptions, 1);
+    assert_eq!(remaining.tokens, 1);
+    assert_eq!(remaining.queue, 0);
+
+    let response = server.post_admin_gdpr("export", &email).await;
+    server.assert_is_redirect_to(&response, "/admin/gdpr");
+}
+
+#[sqlx::test]
+async fn unknown_emails_are_reported(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    for action in ["export", "erase"] {
+        let response =
+            server.post_admin_gdpr(action, "nobody@example.com").await;
+        server.assert_is_redirect_to(&response, "/admin/gdpr");
+        let html_page = server
+            .http_client
+            .get(format!("{}/gdpr", server.admin()))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert!(html_page.contains("We hold no data for nobody@example.com."));
+    }
+}
+
+#[sqlx::test]
+async fn former_addresses_find_their_email_changes(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_confirmed_subscriber(&server).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    sqlx::query!(
+        r#"
+        insert into email_changes (
+            change_token,
+            subscriber_id,
+            old_email,
+            new_email,
+            requested_at,
+            confirmed_at
+        )
+        select 'change-token', id, 'Former@example.com', email, now(), now()
+        from subscriptions;
+        "#
+    )
+    .execute(&server.db_pool)
+    .await
+    .unwrap();
+
+    let response = server.post_admin_gdpr("export", "former@example.com").await;
+    assert_eq!(response.status().as_u16(), 200);
+    let data: Value = response.json().await.unwrap();
+    assert!(data["subscription"].is_null());
+    let changes = data["email_changes"].as_array().unwrap();
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0]["old_email"], "Former@example.com");
+
+    let response = server.post_admin_gdpr("erase", "former@example.com").await;
+    server.assert_is_redirect_to(&response, "/admin/gdpr");
+    let remaining = sqlx::query!(
+        r#"
+        select
+            (select count(*) from subscriptions) as "subscriptions!",
+            (select count(*) from email_changes) as "changes!"
+        "#
+    )
+    .fetch_one(&server.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(remaining.subscriptions, 1);
+    assert_eq!(remaining.changes, 0);
+}
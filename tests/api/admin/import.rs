@@ -0,0 +1,216 @@
+use crate::{
+    newsletter::{create_confirmed_subscriber, create_list},
+    TestServer, TestUser,
+};
+use wiremock::ResponseTemplate;
+use zero2prod::DbPool;
+
+#[sqlx::test]
+async fn unauthenticated_users_can_not_import(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let response = server
+        .post_admin_subscribers_import("Ursula,ursula@example.com", false)
+        .await;
+    server.assert_is_redirect_to(&response, "/login");
+}
+
+#[sqlx::test]
+async fn imports_without_a_csrf_token_are_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let form = reqwest::multipart::Form::new()
+        .text("file", "Ursula,ursula@example.com");
+    let response = server
+        .http_client
+        .post(server.admin_subscribers_import())
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 403);
+}
+
+#[sqlx::test]
+async fn valid_lines_are_imported_and_invalid_ones_reported(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let csv = "name,email,status\n\
+        Ursula Le Guin,ursula@example.com\n\
+        Octavia Butler,octavia@example.com,confirmed\n\
+        Nobody,not-an-email\n\
+        Ursula again,ursula@example.com\n";
+
+    let response = server.post_admin_subscribers_import(csv, false).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let html_page = response.text().await.unwrap();
+    assert!(html_page.contains("Imported 2 of 4 lines."));
+    assert!(html_page.contains("Rejected: not-an-email"));
+    assert!(html_page.contains("Rejected: Duplicate of line 2"));
+
+    let saved =
+        sqlx::query!("select email, status from subscriptions order by email")
+            .fetch_all(&server.db_pool)
+            .await
+            .unwrap();
+    assert_eq!(saved.len(), 2);
+    assert_eq!(saved[0].email, "octavia@example.com");
+    assert_eq!(saved[0].status, "confirmed");
+    assert_eq!(saved[1].email, "ursula@example.com");
+    assert_eq!(saved[1].status, "pending_confirmation");
+}
+
+#[sqlx::test]
+async fn existing_subscribers_are_not_imported_again(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_confirmed_subscriber(&server).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let existing = sqlx::query!("select name, email from subscriptions")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap();
+    let csv = format!("Someone,{}\n", existing.email.to_uppercase());
+
+    let response = server.post_admin_subscribers_import(&csv, false).await;
+    let html_page = response.text().await.unwrap();
+    assert!(html_page.contains("Imported 0 of 1 lines."));
+    assert!(html_page.contains("Skipped 1 already subscribed."));
+    assert!(html_page.contains("Skipped: Already subscribed"));
+    let saved = sqlx::query!("select name from subscriptions")
+        .fetch_all(&server.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(saved.len(), 1);
+    assert_eq!(saved[0].name, existing.name);
+}
+
+#[sqlx::test]
+async fn confirmation_emails_are_sent_on_request(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(1))
+        .await;
+    let csv = "Ursula Le Guin,ursula@example.com\n\
+        Octavia Butler,octavia@example.com,confirmed\n";
+
+    let response = server.post_admin_subscribers_import(csv, true).await;
+    let html_page = response.text().await.unwrap();
+    assert!(html_page.contains("Imported 2 of 2 lines."));
+
+    let email_request = server
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let links = server.extract_links(&email_request);
+    reqwest::get(links.html)
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+    let saved = sqlx::query!("select status from subscriptions")
+        .fetch_all(&server.db_pool)
+        .await
+        .unwrap();
+    assert!(saved.iter().all(|s| s.status == "confirmed"));
+}
+
+#[sqlx::test]
+async fn no_emails_are_sent_unless_requested(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(0))
+        .await;
+    let response = server
+        .post_admin_subscribers_import("Ursula,ursula@example.com", false)
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[sqlx::test]
+async fn empty_uploads_are_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let response = server.post_admin_subscribers_import("", false).await;
+    server.assert_is_redirect_to(&response, "/admin/subscribers/import");
+    let html_page = server
+        .http_client
+        .get(server.admin_subscribers_import())
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(html_page.contains("Please choose a non-empty CSV file"));
+}
+
+#[sqlx::test]
+async fn subscribers_are_imported_into_the_chosen_list(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_list(&server, "beta").await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let file = reqwest::multipart::Part::text("Ursula,ursula@example.com")
+        .file_name("subscribers.csv");
+    let form = reqwest::multipart::Form::new()
+        .text("csrf_token", server.csrf_token().await)
+        .part("file", file)
+        .text("list", "beta");
+    let response = server
+        .http_client
+        .post(server.admin_subscribers_import())
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+
+    let lists = sqlx::query!(
+        "select l.slug from list_memberships m join lists l using (list_id)"
+    )
+    .fetch_all(&server.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(lists.len(), 1);
+    assert_eq!(lists[0].slug, "beta");
+}
+
+#[sqlx::test]
+async fn subscribers_imported_into_single_opt_in_lists_are_confirmed(
+    pool: DbPool,
+) {
+    let server = TestServer::run(pool).await;
+    create_list(&server, "imported").await;
+    sqlx::query!("update lists set opt_in = 'single' where slug = 'imported'")
+        .execute(&server.db_pool)
+        .await
+        .unwrap();
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(0))
+        .await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let file = reqwest::multipart::Part::text("Ursula,ursula@example.com")
+        .file_name("subscribers.csv");
+    let form = reqwest::multipart::Form::new()
+        .text("csrf_token", server.csrf_token().await)
+        .part("file", file)
+        .text("list", "imported")
+        .text("send_confirmation", "on");
+    let response = server
+        .http_client
+        .post(server.admin_subscribers_import())
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+
+    let saved = sqlx::query!("select status from subscriptions")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(saved.status, "confirmed");
+}
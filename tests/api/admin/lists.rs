@@ -0,0 +1,102 @@
+use crate::{TestServer, TestUser};
+use hashmap_macro::hashmap;
+use zero2prod::DbPool;
+
+#[sqlx::test]
+async fn unauthenticated_users_can_not_manage_lists(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let response = server.get_admin_lists().await;
+    server.assert_is_redirect_to(&response, "/login");
+}
+
+#[sqlx::test]
+async fn the_default_list_exists(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let html_page = server.get_admin_lists().await.text().await.unwrap();
+    assert!(html_page.contains("<td>default</td>"));
+}
+
+#[sqlx::test]
+async fn lists_can_be_created(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+
+    let body =
+        hashmap!("slug" => "product-updates", "name" => "Product updates");
+    let response = server.post_admin_lists(&body).await;
+    server.assert_is_redirect_to(&response, "/admin/lists");
+    let html_page = server.get_admin_lists().await.text().await.unwrap();
+    assert!(html_page.contains("The list product-updates has been created."));
+    assert!(html_page.contains("<td>Product updates</td>"));
+
+    let response = server.post_admin_lists(&body).await;
+    server.assert_is_redirect_to(&response, "/admin/lists");
+    let html_page = server.get_admin_lists().await.text().await.unwrap();
+    assert!(html_page.contains("A list called product-updates already exists"));
+}
+
+#[sqlx::test]
+async fn invalid_lists_are_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let cases = [
+        (
+            hashmap!("slug" => "Product Updates", "name" => "Product updates"),
+            "is not a valid list identifier",
+        ),
+        (
+            hashmap!("slug" => "updates", "name" => " "),
+            "The list name cannot be empty.",
+        ),
+    ];
+    for (body, message) in cases {
+        let response = server.post_admin_lists(&body).await;
+        server.assert_is_redirect_to(&response, "/admin/lists");
+        let html_page = server.get_admin_lists().await.text().await.unwrap();
+        assert!(html_page.contains(message), "{message}");
+    }
+    let lists = sqlx::query!("select slug from lists")
+        .fetch_all(&server.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(lists.len(), 1);
+}
+
+#[sqlx::test]
+async fn the_opt_in_mode_can_be_set_per_list(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+
+    let body = hashmap!(
+        "slug" => "imported",
+        "name" => "Imported",
+        "opt_in" => "single"
+    );
+    server.post_admin_lists(&body).await;
+    let opt_in = || async {
+        sqlx::query!("select opt_in from lists where slug = 'imported'")
+            .fetch_one(&server.db_pool)
+            .await
+            .unwrap()
+            .opt_in
+    };
+    assert_eq!(opt_in().await.as_deref(), Some("single"));
+
+    let body = hashmap!("slug" => "imported", "opt_in" => "");
+    let response = server.post_admin_lists_opt_in(&body).await;
+    server.assert_is_redirect_to(&response, "/admin/lists");
+    let html_page = server.get_admin_lists().await.text().await.unwrap();
+    assert!(html_page.contains("The opt-in mode of imported has been updated."));
+    assert_eq!(opt_in().await, None);
+
+    let body = hashmap!("slug" => "imported", "opt_in" => "triple");
+    server.post_admin_lists_opt_in(&body).await;
+    let html_page = server.get_admin_lists().await.text().await.unwrap();
+    assert!(html_page.contains("triple is not a valid opt-in mode"));
+
+    let body = hashmap!("slug" => "unknown", "opt_in" => "single");
+    server.post_admin_lists_opt_in(&body).await;
+    let html_page = server.get_admin_lists().await.text().await.unwrap();
+    assert!(html_page.contains("There is no list called unknown"));
+}
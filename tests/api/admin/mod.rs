@@ -1,2 +1,11 @@
+mod api_tokens;
+mod assets;
 mod dashboard;
+mod exports;
+mod gdpr;
+mod import;
+mod lists;
 mod password;
+mod sessions;
+mod subscribers;
+mod tags;
@@ -0,0 +1,324 @@
+use crate::{build_http_client, TestServer, TestUser};
+use hashmap_macro::hashmap;
+use reqwest::Client;
+use std::time::Duration;
+use uuid::Uuid;
+use zero2prod::{configuration::SessionStoreKind, DbPool};
+
+#[sqlx::test]
+async fn active_sessions_are_listed(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let user = TestUser::stored(&server.db_pool).await;
+    user.login(&server).await;
+
+    let html_page = server.get_admin_sessions().await.text().await.unwrap();
+    assert!(html_page.contains("(current)"));
+}
+
+#[sqlx::test]
+async fn unauthenticated_users_can_not_access_sessions(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let response = server.get_admin_sessions().await;
+    server.assert_is_redirect_to(&response, "/login");
+}
+
+#[sqlx::test]
+async fn revoked_session_is_logged_out(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let user = TestUser::stored(&server.db_pool).await;
+    user.login(&server).await;
+    let other_device = login_from_another_device(&server, &user).await;
+    let other_session_id = latest_session_id(&server, &user).await;
+
+    let session_id = other_session_id.to_string();
+    let body = hashmap!("session_id" => session_id.as_str());
+    let response = server.post_admin_sessions_revoke(&body).await;
+    server.assert_is_redirect_to(&response, "/admin/sessions");
+
+    let response = other_device
+        .get(server.admin_dashboard())
+        .send()
+        .await
+        .unwrap();
+    server.assert_is_redirect_to(&response, "/login");
+    let response = server.get_admin_dashboard().await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[sqlx::test]
+async fn log_out_everywhere_revokes_all_sessions(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let user = TestUser::stored(&server.db_pool).await;
+    user.login(&server).await;
+    let other_device = login_from_another_device(&server, &user).await;
+
+    let response = server.post_admin_sessions_revoke_all().await;
+    server.assert_is_redirect_to(&response, "/login");
+
+    let response = other_device
+        .get(server.admin_dashboard())
+        .send()
+        .await
+        .unwrap();
+    server.assert_is_redirect_to(&response, "/login");
+    let response = server.get_admin_dashboard().await;
+    server.assert_is_redirect_to(&response, "/login");
+}
+
+#[sqlx::test]
+async fn changing_password_revokes_other_sessions(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let user = TestUser::stored(&server.db_pool).await;
+    user.login(&server).await;
+    let other_device = login_from_another_device(&server, &user).await;
+
+    let new_password = Uuid::new_v4().to_string();
+    let body = hashmap!(
+        "current_password" => user.password.as_str(),
+        "new_password" => new_password.as_str(),
+        "new_password_check" => new_password.as_str(),
+    );
+    let response = server.post_admin_password(&body).await;
+    server.assert_is_redirect_to(&response, "/admin/password");
+
+    let response = other_device
+        .get(server.admin_dashboard())
+        .send()
+        .await
+        .unwrap();
+    server.assert_is_redirect_to(&response, "/login");
+    let response = server.get_admin_dashboard().await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[sqlx::test]
+async fn idle_session_redirects_to_login(pool: DbPool) {
+    let server = TestServer::run_with_config(pool, |c| {
+        c.application.session.idle_timeout = Duration::from_secs(1);
+    })
+    .await;
+    let user = TestUser::stored(&server.db_pool).await;
+    user.login(&server).await;
+    let response = server.get_admin_dashboard().await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    tokio::time::sleep(Duration::from_millis(2500)).await;
+    let response = server.get_admin_dashboard().await;
+    server.assert_is_redirect_to(&response, "/login");
+
+    let html_page = server.get_login().await.text().await.unwrap();
+    assert!(html_page.contains(
+        "<p><i>Your session has expired. Please log in again.</i></p>"
+    ));
+}
+
+#[sqlx::test]
+async fn expired_session_redirects_to_login(pool: DbPool) {
+    let server = TestServer::run_with_config(pool, |c| {
+        c.application.session.max_age = Duration::from_secs(1);
+    })
+    .await;
+    let user = TestUser::stored(&server.db_pool).await;
+    user.login(&server).await;
+
+    tokio::time::sleep(Duration::from_millis(2500)).await;
+    let response = server.get_admin_dashboard().await;
+    server.assert_is_redirect_to(&response, "/login");
+}
+
+#[sqlx::test]
+async fn remembered_session_survives_idle_timeout(pool: DbPool) {
+    let server = TestServer::run_with_config(pool, |c| {
+        c.application.session.idle_timeout = Duration::from_secs(1);
+    })
+    .await;
+    let user = TestUser::stored(&server.db_pool).await;
+    user.login_remembered(&server).await;
+
+    tokio::time::sleep(Duration::from_millis(2500)).await;
+    let response = server.get_admin_dashboard().await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[sqlx::test]
+async fn remembered_sessions_still_expire_when_idle_for_long(pool: DbPool) {
+    let server = TestServer::run_with_config(pool, |c| {
+        c.application.session.remember_me_idle_timeout = Duration::from_secs(1);
+    })
+    .await;
+    let user = TestUser::stored(&server.db_pool).await;
+    user.login_remembered(&server).await;
+
+    tokio::time::sleep(Duration::from_millis(2500)).await;
+    let response = server.get_admin_dashboard().await;
+    server.assert_is_redirect_to(&response, "/login");
+}
+
+#[sqlx::test]
+async fn expired_sessions_are_pruned_when_listing(pool: DbPool) {
+    let server = TestServer::run_with_config(pool, |c| {
+        c.application.session.idle_timeout = Duration::from_secs(1);
+    })
+    .await;
+    let user = TestUser::stored(&server.db_pool).await;
+    user.login_remembered(&server).await;
+    let remembered_session_id = latest_session_id(&server, &user).await;
+    login_from_another_device(&server, &user).await;
+
+    tokio::time::sleep(Duration::from_millis(2500)).await;
+    server.get_admin_sessions().await;
+    let session_ids = sqlx::query_scalar!(
+        "select session_id from user_sessions where user_id = $1",
+        user.user_id
+    )
+    .fetch_all(&server.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(session_ids, vec![remembered_session_id]);
+}
+
+#[sqlx::test]
+async fn activity_is_not_recorded_on_every_request(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let user = TestUser::stored(&server.db_pool).await;
+    user.login(&server).await;
+    let last_active_at = || {
+        sqlx::query_scalar!(
+            "select last_active_at from user_sessions where user_id = $1",
+            user.user_id
+        )
+        .fetch_one(&server.db_pool)
+    };
+    let before = last_active_at().await.unwrap();
+
+    let response = server.get_admin_dashboard().await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(last_active_at().await.unwrap(), before);
+}
+
+fn session_cookie(response: &reqwest::Response) -> String {
+    response
+        .headers()
+        .get_all("Set-Cookie")
+        .iter()
+        .map(|h| h.to_str().unwrap().to_owned())
+        .find(|c| c.starts_with("id="))
+        .expect("No session cookie was set")
+}
+
+#[sqlx::test]
+async fn only_remembered_sessions_outlive_the_browser(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let user = TestUser::stored(&server.db_pool).await;
+    let response = user.login(&server).await;
+    assert!(!session_cookie(&response).contains("Max-Age"));
+    let response = server.get_admin_dashboard().await;
+    assert!(!session_cookie(&response).contains("Max-Age"));
+
+    let client = build_http_client();
+    let csrf_token = server.csrf_token_for(&client).await;
+    let body = hashmap!(
+        "username" => user.username.as_str(),
+        "password" => user.password.as_str(),
+        "remember_me" => "on",
+        "csrf_token" => csrf_token.as_str(),
+    );
+    let response = client
+        .post(server.login())
+        .form(&body)
+        .send()
+        .await
+        .unwrap();
+    assert!(session_cookie(&response).contains("Max-Age=2592000"));
+    let response = client.get(server.admin_dashboard()).send().await.unwrap();
+    assert!(session_cookie(&response).contains("Max-Age=2592000"));
+}
+
+#[sqlx::test]
+async fn sessions_can_be_stored_in_redis(pool: DbPool) {
+    assert_sessions_work_with_store(pool, SessionStoreKind::Redis).await;
+}
+
+#[sqlx::test]
+async fn sessions_can_be_stored_in_postgres(pool: DbPool) {
+    let (server, user) =
+        assert_sessions_work_with_store(pool, SessionStoreKind::Postgres).await;
+    user.login(&server).await;
+    let stored = sqlx::query!("select count(*) as \"count!\" from sessions")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap();
+    assert!(stored.count > 0);
+}
+
+#[sqlx::test]
+async fn sessions_can_be_stored_in_memory(pool: DbPool) {
+    assert_sessions_work_with_store(pool, SessionStoreKind::Memory).await;
+}
+
+/// Logs in, out, and in again until the session expires.
+async fn assert_sessions_work_with_store(
+    pool: DbPool,
+    store: SessionStoreKind,
+) -> (TestServer, TestUser) {
+    let server = TestServer::run_with_config(pool, |c| {
+        c.application.session.store = store;
+        c.application.session.max_age = Duration::from_secs(2);
+    })
+    .await;
+    let user = TestUser::stored(&server.db_pool).await;
+    let response = user.login(&server).await;
+    server.assert_is_redirect_to(&response, "/admin/dashboard");
+    let html_page = server.get_admin_dashboard().await.text().await.unwrap();
+    assert!(html_page.contains(&format!("Welcome, {}", user.username)));
+
+    let response = server.post_admin_logout().await;
+    server.assert_is_redirect_to(&response, "/login");
+    let response = server.get_admin_dashboard().await;
+    server.assert_is_redirect_to(&response, "/login");
+
+    user.login(&server).await;
+    let response = server.get_admin_dashboard().await;
+    assert_eq!(response.status().as_u16(), 200);
+    tokio::time::sleep(Duration::from_millis(3500)).await;
+    let response = server.get_admin_dashboard().await;
+    server.assert_is_redirect_to(&response, "/login");
+    (server, user)
+}
+
+async fn login_from_another_device(
+    server: &TestServer,
+    user: &TestUser,
+) -> Client {
+    let client = build_http_client();
+    let csrf_token = server.csrf_token_for(&client).await;
+    let body = hashmap!(
+        "username" => user.username.as_str(),
+        "password" => user.password.as_str(),
+        "csrf_token" => csrf_token.as_str(),
+    );
+    let response = client
+        .post(server.login())
+        .form(&body)
+        .send()
+        .await
+        .unwrap();
+    server.assert_is_redirect_to(&response, "/admin/dashboard");
+    client
+}
+
+async fn latest_session_id(server: &TestServer, user: &TestUser) -> Uuid {
+    sqlx::query!(
+        r#"
+        select session_id from user_sessions
+        where user_id = $1
+        order by created_at desc
+        limit 1;
+        "#,
+        user.user_id
+    )
+    .fetch_one(&server.db_pool)
+    .await
+    .expect("Failed to fetch user sessions")
+    .session_id
+}
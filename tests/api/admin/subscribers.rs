@@ -0,0 +1,259 @@
+use crate::{
+    newsletter::{create_confirmed_subscriber, create_unconfirmed_subscriber},
+    TestServer, TestUser,
+};
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+use wiremock::ResponseTemplate;
+use zero2prod::DbPool;
+
+#[sqlx::test]
+async fn unauthenticated_users_can_not_browse_subscribers(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let response = server.get_admin_subscribers(&[]).await;
+    server.assert_is_redirect_to(&response, "/login");
+}
+
+#[sqlx::test]
+async fn subscribers_can_be_searched_and_filtered(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    insert_subscriber(&server, "Ursula Le Guin", "ursula@example.com", 0).await;
+    insert_subscriber(&server, "Octavia Butler", "octavia@example.com", 1)
+        .await;
+    sqlx::query!(
+        "update subscriptions set status = 'confirmed' where name = $1",
+        "Octavia Butler"
+    )
+    .execute(&server.db_pool)
+    .await
+    .unwrap();
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+
+    let html_page = server
+        .get_admin_subscribers(&[])
+        .await
+        .text()
+        .await
+        .unwrap();
+    assert!(html_page.contains("ursula@example.com"));
+    assert!(html_page.contains("octavia@example.com"));
+
+    let html_page = server
+        .get_admin_subscribers(&[("search", "URSULA")])
+        .await
+        .text()
+        .await
+        .unwrap();
+    assert!(html_page.contains("ursula@example.com"));
+    assert!(!html_page.contains("octavia@example.com"));
+
+    let html_page = server
+        .get_admin_subscribers(&[("search", "Butler"), ("status", "")])
+        .await
+        .text()
+        .await
+        .unwrap();
+    assert!(!html_page.contains("ursula@example.com"));
+    assert!(html_page.contains("octavia@example.com"));
+
+    let html_page = server
+        .get_admin_subscribers(&[("status", "confirmed")])
+        .await
+        .text()
+        .await
+        .unwrap();
+    assert!(!html_page.contains("ursula@example.com"));
+    assert!(html_page.contains("octavia@example.com"));
+}
+
+#[sqlx::test]
+async fn invalid_filters_are_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    for query in [[("status", "sleeping")], [("cursor", "garbage")]] {
+        let response = server.get_admin_subscribers(&query).await;
+        assert_eq!(response.status().as_u16(), 400, "{query:?}");
+    }
+}
+
+#[sqlx::test]
+async fn subscribers_are_paginated(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    for i in 0..30 {
+        let email = format!("reader{i:02}@example.com");
+        insert_subscriber(&server, "Reader", &email, i).await;
+    }
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+
+    let html_page = server
+        .get_admin_subscribers(&[])
+        .await
+        .text()
+        .await
+        .unwrap();
+    assert!(html_page.contains("reader00@example.com"));
+    assert!(html_page.contains("reader24@example.com"));
+    assert!(!html_page.contains("reader25@example.com"));
+
+    let next = html_page
+        .split(r#"<a href="/admin/subscribers?"#)
+        .nth(1)
+        .and_then(|s| s.split('"').next())
+        .expect("Missing next page link")
+        .replace("&amp;", "&");
+    let query: Vec<(String, String)> =
+        serde_urlencoded::from_str(&next).unwrap();
+    let query: Vec<_> = query
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .collect();
+    let html_page = server
+        .get_admin_subscribers(&query)
+        .await
+        .text()
+        .await
+        .unwrap();
+    assert!(!html_page.contains("reader24@example.com"));
+    assert!(html_page.contains("reader25@example.com"));
+    assert!(html_page.contains("reader29@example.com"));
+    assert!(!html_page.contains("Next page"));
+}
+
+#[sqlx::test]
+async fn confirmation_can_be_resent_to_pending_subscribers(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_unconfirmed_subscriber(&server).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(1))
+        .await;
+
+    let id = subscriber_id(&server).await;
+    let response = server
+        .post_admin_subscribers_action("resend_confirmation", &id)
+        .await;
+    server.assert_is_redirect_to(&response, "/admin/subscribers");
+    let html_page = server
+        .get_admin_subscribers(&[])
+        .await
+        .text()
+        .await
+        .unwrap();
+    assert!(html_page.contains("A confirmation email has been sent to"));
+
+    let email_request = server
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let links = server.extract_links(&email_request);
+    reqwest::get(links.html)
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+    let html_page = server
+        .get_admin_subscribers(&[("status", "confirmed")])
+        .await
+        .text()
+        .await
+        .unwrap();
+    assert!(html_page.contains("<td>confirmed</td>"));
+}
+
+#[sqlx::test]
+async fn confirmed_subscribers_are_not_sent_a_confirmation(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_confirmed_subscriber(&server).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(0))
+        .await;
+
+    let id = subscriber_id(&server).await;
+    let response = server
+        .post_admin_subscribers_action("resend_confirmation", &id)
+        .await;
+    server.assert_is_redirect_to(&response, "/admin/subscribers");
+    let html_page = server
+        .get_admin_subscribers(&[])
+        .await
+        .text()
+        .await
+        .unwrap();
+    assert!(html_page.contains("Only subscribers pending confirmation"));
+}
+
+#[sqlx::test]
+async fn subscribers_can_be_unsubscribed_and_deleted(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_confirmed_subscriber(&server).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let id = subscriber_id(&server).await;
+
+    let response = server
+        .post_admin_subscribers_action("unsubscribe", &id)
+        .await;
+    server.assert_is_redirect_to(&response, "/admin/subscribers");
+    let html_page = server
+        .get_admin_subscribers(&[])
+        .await
+        .text()
+        .await
+        .unwrap();
+    assert!(html_page.contains("The subscriber has been unsubscribed."));
+    assert!(html_page.contains("<td>unsubscribed</td>"));
+
+    let response = server.post_admin_subscribers_action("delete", &id).await;
+    server.assert_is_redirect_to(&response, "/admin/subscribers");
+    let html_page = server
+        .get_admin_subscribers(&[])
+        .await
+        .text()
+        .await
+        .unwrap();
+    assert!(html_page.contains("The subscriber has been deleted."));
+    assert!(!html_page.contains(&id));
+
+    let response = server.post_admin_subscribers_action("delete", &id).await;
+    server.assert_is_redirect_to(&response, "/admin/subscribers");
+    let html_page = server
+        .get_admin_subscribers(&[])
+        .await
+        .text()
+        .await
+        .unwrap();
+    assert!(html_page.contains("The subscriber does not exist."));
+}
+
+async fn insert_subscriber(
+    server: &TestServer,
+    name: &str,
+    email: &str,
+    minutes_ago: i64,
+) {
+    sqlx::query!(
+        r#"
+        insert into subscriptions (id, name, email, subscribed_at, status)
+        values ($1, $2, $3, $4, 'pending_confirmation');
+        "#,
+        Uuid::new_v4(),
+        name,
+        email,
+        Utc::now() - Duration::minutes(60 - minutes_ago),
+    )
+    .execute(&server.db_pool)
+    .await
+    .unwrap();
+}
+
+async fn subscriber_id(server: &TestServer) -> String {
+    sqlx::query!("select id from subscriptions")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap()
+        .id
+        .to_string()
+}
@@ -0,0 +1,70 @@
+use crate::{newsletter::create_confirmed_subscriber_on, TestServer, TestUser};
+use hashmap_macro::hashmap;
+use zero2prod::DbPool;
+
+#[sqlx::test]
+async fn unauthenticated_users_can_not_manage_tags(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let response = server.get_admin_tags().await;
+    server.assert_is_redirect_to(&response, "/login");
+}
+
+#[sqlx::test]
+async fn subscribers_can_be_tagged_and_untagged(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let first = create_confirmed_subscriber_on(&server, &["default"]).await;
+    let second = create_confirmed_subscriber_on(&server, &["default"]).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+
+    let emails =
+        format!("{}\n{second}\nnobody@example.com", first.to_uppercase());
+    let body = hashmap!("tag" => "beta", "emails" => emails.as_str());
+    let response = server.post_admin_tags("add", &body).await;
+    server.assert_is_redirect_to(&response, "/admin/tags");
+    let html_page = server.get_admin_tags().await.text().await.unwrap();
+    assert!(html_page.contains("Tagged 2 subscribers with beta."));
+    assert!(
+        html_page.contains("<td>beta</td>\n                        <td>2</td>")
+    );
+
+    let body = hashmap!("tag" => "beta", "emails" => second.as_str());
+    let response = server.post_admin_tags("remove", &body).await;
+    server.assert_is_redirect_to(&response, "/admin/tags");
+    let html_page = server.get_admin_tags().await.text().await.unwrap();
+    assert!(html_page.contains("Removed beta from 1 subscribers."));
+    assert!(
+        html_page.contains("<td>beta</td>\n                        <td>1</td>")
+    );
+
+    let response = server
+        .post_admin_tags("delete", &hashmap!("tag" => "beta"))
+        .await;
+    server.assert_is_redirect_to(&response, "/admin/tags");
+    let html_page = server.get_admin_tags().await.text().await.unwrap();
+    assert!(
+        html_page.contains("The tag beta has been removed from 1 subscribers.")
+    );
+    assert!(!html_page.contains("<td>beta</td>"));
+}
+
+#[sqlx::test]
+async fn invalid_tag_requests_are_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let cases = [
+        (
+            hashmap!("tag" => "Beta Testers", "emails" => "a@example.com"),
+            "Beta Testers is not a valid tag",
+        ),
+        (
+            hashmap!("tag" => "beta", "emails" => " \n "),
+            "Please enter at least one email address.",
+        ),
+    ];
+    for (body, message) in cases {
+        let response = server.post_admin_tags("add", &body).await;
+        server.assert_is_redirect_to(&response, "/admin/tags");
+        let html_page = server.get_admin_tags().await.text().await.unwrap();
+        assert!(html_page.contains(message), "{message}");
+    }
+}
@@ -0,0 +1,10 @@
+mod newsletters;
+mod subscribers;
+
+use crate::{TestServer, TestUser};
+
+pub async fn authenticated_token(server: &TestServer) -> String {
+    let user = TestUser::stored(&server.db_pool).await;
+    user.login(server).await;
+    server.create_api_token().await
+}
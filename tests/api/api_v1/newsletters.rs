@@ -0,0 +1,252 @@
+use super::authenticated_token;
+use crate::{
+    newsletter::{create_confirmed_subscriber, create_unconfirmed_subscriber},
+    TestServer,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+use wiremock::ResponseTemplate;
+use zero2prod::DbPool;
+
+#[sqlx::test]
+async fn requests_without_a_token_are_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let response = server
+        .http_client
+        .post(server.api_newsletters())
+        .header("Idempotency-Key", "key")
+        .json(&body())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+    assert_eq!(response.headers()["WWW-Authenticate"], "Bearer");
+}
+
+#[sqlx::test]
+async fn requests_with_an_unknown_token_are_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let response = server
+        .post_api_newsletters("unknown", Some("key"), &body())
+        .await;
+    assert_eq!(response.status().as_u16(), 401);
+    let error: Value = response.json().await.unwrap();
+    assert!(error["error"].is_string());
+}
+
+#[sqlx::test]
+async fn newsletters_are_delivered_to_confirmed_subscribers(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_confirmed_subscriber(&server).await;
+    create_unconfirmed_subscriber(&server).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(1))
+        .await;
+    let token = authenticated_token(&server).await;
+
+    let key = Uuid::new_v4().to_string();
+    let response = server
+        .post_api_newsletters(&token, Some(&key), &body())
+        .await;
+    assert_eq!(response.status().as_u16(), 202);
+    let published: Value = response.json().await.unwrap();
+    assert!(published["newsletter_issue_id"].is_string());
+    server.dispatch_pending_emails().await;
+}
+
+#[sqlx::test]
+async fn publishing_is_idempotent(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_confirmed_subscriber(&server).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(1))
+        .await;
+    let token = authenticated_token(&server).await;
+
+    let key = Uuid::new_v4().to_string();
+    let response1 = server
+        .post_api_newsletters(&token, Some(&key), &body())
+        .await;
+    let response2 = server
+        .post_api_newsletters(&token, Some(&key), &body())
+        .await;
+    assert_eq!(response1.status().as_u16(), 202);
+    assert_eq!(response1.status(), response2.status());
+    assert_eq!(
+        response1.text().await.unwrap(),
+        response2.text().await.unwrap()
+    );
+    server.dispatch_pending_emails().await;
+}
+
+#[sqlx::test]
+async fn missing_idempotency_key_is_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let token = authenticated_token(&server).await;
+    let response = server.post_api_newsletters(&token, None, &body()).await;
+    assert_eq!(response.status().as_u16(), 400);
+    let error: Value = response.json().await.unwrap();
+    assert!(error["error"].as_str().unwrap().contains("Idempotency-Key"));
+}
+
+#[sqlx::test]
+async fn invalid_body_is_rejected_with_a_json_error(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let token = authenticated_token(&server).await;
+    let key = Uuid::new_v4().to_string();
+    let body = json!({ "title": "Newsletter title" });
+    let response = server.post_api_newsletters(&token, Some(&key), &body).await;
+    assert_eq!(response.status().as_u16(), 400);
+    let error: Value = response.json().await.unwrap();
+    assert!(error["error"].is_string());
+}
+
+fn body() -> Value {
+    json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+    })
+}
+
+#[sqlx::test]
+async fn unknown_lists_are_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let token = authenticated_token(&server).await;
+    let key = Uuid::new_v4().to_string();
+    let mut body = body();
+    body["lists"] = json!(["unknown"]);
+    let response = server.post_api_newsletters(&token, Some(&key), &body).await;
+    assert_eq!(response.status().as_u16(), 400);
+    let error: Value = response.json().await.unwrap();
+    assert_eq!(error["error"], "There is no list called unknown");
+}
+
+#[sqlx::test]
+async fn invalid_segments_are_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let token = authenticated_token(&server).await;
+    let key = Uuid::new_v4().to_string();
+    let mut body = body();
+    body["segment"] = json!("tag = ");
+    let response = server.post_api_newsletters(&token, Some(&key), &body).await;
+    assert_eq!(response.status().as_u16(), 400);
+    let error: Value = response.json().await.unwrap();
+    assert_eq!(
+        error["error"],
+        "The segment is invalid: Expected a value, found the end of the \
+        segment at character 7"
+    );
+}
+
+#[sqlx::test]
+async fn unknown_merge_fields_are_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let token = authenticated_token(&server).await;
+    let key = Uuid::new_v4().to_string();
+    let mut body = body();
+    body["html_content"] = json!("<p>Hi {{ nickname }}</p>");
+    let response = server.post_api_newsletters(&token, Some(&key), &body).await;
+    assert_eq!(response.status().as_u16(), 400);
+    let error: Value = response.json().await.unwrap();
+    assert_eq!(
+        error["error"],
+        "The HTML content is invalid: unknown placeholders nickname, use \
+        name, email, unsubscribe_url"
+    );
+}
+
+#[sqlx::test]
+async fn issues_can_be_published_in_markdown(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let token = authenticated_token(&server).await;
+    let key = Uuid::new_v4().to_string();
+    let body = json!({
+        "title": "Newsletter title",
+        "markdown_content": "Hello **{{ name }}**",
+    });
+    let response = server.post_api_newsletters(&token, Some(&key), &body).await;
+    assert_eq!(response.status().as_u16(), 202);
+    let issue = sqlx::query!(
+        "select text_content, html_content, markdown_content \
+        from newsletter_issues"
+    )
+    .fetch_one(&server.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(issue.text_content, "Hello {{ name }}");
+    assert_eq!(
+        issue.html_content,
+        "<p>Hello <strong>{{ name }}</strong></p>\n"
+    );
+    assert_eq!(issue.markdown_content.unwrap(), "Hello **{{ name }}**");
+}
+
+#[sqlx::test]
+async fn markdown_cannot_be_mixed_with_hand_written_bodies(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let token = authenticated_token(&server).await;
+    let key = Uuid::new_v4().to_string();
+    let mut body = body();
+    body["markdown_content"] = json!("# News");
+    let response = server.post_api_newsletters(&token, Some(&key), &body).await;
+    assert_eq!(response.status().as_u16(), 400);
+    let error: Value = response.json().await.unwrap();
+    assert_eq!(
+        error["error"],
+        "Provide either markdown_content or both text_content and html_content"
+    );
+}
+
+#[sqlx::test]
+async fn unsafe_html_content_is_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let token = authenticated_token(&server).await;
+    let key = Uuid::new_v4().to_string();
+    let mut body = body();
+    body["html_content"] = json!("<script>steal()</script>");
+    let response = server.post_api_newsletters(&token, Some(&key), &body).await;
+    assert_eq!(response.status().as_u16(), 400);
+    let error: Value = response.json().await.unwrap();
+    assert_eq!(
+        error["error"],
+        "The HTML content could not be processed: nothing is left once \
+        unsafe tags are removed"
+    );
+}
+
+#[sqlx::test]
+async fn unknown_assets_are_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let token = authenticated_token(&server).await;
+    let key = Uuid::new_v4().to_string();
+    let mut body = body();
+    body["assets"] = json!(["missing.pdf"]);
+    let response = server.post_api_newsletters(&token, Some(&key), &body).await;
+    assert_eq!(response.status().as_u16(), 400);
+    let error: Value = response.json().await.unwrap();
+    assert_eq!(error["error"], "There is no asset called missing.pdf");
+}
+
+#[sqlx::test]
+async fn issues_can_be_published_with_tracking(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let token = authenticated_token(&server).await;
+    let key = Uuid::new_v4().to_string();
+    let mut body = body();
+    body["tracking"] = json!(true);
+    let response = server.post_api_newsletters(&token, Some(&key), &body).await;
+    assert_eq!(response.status().as_u16(), 202);
+    let published: Value = response.json().await.unwrap();
+    let issue = sqlx::query!(
+        "select newsletter_issue_id, tracking from newsletter_issues"
+    )
+    .fetch_one(&server.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        published["newsletter_issue_id"],
+        issue.newsletter_issue_id.to_string()
+    );
+    assert!(issue.tracking);
+}
@@ -0,0 +1,154 @@
+use super::authenticated_token;
+use crate::{
+    newsletter::{create_confirmed_subscriber, create_unconfirmed_subscriber},
+    TestServer,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+use zero2prod::DbPool;
+
+#[sqlx::test]
+async fn requests_without_a_token_are_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let response = server
+        .http_client
+        .get(server.api_subscribers())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[sqlx::test]
+async fn subscribers_are_listed_with_their_status(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_confirmed_subscriber(&server).await;
+    create_unconfirmed_subscriber(&server).await;
+    let token = authenticated_token(&server).await;
+
+    let response = server.get_api_subscribers(&token, &[]).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let list: Value = response.json().await.unwrap();
+    let subscribers = list["subscribers"].as_array().unwrap();
+    assert_eq!(subscribers.len(), 2);
+    assert_eq!(subscribers[0]["status"], "confirmed");
+    assert_eq!(subscribers[1]["status"], "pending_confirmation");
+    assert!(subscribers[0]["email"].is_string());
+    assert!(list["next_cursor"].is_null());
+}
+
+#[sqlx::test]
+async fn subscribers_can_be_filtered_by_status(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_confirmed_subscriber(&server).await;
+    create_unconfirmed_subscriber(&server).await;
+    let token = authenticated_token(&server).await;
+
+    let response = server
+        .get_api_subscribers(&token, &[("status", "pending_confirmation")])
+        .await;
+    let list: Value = response.json().await.unwrap();
+    let subscribers = list["subscribers"].as_array().unwrap();
+    assert_eq!(subscribers.len(), 1);
+    assert_eq!(subscribers[0]["status"], "pending_confirmation");
+}
+
+#[sqlx::test]
+async fn subscribers_are_paginated_with_a_cursor(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    for _ in 0..3 {
+        create_unconfirmed_subscriber(&server).await;
+    }
+    let token = authenticated_token(&server).await;
+
+    let mut seen = Vec::new();
+    let mut query = vec![("limit", "2".to_string())];
+    loop {
+        let query_ref: Vec<_> =
+            query.iter().map(|(k, v)| (*k, v.as_str())).collect();
+        let response = server.get_api_subscribers(&token, &query_ref).await;
+        let list: Value = response.json().await.unwrap();
+        let subscribers = list["subscribers"].as_array().unwrap();
+        assert!(subscribers.len() <= 2);
+        seen.extend(subscribers.iter().map(|s| s["id"].clone()));
+        match list["next_cursor"].as_str() {
+            Some(cursor) => {
+                query = vec![("limit", "2".into()), ("cursor", cursor.into())]
+            }
+            None => break,
+        }
+    }
+    assert_eq!(seen.len(), 3);
+    seen.dedup();
+    assert_eq!(seen.len(), 3);
+}
+
+#[sqlx::test]
+async fn invalid_list_parameters_are_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let token = authenticated_token(&server).await;
+    let cases = [
+        [("status", "sleeping")],
+        [("limit", "0")],
+        [("limit", "1000")],
+        [("cursor", "garbage")],
+    ];
+    for query in cases {
+        let response = server.get_api_subscribers(&token, &query).await;
+        assert_eq!(response.status().as_u16(), 400, "{query:?}");
+        let error: Value = response.json().await.unwrap();
+        assert!(error["error"].is_string());
+    }
+}
+
+#[sqlx::test]
+async fn a_subscriber_can_be_fetched_updated_and_deleted(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_unconfirmed_subscriber(&server).await;
+    let token = authenticated_token(&server).await;
+    let list: Value = server
+        .get_api_subscribers(&token, &[])
+        .await
+        .json()
+        .await
+        .unwrap();
+    let id = list["subscribers"][0]["id"].as_str().unwrap().to_owned();
+
+    let response = server.get_api_subscriber(&token, &id).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let subscriber: Value = response.json().await.unwrap();
+    assert_eq!(subscriber["status"], "pending_confirmation");
+
+    let body = json!({ "status": "unsubscribed" });
+    let response = server.patch_api_subscriber(&token, &id, &body).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let subscriber: Value = response.json().await.unwrap();
+    assert_eq!(subscriber["status"], "unsubscribed");
+
+    let response = server.delete_api_subscriber(&token, &id).await;
+    assert_eq!(response.status().as_u16(), 204);
+    let response = server.get_api_subscriber(&token, &id).await;
+    assert_eq!(response.status().as_u16(), 404);
+    let tokens = sqlx::query!("select count(*) from subscription_tokens")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(tokens.count, Some(0));
+}
+
+#[sqlx::test]
+async fn unknown_subscribers_are_not_found(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let token = authenticated_token(&server).await;
+    let id = Uuid::new_v4().to_string();
+
+    let response = server.get_api_subscriber(&token, &id).await;
+    assert_eq!(response.status().as_u16(), 404);
+    let body = json!({ "status": "confirmed" });
+    let response = server.patch_api_subscriber(&token, &id, &body).await;
+    assert_eq!(response.status().as_u16(), 404);
+    let response = server.delete_api_subscriber(&token, &id).await;
+    assert_eq!(response.status().as_u16(), 404);
+    let response = server.get_api_subscriber(&token, "not-a-uuid").await;
+    assert_eq!(response.status().as_u16(), 400);
+}
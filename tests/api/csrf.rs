@@ -0,0 +1,118 @@
+use crate::{TestServer, TestUser, FAILED_TO_EXECUTE_REQUEST};
+use hashmap_macro::hashmap;
+use zero2prod::DbPool;
+
+#[sqlx::test]
+async fn login_form_contains_a_csrf_token(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let html_page = server.get_login().await.text().await.unwrap();
+    assert!(html_page.contains(r#"name="csrf_token""#));
+}
+
+#[sqlx::test]
+async fn login_without_csrf_token_is_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let user = TestUser::stored(&server.db_pool).await;
+    server.get_login().await;
+    let body = hashmap!(
+        "username" => user.username.as_str(),
+        "password" => user.password.as_str(),
+    );
+    let response = server
+        .http_client
+        .post(server.login())
+        .form(&body)
+        .send()
+        .await
+        .expect(FAILED_TO_EXECUTE_REQUEST);
+    assert_eq!(response.status().as_u16(), 403);
+}
+
+#[sqlx::test]
+async fn admin_post_with_mismatched_csrf_token_is_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let user = TestUser::stored(&server.db_pool).await;
+    user.login(&server).await;
+    let body = hashmap!("csrf_token" => "forged");
+    let response = server
+        .http_client
+        .post(server.admin_logout())
+        .form(&body)
+        .send()
+        .await
+        .expect(FAILED_TO_EXECUTE_REQUEST);
+    assert_eq!(response.status().as_u16(), 403);
+
+    let response = server.get_admin_dashboard().await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[sqlx::test]
+async fn admin_post_without_csrf_token_is_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let user = TestUser::stored(&server.db_pool).await;
+    user.login(&server).await;
+    let response = server
+        .http_client
+        .post(server.admin_sessions_revoke_all())
+        .send()
+        .await
+        .expect(FAILED_TO_EXECUTE_REQUEST);
+    assert_eq!(response.status().as_u16(), 403);
+}
+
+#[sqlx::test]
+async fn admin_forms_contain_the_session_csrf_token(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let user = TestUser::stored(&server.db_pool).await;
+    user.login(&server).await;
+    let csrf_token = server.csrf_token().await;
+    let field = format!(r#"name="csrf_token" value="{csrf_token}""#);
+    for page in [
+        server.get_admin_dashboard().await,
+        server.get_admin_password().await,
+        server.get_admin_newsletters().await,
+        server.get_admin_sessions().await,
+        server
+            .http_client
+            .get(server.admin_subscribers_import())
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST),
+        server.get_admin_assets().await,
+    ] {
+        assert!(page.text().await.unwrap().contains(&field));
+    }
+}
+
+#[sqlx::test]
+async fn uploads_need_the_csrf_token_as_a_form_field(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let user = TestUser::stored(&server.db_pool).await;
+    user.login(&server).await;
+    let csrf_token = server.csrf_token().await;
+    for url in [server.admin_assets(), server.admin_subscribers_import()] {
+        for form in [
+            reqwest::multipart::Form::new(),
+            reqwest::multipart::Form::new().text("csrf_token", "forged"),
+        ] {
+            let file = reqwest::multipart::Part::text("a,a@example.com")
+                .file_name("upload.csv");
+            let response = server
+                .http_client
+                .post(&url)
+                .query(&[("csrf_token", &csrf_token)])
+                .multipart(form.part("file", file))
+                .send()
+                .await
+                .expect(FAILED_TO_EXECUTE_REQUEST);
+            assert_eq!(response.status().as_u16(), 403);
+        }
+    }
+    let assets =
+        sqlx::query!("select count(*) as \"count!\" from newsletter_assets")
+            .fetch_one(&server.db_pool)
+            .await
+            .unwrap();
+    assert_eq!(assets.count, 0);
+}
@@ -1,8 +1,12 @@
 mod admin;
+mod api_v1;
+mod csrf;
 mod health_check;
 mod login;
 mod newsletter;
 mod subscriptions;
+mod tracking;
+mod webhooks;
 
 use argon2::{
     password_hash::SaltString, Algorithm, Argon2, Params, PasswordHasher,
@@ -11,14 +15,16 @@ use argon2::{
 use dotenvy::dotenv;
 use hashmap_macro::hashmap;
 use once_cell::sync::Lazy;
+use regex::Regex;
 use reqwest::{header::LOCATION, redirect::Policy, Client, Response, Url};
+use serde::Serialize;
 use std::collections::HashMap;
 use uuid::Uuid;
 use wiremock::MockServer;
 use zero2prod::{
     configuration::Config,
     issue_delivery::{try_execute_task, ExecutionOutcome},
-    telemetry, DbPool, EmailClient, Server,
+    telemetry, DbPool, EmailClient, EmailTemplates, Locales, Server,
 };
 
 static FAILED_TO_EXECUTE_REQUEST: &str = "Failed to execute request";
@@ -39,10 +45,18 @@ pub struct TestServer {
     pub db_pool: DbPool,
     pub email_server: MockServer,
     pub email_client: EmailClient,
+    pub email_templates: EmailTemplates,
 }
 
 impl TestServer {
     pub async fn run(db_pool: DbPool) -> Self {
+        Self::run_with_config(db_pool, |_| {}).await
+    }
+
+    pub async fn run_with_config(
+        db_pool: DbPool,
+        configure: impl FnOnce(&mut Config),
+    ) -> Self {
         dotenv().ok();
         Lazy::force(&TELEMETRY)
             .as_ref()
@@ -55,19 +69,21 @@ impl TestServer {
             c.database.options.database =
                 db_pool.connect_options().get_database().unwrap().into();
             c.email_client.base_url = Url::parse(&email_server.uri()).unwrap();
+            configure(&mut c);
             c
         };
         let email_client = EmailClient::new(config.email_client.clone());
+        let locales = Locales::load(config.locales.clone())
+            .expect("Failed to load locales");
+        let email_templates =
+            EmailTemplates::load(config.email_templates.clone(), &locales)
+                .expect("Failed to load email templates");
         let server = Server::build(config.clone())
             .await
             .expect("Failed to run server");
         let base_url = config.application.base_url;
         let port = server.port();
-        let http_client = Client::builder()
-            .redirect(Policy::none())
-            .cookie_store(true)
-            .build()
-            .unwrap();
+        let http_client = build_http_client();
         #[allow(clippy::let_underscore_future)]
         let _ = tokio::spawn(server.run());
         Self {
@@ -77,10 +93,19 @@ impl TestServer {
             db_pool,
             email_server,
             email_client,
+            email_templates,
         }
     }
 }
 
+pub fn build_http_client() -> Client {
+    Client::builder()
+        .redirect(Policy::none())
+        .cookie_store(true)
+        .build()
+        .unwrap()
+}
+
 impl TestServer {
     async fn get_health_check(&self) -> Response {
         self.http_client
@@ -101,7 +126,7 @@ impl TestServer {
     async fn post_login(&self, body: &HashMap<&str, &str>) -> Response {
         self.http_client
             .post(self.login())
-            .form(body)
+            .form(&self.with_csrf_token(body).await)
             .send()
             .await
             .expect(FAILED_TO_EXECUTE_REQUEST)
@@ -110,6 +135,7 @@ impl TestServer {
     async fn post_admin_logout(&self) -> Response {
         self.http_client
             .post(self.admin_logout())
+            .form(&self.with_csrf_token(&HashMap::new()).await)
             .send()
             .await
             .expect(FAILED_TO_EXECUTE_REQUEST)
@@ -137,7 +163,311 @@ impl TestServer {
     ) -> Response {
         self.http_client
             .post(self.admin_password())
-            .form(body)
+            .form(&self.with_csrf_token(body).await)
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn get_admin_sessions(&self) -> Response {
+        self.http_client
+            .get(self.admin_sessions())
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn post_admin_sessions_revoke(
+        &self,
+        body: &HashMap<&str, &str>,
+    ) -> Response {
+        self.http_client
+            .post(self.admin_sessions_revoke())
+            .form(&self.with_csrf_token(body).await)
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn post_admin_sessions_revoke_all(&self) -> Response {
+        self.http_client
+            .post(self.admin_sessions_revoke_all())
+            .form(&self.with_csrf_token(&HashMap::new()).await)
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn get_admin_api_tokens(&self) -> Response {
+        self.http_client
+            .get(self.admin_api_tokens())
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn post_admin_api_tokens(
+        &self,
+        body: &HashMap<&str, &str>,
+    ) -> Response {
+        self.http_client
+            .post(self.admin_api_tokens())
+            .form(&self.with_csrf_token(body).await)
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn post_admin_api_tokens_revoke(
+        &self,
+        body: &HashMap<&str, &str>,
+    ) -> Response {
+        self.http_client
+            .post(self.admin_api_tokens_revoke())
+            .form(&self.with_csrf_token(body).await)
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn get_admin_subscribers(&self, query: &[(&str, &str)]) -> Response {
+        self.http_client
+            .get(self.admin_subscribers())
+            .query(query)
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn post_admin_subscribers_action(
+        &self,
+        action: &str,
+        subscriber_id: &str,
+    ) -> Response {
+        let body = hashmap!("subscriber_id" => subscriber_id);
+        self.http_client
+            .post(format!("{}/{action}", self.admin_subscribers()))
+            .form(&self.with_csrf_token(&body).await)
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn post_admin_subscribers_import(
+        &self,
+        csv: &str,
+        send_confirmation: bool,
+    ) -> Response {
+        let file = reqwest::multipart::Part::text(csv.to_owned())
+            .file_name("subscribers.csv")
+            .mime_str("text/csv")
+            .unwrap();
+        let mut form = reqwest::multipart::Form::new()
+            .text("csrf_token", self.csrf_token().await)
+            .part("file", file);
+        if send_confirmation {
+            form = form.text("send_confirmation", "on");
+        }
+        self.http_client
+            .post(self.admin_subscribers_import())
+            .multipart(form)
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn get_admin_assets(&self) -> Response {
+        self.http_client
+            .get(self.admin_assets())
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn post_admin_assets(
+        &self,
+        file_name: &str,
+        content: &[u8],
+        mime: &str,
+    ) -> Response {
+        let file = reqwest::multipart::Part::bytes(content.to_vec())
+            .file_name(file_name.to_owned())
+            .mime_str(mime)
+            .unwrap();
+        let form = reqwest::multipart::Form::new()
+            .text("csrf_token", self.csrf_token().await)
+            .part("file", file);
+        self.http_client
+            .post(self.admin_assets())
+            .multipart(form)
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn get_admin_export(&self, table: &str, format: &str) -> Response {
+        self.http_client
+            .get(format!("{}/{table}", self.admin_exports()))
+            .query(&[("format", format)])
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn post_admin_gdpr(&self, action: &str, email: &str) -> Response {
+        let body = hashmap!("email" => email);
+        self.http_client
+            .post(format!("{}/gdpr/{action}", self.admin()))
+            .form(&self.with_csrf_token(&body).await)
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn get_admin_stats(&self, path: &str) -> Response {
+        self.http_client
+            .get(format!("{}/stats{path}", self.admin()))
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn post_postmark_webhook(
+        &self,
+        credentials: Option<(&str, &str)>,
+        event: &serde_json::Value,
+    ) -> Response {
+        let mut request = self
+            .http_client
+            .post(format!("{}/webhooks/postmark", self.addr()))
+            .json(event);
+        if let Some((username, password)) = credentials {
+            request = request.basic_auth(username, Some(password));
+        }
+        request.send().await.expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn get_admin_lists(&self) -> Response {
+        self.http_client
+            .get(self.admin_lists())
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn post_admin_lists(&self, body: &HashMap<&str, &str>) -> Response {
+        self.http_client
+            .post(self.admin_lists())
+            .form(&self.with_csrf_token(body).await)
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn post_admin_lists_opt_in(
+        &self,
+        body: &HashMap<&str, &str>,
+    ) -> Response {
+        self.http_client
+            .post(format!("{}/opt_in", self.admin_lists()))
+            .form(&self.with_csrf_token(body).await)
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn get_admin_tags(&self) -> Response {
+        self.http_client
+            .get(self.admin_tags())
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn post_admin_tags(
+        &self,
+        action: &str,
+        body: &HashMap<&str, &str>,
+    ) -> Response {
+        self.http_client
+            .post(format!("{}/{action}", self.admin_tags()))
+            .form(&self.with_csrf_token(body).await)
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn post_admin_newsletters_preview(
+        &self,
+        body: &HashMap<&str, &str>,
+    ) -> Response {
+        self.http_client
+            .post(format!("{}/preview", self.admin_newsletters()))
+            .form(&self.with_csrf_token(body).await)
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn post_api_newsletters(
+        &self,
+        token: &str,
+        idempotency_key: Option<&str>,
+        body: &serde_json::Value,
+    ) -> Response {
+        let mut request = self
+            .http_client
+            .post(self.api_newsletters())
+            .bearer_auth(token)
+            .json(body);
+        if let Some(key) = idempotency_key {
+            request = request.header("Idempotency-Key", key);
+        }
+        request.send().await.expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn get_api_subscribers(
+        &self,
+        token: &str,
+        query: &[(&str, &str)],
+    ) -> Response {
+        self.http_client
+            .get(self.api_subscribers())
+            .bearer_auth(token)
+            .query(query)
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn get_api_subscriber(&self, token: &str, id: &str) -> Response {
+        self.http_client
+            .get(self.api_subscriber(id))
+            .bearer_auth(token)
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn patch_api_subscriber(
+        &self,
+        token: &str,
+        id: &str,
+        body: &serde_json::Value,
+    ) -> Response {
+        self.http_client
+            .patch(self.api_subscriber(id))
+            .bearer_auth(token)
+            .json(body)
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn delete_api_subscriber(&self, token: &str, id: &str) -> Response {
+        self.http_client
+            .delete(self.api_subscriber(id))
+            .bearer_auth(token)
             .send()
             .await
             .expect(FAILED_TO_EXECUTE_REQUEST)
@@ -152,6 +482,57 @@ impl TestServer {
             .expect(FAILED_TO_EXECUTE_REQUEST)
     }
 
+    async fn get_subscriptions_preferences(&self, token: &str) -> Response {
+        self.http_client
+            .get(self.subscriptions_preferences())
+            .query(&[("subscription_token", token)])
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn post_subscriptions_preferences<Body: Serialize + ?Sized>(
+        &self,
+        body: &Body,
+    ) -> Response {
+        self.http_client
+            .post(self.subscriptions_preferences())
+            .form(body)
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn get_subscriptions_unsubscribe(&self, token: &str) -> Response {
+        self.http_client
+            .get(self.subscriptions_unsubscribe())
+            .query(&[("unsubscribe_token", token)])
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn post_subscriptions_unsubscribe(&self, token: &str) -> Response {
+        self.http_client
+            .post(self.subscriptions_unsubscribe())
+            .form(&[("unsubscribe_token", token)])
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
+    async fn post_subscriptions_email_change(
+        &self,
+        body: &HashMap<&str, &str>,
+    ) -> Response {
+        self.http_client
+            .post(format!("{}/email_change", self.subscriptions()))
+            .form(body)
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+
     async fn get_subscriptions_confirm(&self) -> Response {
         self.http_client
             .get(self.subscriptions_confirm())
@@ -174,10 +555,63 @@ impl TestServer {
     ) -> Response {
         self.http_client
             .post(self.admin_newsletters())
-            .form(body)
+            .form(&self.with_csrf_token(body).await)
+            .send()
+            .await
+            .expect(FAILED_TO_EXECUTE_REQUEST)
+    }
+}
+
+impl TestServer {
+    async fn csrf_token(&self) -> String {
+        self.csrf_token_for(&self.http_client).await
+    }
+
+    async fn csrf_token_for(&self, client: &Client) -> String {
+        let html_page = client
+            .get(self.login())
             .send()
             .await
             .expect(FAILED_TO_EXECUTE_REQUEST)
+            .text()
+            .await
+            .unwrap();
+        let pattern = Regex::new(r#"name="csrf_token" value="(\w+)""#).unwrap();
+        pattern
+            .captures(&html_page)
+            .and_then(|c| c.get(1))
+            .map(|m| m.as_str().to_owned())
+            .expect("Login form does not contain a CSRF token")
+    }
+
+    /// Creates an API token through the admin area.
+    /// The user must be logged in.
+    async fn create_api_token(&self) -> String {
+        let body = hashmap!("name" => "test token");
+        let response = self.post_admin_api_tokens(&body).await;
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(response.headers()["Cache-Control"], "no-store");
+        let html_page = response.text().await.unwrap();
+        let pattern =
+            Regex::new(r"Your new API token: <code>(\w+)</code>\.").unwrap();
+        pattern
+            .captures(&html_page)
+            .and_then(|c| c.get(1))
+            .map(|m| m.as_str().to_owned())
+            .expect("API token was not shown after creation")
+    }
+
+    async fn with_csrf_token<'a>(
+        &self,
+        body: &HashMap<&'a str, &'a str>,
+    ) -> HashMap<String, String> {
+        let mut body = body
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<HashMap<_, _>>();
+        body.entry("csrf_token".into())
+            .or_insert(self.csrf_token().await);
+        body
     }
 }
 
@@ -212,10 +646,14 @@ impl TestServer {
 
     async fn dispatch_pending_emails(&self) {
         loop {
-            if let ExecutionOutcome::EmptyQueue =
-                try_execute_task(&self.db_pool, &self.email_client)
-                    .await
-                    .unwrap()
+            if let ExecutionOutcome::EmptyQueue = try_execute_task(
+                &self.db_pool,
+                &self.email_client,
+                &self.email_templates,
+                &self.base_url,
+            )
+            .await
+            .unwrap()
             {
                 break;
             }
@@ -313,6 +751,15 @@ impl TestUser {
         );
         server.post_login(&body).await
     }
+
+    async fn login_remembered(&self, server: &TestServer) -> Response {
+        let body = hashmap!(
+            "username" => self.username.as_str(),
+            "password" => self.password.as_str(),
+            "remember_me" => "on",
+        );
+        server.post_login(&body).await
+    }
 }
 
 impl TestServer {
@@ -344,10 +791,78 @@ impl TestServer {
         format!("{}/password", self.admin())
     }
 
+    fn admin_sessions(&self) -> String {
+        format!("{}/sessions", self.admin())
+    }
+
+    fn admin_sessions_revoke(&self) -> String {
+        format!("{}/revoke", self.admin_sessions())
+    }
+
+    fn admin_sessions_revoke_all(&self) -> String {
+        format!("{}/revoke_all", self.admin_sessions())
+    }
+
+    fn admin_api_tokens(&self) -> String {
+        format!("{}/api_tokens", self.admin())
+    }
+
+    fn admin_api_tokens_revoke(&self) -> String {
+        format!("{}/revoke", self.admin_api_tokens())
+    }
+
+    fn admin_exports(&self) -> String {
+        format!("{}/exports", self.admin())
+    }
+
+    fn admin_assets(&self) -> String {
+        format!("{}/assets", self.admin())
+    }
+
+    fn admin_lists(&self) -> String {
+        format!("{}/lists", self.admin())
+    }
+
+    fn admin_tags(&self) -> String {
+        format!("{}/tags", self.admin())
+    }
+
+    fn admin_subscribers(&self) -> String {
+        format!("{}/subscribers", self.admin())
+    }
+
+    fn admin_subscribers_import(&self) -> String {
+        format!("{}/import", self.admin_subscribers())
+    }
+
+    fn api(&self) -> String {
+        format!("{}/api/v1", self.addr())
+    }
+
+    fn api_newsletters(&self) -> String {
+        format!("{}/newsletters", self.api())
+    }
+
+    fn api_subscribers(&self) -> String {
+        format!("{}/subscribers", self.api())
+    }
+
+    fn api_subscriber(&self, id: &str) -> String {
+        format!("{}/{id}", self.api_subscribers())
+    }
+
     fn subscriptions(&self) -> String {
         format!("{}/subscriptions", self.addr())
     }
 
+    fn subscriptions_preferences(&self) -> String {
+        format!("{}/preferences", self.subscriptions())
+    }
+
+    fn subscriptions_unsubscribe(&self) -> String {
+        format!("{}/unsubscribe", self.subscriptions())
+    }
+
     fn subscriptions_confirm(&self) -> String {
         format!("{}/confirm", self.subscriptions())
     }
@@ -76,6 +76,340 @@ async fn newsletters_are_delievered_to_confirmed_subscribers(pool: DbPool) {
     server.dispatch_pending_emails().await;
 }
 
+#[sqlx::test]
+async fn issues_are_wrapped_in_the_newsletter_template(pool: DbPool) {
+    let server = TestServer::run_with_config(pool, |c| {
+        c.email_templates.subjects.newsletter = "Weekly: {{ title }}".into();
+    })
+    .await;
+    create_confirmed_subscriber(&server).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(1))
+        .await;
+    let user = TestUser::stored(&server.db_pool).await;
+    user.login(&server).await;
+    let idempotency_key = Uuid::new_v4().to_string();
+    server.post_admin_newsletters(&body(&idempotency_key)).await;
+    server.dispatch_pending_emails().await;
+
+    let email_request = server
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let email: serde_json::Value = email_request.body_json().unwrap();
+    assert_eq!(email["Subject"], "Weekly: Newsletter title");
+    assert_eq!(email["TextBody"], "Newsletter body as plain text");
+    let html = email["HtmlBody"].as_str().unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<title>Newsletter title</title>"));
+    assert!(html.contains("<p>Newsletter body as HTML</p>"));
+}
+
+#[sqlx::test]
+async fn merge_fields_are_filled_in_for_each_recipient(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let email = create_confirmed_subscriber_on(&server, &["default"]).await;
+    sqlx::query!("update subscriptions set name = 'Tom & Jerry'")
+        .execute(&server.db_pool)
+        .await
+        .unwrap();
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(1))
+        .await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let idempotency_key = Uuid::new_v4().to_string();
+    let mut body = body(&idempotency_key);
+    body.insert(
+        "textContent",
+        "Hi {{ name }} ({{ email }}), leave at {{ unsubscribe_url }}",
+    );
+    body.insert(
+        "htmlContent",
+        r#"<p>Hi {{ name }}</p><a href="{{ unsubscribe_url }}">Leave</a>"#,
+    );
+    let response = server.post_admin_newsletters(&body).await;
+    server.assert_is_redirect_to(&response, "/admin/newsletters");
+    server.dispatch_pending_emails().await;
+
+    let email_request = server
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let sent: serde_json::Value = email_request.body_json().unwrap();
+    let text = sent["TextBody"].as_str().unwrap();
+    assert!(text.starts_with(&format!("Hi Tom & Jerry ({email}), leave at")));
+    let html = sent["HtmlBody"].as_str().unwrap();
+    assert!(html.contains("<p>Hi Tom &amp; Jerry</p>"));
+    let links = server.extract_links(&email_request);
+    assert_eq!(links.text, links.html);
+    assert_eq!(links.text.path(), "/subscriptions/unsubscribe");
+    let response = server.http_client.get(links.html).send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[sqlx::test]
+async fn issues_are_tagged_and_carry_an_unsubscribe_header(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_confirmed_subscriber(&server).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(1))
+        .await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let idempotency_key = Uuid::new_v4().to_string();
+    server.post_admin_newsletters(&body(&idempotency_key)).await;
+    server.dispatch_pending_emails().await;
+
+    let email_request = server
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let sent: serde_json::Value = email_request.body_json().unwrap();
+    let issue =
+        sqlx::query!("select newsletter_issue_id from newsletter_issues")
+            .fetch_one(&server.db_pool)
+            .await
+            .unwrap();
+    assert_eq!(sent["Tag"], "newsletter");
+    assert_eq!(
+        sent["Metadata"]["newsletter_issue_id"],
+        issue.newsletter_issue_id.to_string()
+    );
+    assert_eq!(sent["Headers"][0]["Name"], "List-Unsubscribe");
+    let value = sent["Headers"][0]["Value"].as_str().unwrap();
+    let url = reqwest::Url::parse(&value[1..value.len() - 1]).unwrap();
+    assert_eq!(url.path(), "/subscriptions/unsubscribe");
+}
+
+#[sqlx::test]
+async fn unknown_merge_fields_are_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_confirmed_subscriber(&server).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(0))
+        .await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let idempotency_key = Uuid::new_v4().to_string();
+    let mut body = body(&idempotency_key);
+    body.insert("textContent", "Hi {{ first_name }}");
+    let response = server.post_admin_newsletters(&body).await;
+    server.assert_is_redirect_to(&response, "/admin/newsletters");
+    let html_page = server.get_admin_newsletters().await.text().await.unwrap();
+    assert!(html_page.contains(
+        "<p><i>The text content is invalid: unknown placeholders \
+        first_name, use name, email, unsubscribe_url</i></p>"
+    ));
+    let issues =
+        sqlx::query!("select newsletter_issue_id from newsletter_issues")
+            .fetch_all(&server.db_pool)
+            .await
+            .unwrap();
+    assert!(issues.is_empty());
+}
+
+#[sqlx::test]
+async fn issues_can_be_written_in_markdown(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_confirmed_subscriber(&server).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(1))
+        .await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let idempotency_key = Uuid::new_v4().to_string();
+    let markdown = "# News\n\nRead [more](https://example.com).";
+    let body = hashmap!(
+        "title" => "Newsletter title",
+        "markdownContent" => markdown,
+        "idempotencyKey" => idempotency_key.as_str(),
+        "lists" => "default"
+    );
+    let response = server.post_admin_newsletters(&body).await;
+    server.assert_is_redirect_to(&response, "/admin/newsletters");
+    server.dispatch_pending_emails().await;
+
+    let email_request = server
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let email: serde_json::Value = email_request.body_json().unwrap();
+    assert_eq!(
+        email["TextBody"],
+        "News\n====\n\nRead more (https://example.com)."
+    );
+    assert!(email["HtmlBody"].as_str().unwrap().contains(
+        "<h1>News</h1>\n<p>Read <a href=\"https://example.com\">more</a>.</p>"
+    ));
+    let issue = sqlx::query!("select markdown_content from newsletter_issues")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(issue.markdown_content.as_deref(), Some(markdown));
+}
+
+#[sqlx::test]
+async fn markdown_cannot_be_mixed_with_hand_written_bodies(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let idempotency_key = Uuid::new_v4().to_string();
+    let mut body = body(&idempotency_key);
+    body.insert("markdownContent", "# News");
+    let response = server.post_admin_newsletters(&body).await;
+    server.assert_is_redirect_to(&response, "/admin/newsletters");
+    let html_page = server.get_admin_newsletters().await.text().await.unwrap();
+    assert!(html_page.contains(
+        "<p><i>Fill in either the Markdown content or the plain text and \
+        HTML content, not both.</i></p>"
+    ));
+}
+
+#[sqlx::test]
+async fn html_content_is_sanitized_and_its_css_inlined(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let idempotency_key = Uuid::new_v4().to_string();
+    let mut body = body(&idempotency_key);
+    body.insert(
+        "htmlContent",
+        "<style>p { color: red }</style>\
+        <p onclick=\"steal()\">Hi</p><script>steal()</script>",
+    );
+    let response = server.post_admin_newsletters(&body).await;
+    server.assert_is_redirect_to(&response, "/admin/newsletters");
+    let issue = sqlx::query!("select html_content from newsletter_issues")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(issue.html_content, r#"<p style="color: red;">Hi</p>"#);
+}
+
+#[sqlx::test]
+async fn html_content_with_nothing_safe_is_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let idempotency_key = Uuid::new_v4().to_string();
+    let mut body = body(&idempotency_key);
+    body.insert("htmlContent", "<script>steal()</script>");
+    let response = server.post_admin_newsletters(&body).await;
+    server.assert_is_redirect_to(&response, "/admin/newsletters");
+    let html_page = server.get_admin_newsletters().await.text().await.unwrap();
+    assert!(html_page.contains(
+        "<p><i>The HTML content could not be processed: nothing is left \
+        once unsafe tags are removed</i></p>"
+    ));
+    let issues =
+        sqlx::query!("select newsletter_issue_id from newsletter_issues")
+            .fetch_all(&server.db_pool)
+            .await
+            .unwrap();
+    assert!(issues.is_empty());
+}
+
+#[sqlx::test]
+async fn assets_are_sent_with_the_issue(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_confirmed_subscriber(&server).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(1))
+        .await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    server
+        .post_admin_assets("logo.png", b"png", "image/png")
+        .await;
+    server
+        .post_admin_assets("terms.pdf", b"pdf", "application/pdf")
+        .await;
+    let idempotency_key = Uuid::new_v4().to_string();
+    let mut body = body(&idempotency_key);
+    body.insert("htmlContent", r#"<p>Hi</p><img src="cid:logo.png">"#);
+    body.insert("assets", "terms.pdf");
+    let response = server.post_admin_newsletters(&body).await;
+    server.assert_is_redirect_to(&response, "/admin/newsletters");
+    server.dispatch_pending_emails().await;
+
+    let email_request = server
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let email: serde_json::Value = email_request.body_json().unwrap();
+    assert!(email["HtmlBody"]
+        .as_str()
+        .unwrap()
+        .contains(r#"<img src="cid:logo.png">"#));
+    assert_eq!(
+        email["Attachments"],
+        serde_json::json!([
+            {
+                "Name": "logo.png",
+                "Content": "cG5n",
+                "ContentType": "image/png",
+                "ContentID": "cid:logo.png",
+            },
+            {
+                "Name": "terms.pdf",
+                "Content": "cGRm",
+                "ContentType": "application/pdf",
+            },
+        ])
+    );
+}
+
+#[sqlx::test]
+async fn unknown_assets_are_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let idempotency_key = Uuid::new_v4().to_string();
+    let mut body = body(&idempotency_key);
+    body.insert("htmlContent", r#"<img src="cid:missing.png">"#);
+    let response = server.post_admin_newsletters(&body).await;
+    server.assert_is_redirect_to(&response, "/admin/newsletters");
+    let html_page = server.get_admin_newsletters().await.text().await.unwrap();
+    assert!(html_page
+        .contains("<p><i>There is no asset called missing.png</i></p>"));
+    let issues =
+        sqlx::query!("select newsletter_issue_id from newsletter_issues")
+            .fetch_all(&server.db_pool)
+            .await
+            .unwrap();
+    assert!(issues.is_empty());
+}
+
+#[sqlx::test]
+async fn assets_over_the_size_limit_are_rejected(pool: DbPool) {
+    let server = TestServer::run_with_config(pool, |c| {
+        c.email_client.max_attachments_size = 5;
+    })
+    .await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    server.post_admin_assets("a.png", b"aaa", "image/png").await;
+    server.post_admin_assets("b.png", b"bbb", "image/png").await;
+    let idempotency_key = Uuid::new_v4().to_string();
+    let mut body = body(&idempotency_key);
+    body.insert(
+        "htmlContent",
+        r#"<img src="cid:a.png"><img src="cid:b.png">"#,
+    );
+    server.post_admin_newsletters(&body).await;
+
+    let html_page = server.get_admin_newsletters().await.text().await.unwrap();
+    assert!(html_page.contains(
+        "The assets add up to 6 bytes, more than the 5 an email can carry"
+    ));
+}
+
 #[sqlx::test]
 async fn newsletters_are_not_delievered_to_unconfirmed_subscribers(
     pool: DbPool,
@@ -149,7 +483,246 @@ async fn post_newsletters_returns_400_for_invalid_data(pool: DbPool) {
     }
 }
 
-async fn create_unconfirmed_subscriber(server: &TestServer) -> Links {
+#[sqlx::test]
+async fn newsletters_are_only_delivered_to_the_target_lists(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_list(&server, "beta").await;
+    create_confirmed_subscriber(&server).await;
+    let beta_tester = create_confirmed_subscriber_on(&server, &["beta"]).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(1))
+        .await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+
+    let idempotency_key = Uuid::new_v4().to_string();
+    let mut body = body(&idempotency_key);
+    body.insert("lists", "beta");
+    let response = server.post_admin_newsletters(&body).await;
+    server.assert_is_redirect_to(&response, "/admin/newsletters");
+    server.dispatch_pending_emails().await;
+
+    let email_request = server
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let email: serde_json::Value = email_request.body_json().unwrap();
+    assert_eq!(email["To"], beta_tester.as_str());
+}
+
+#[sqlx::test]
+async fn members_of_several_target_lists_get_a_single_copy(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_list(&server, "beta").await;
+    create_confirmed_subscriber_on(&server, &["default", "beta"]).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(1))
+        .await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+
+    let idempotency_key = Uuid::new_v4().to_string();
+    let csrf_token = server.csrf_token().await;
+    let body = [
+        ("title", "Newsletter title"),
+        ("textContent", "Newsletter body as plain text"),
+        ("htmlContent", "<p>Newsletter body as HTML</p>"),
+        ("idempotencyKey", &idempotency_key),
+        ("lists", "default"),
+        ("lists", "beta"),
+        ("csrf_token", &csrf_token),
+    ];
+    let response = server
+        .http_client
+        .post(server.admin_newsletters())
+        .form(&body)
+        .send()
+        .await
+        .expect(FAILED_TO_EXECUTE_REQUEST);
+    server.assert_is_redirect_to(&response, "/admin/newsletters");
+    server.dispatch_pending_emails().await;
+}
+
+#[sqlx::test]
+async fn publishing_requires_a_target_list(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_confirmed_subscriber(&server).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(0))
+        .await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+
+    let idempotency_key = Uuid::new_v4().to_string();
+    let mut body = body(&idempotency_key);
+    body.remove("lists");
+    let response = server.post_admin_newsletters(&body).await;
+    server.assert_is_redirect_to(&response, "/admin/newsletters");
+    let html_page = server.get_admin_newsletters().await.text().await.unwrap();
+    assert!(html_page.contains("Please pick at least one list."));
+
+    body.insert("lists", "unknown");
+    let response = server.post_admin_newsletters(&body).await;
+    server.assert_is_redirect_to(&response, "/admin/newsletters");
+    let html_page = server.get_admin_newsletters().await.text().await.unwrap();
+    assert!(html_page.contains("There is no list called unknown"));
+    server.dispatch_pending_emails().await;
+}
+
+#[sqlx::test]
+async fn newsletters_are_only_delivered_to_the_matching_segment(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_confirmed_subscriber(&server).await;
+    let beta_tester =
+        create_confirmed_subscriber_on(&server, &["default"]).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(1))
+        .await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let tag = hashmap!("tag" => "beta", "emails" => beta_tester.as_str());
+    server.post_admin_tags("add", &tag).await;
+
+    let idempotency_key = Uuid::new_v4().to_string();
+    let mut body = body(&idempotency_key);
+    body.insert("segment", "tag = beta and subscribed after 2000-01-01");
+    let response = server.post_admin_newsletters(&body).await;
+    server.assert_is_redirect_to(&response, "/admin/newsletters");
+    server.dispatch_pending_emails().await;
+
+    let email_request = server
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let email: serde_json::Value = email_request.body_json().unwrap();
+    assert_eq!(email["To"], beta_tester.as_str());
+}
+
+#[sqlx::test]
+async fn invalid_segments_are_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_confirmed_subscriber(&server).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(0))
+        .await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+
+    let idempotency_key = Uuid::new_v4().to_string();
+    let mut body = body(&idempotency_key);
+    body.insert("segment", "tag = beta or");
+    let response = server.post_admin_newsletters(&body).await;
+    server.assert_is_redirect_to(&response, "/admin/newsletters");
+    let html_page = server.get_admin_newsletters().await.text().await.unwrap();
+    assert!(html_page.contains(
+        "The segment is invalid: Expected tag, list, domain or subscribed, \
+        found the end of the segment at character 14"
+    ));
+    server.dispatch_pending_emails().await;
+}
+
+#[sqlx::test]
+async fn the_recipient_count_can_be_previewed(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_confirmed_subscriber(&server).await;
+    let beta_tester =
+        create_confirmed_subscriber_on(&server, &["default"]).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(0))
+        .await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let tag = hashmap!("tag" => "beta", "emails" => beta_tester.as_str());
+    server.post_admin_tags("add", &tag).await;
+
+    let idempotency_key = Uuid::new_v4().to_string();
+    let mut body = body(&idempotency_key);
+    let response = server.post_admin_newsletters_preview(&body).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let html_page = response.text().await.unwrap();
+    assert!(html_page.contains("This issue would reach 2 subscribers."));
+    assert!(html_page.contains(r#"value="Newsletter title""#));
+    assert!(html_page.contains(&idempotency_key));
+
+    body.insert("segment", "tag != beta");
+    let html_page = server
+        .post_admin_newsletters_preview(&body)
+        .await
+        .text()
+        .await
+        .unwrap();
+    assert!(html_page.contains("This issue would reach 1 subscribers."));
+    assert!(html_page.contains(r#"value="tag != beta""#));
+
+    // Nothing gets published by a preview.
+    let issues =
+        sqlx::query!("select newsletter_issue_id from newsletter_issues")
+            .fetch_all(&server.db_pool)
+            .await
+            .unwrap();
+    assert!(issues.is_empty());
+}
+
+pub async fn create_list(server: &TestServer, slug: &str) {
+    sqlx::query!(
+        r#"
+        insert into lists (list_id, slug, name, created_at)
+        values ($1, $2, $2, now());
+        "#,
+        Uuid::new_v4(),
+        slug,
+    )
+    .execute(&server.db_pool)
+    .await
+    .unwrap();
+}
+
+/// Subscribes a new reader to every list, confirming the first
+/// subscription. Returns their email.
+pub async fn create_confirmed_subscriber_on(
+    server: &TestServer,
+    lists: &[&str],
+) -> String {
+    use fake::faker::internet::en::SafeEmail;
+    let name = Sentence(2..3).fake::<String>();
+    let email = SafeEmail().fake::<String>();
+    for (i, list) in lists.iter().enumerate() {
+        let _mock_guard = Mock::given(path("/email"))
+            .and(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(if i == 0 { 1 } else { 0 })
+            .mount_as_scoped(&server.email_server)
+            .await;
+        let body = hashmap![
+            "name" => name.as_str(),
+            "email" => email.as_str(),
+            "list" => *list
+        ];
+        server
+            .post_subscriptions(&body)
+            .await
+            .error_for_status()
+            .unwrap();
+        if i == 0 {
+            let email_request = server
+                .email_server
+                .received_requests()
+                .await
+                .unwrap()
+                .pop()
+                .unwrap();
+            let links = server.extract_links(&email_request);
+            reqwest::get(links.html)
+                .await
+                .unwrap()
+                .error_for_status()
+                .unwrap();
+        }
+    }
+    email
+}
+
+pub async fn create_unconfirmed_subscriber(server: &TestServer) -> Links {
     use fake::faker::internet::en::SafeEmail;
     let _mock_guard = Mock::given(path("/email"))
         .and(method("POST"))
@@ -177,7 +750,7 @@ async fn create_unconfirmed_subscriber(server: &TestServer) -> Links {
     server.extract_links(&email_request)
 }
 
-async fn create_confirmed_subscriber(server: &TestServer) {
+pub async fn create_confirmed_subscriber(server: &TestServer) {
     let links = create_unconfirmed_subscriber(server).await;
     reqwest::get(links.html)
         .await
@@ -186,11 +759,12 @@ async fn create_confirmed_subscriber(server: &TestServer) {
         .unwrap();
 }
 
-fn body(idempotency_key: &str) -> HashMap<&str, &str> {
+pub fn body(idempotency_key: &str) -> HashMap<&str, &str> {
     hashmap!(
         "title" => "Newsletter title",
         "textContent" => "Newsletter body as plain text",
         "htmlContent" => "<p>Newsletter body as HTML</p>",
-        "idempotencyKey" => idempotency_key
+        "idempotencyKey" => idempotency_key,
+        "lists" => "default"
     )
 }
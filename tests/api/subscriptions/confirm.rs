@@ -62,3 +62,51 @@ async fn get_returns_400_if_confirmation_token_is_absent(pool: DbPool) {
     let response = server.get_subscriptions_confirm().await;
     assert_eq!(response.status().as_u16(), 400);
 }
+
+#[sqlx::test]
+async fn the_confirmation_page_is_in_the_subscribers_language(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), None)
+        .await;
+    let body = hashmap![
+        "name" => "Jeanne",
+        "email" => "jeanne@example.com",
+        "locale" => "fr"
+    ];
+    server.post_subscriptions(&body).await;
+
+    let email_request = server
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let mut link = server.extract_links(&email_request).html;
+    link.set_port(Some(server.port)).unwrap();
+    let response = reqwest::get(link).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    let html_page = response.text().await.unwrap();
+    assert!(html_page.contains(r#"<html lang="fr">"#));
+    assert!(html_page.contains("Merci, votre abonnement est confirmé."));
+}
+
+#[sqlx::test]
+async fn unknown_tokens_are_rejected_in_the_requested_language(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let response = server
+        .http_client
+        .get(server.subscriptions_confirm())
+        .query(&[("subscription_token", "unknown")])
+        .header("Accept-Language", "fr")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+    assert!(response
+        .text()
+        .await
+        .unwrap()
+        .contains("Ce lien n’est pas valide."));
+}
@@ -0,0 +1,267 @@
+use super::preferences::confirmed_subscriber_token;
+use crate::{
+    newsletter::{create_confirmed_subscriber_on, create_list},
+    TestServer,
+};
+use hashmap_macro::hashmap;
+use wiremock::ResponseTemplate;
+use zero2prod::DbPool;
+
+#[sqlx::test]
+async fn unknown_tokens_are_rejected_with_a_401(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let body = hashmap!(
+        "subscription_token" => "unknown",
+        "email" => "new@example.com"
+    );
+    let response = server.post_subscriptions_email_change(&body).await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let response = reqwest::get(format!(
+        "{}/subscriptions/email_change/confirm?change_token=unknown",
+        server.addr()
+    ))
+    .await
+    .unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[sqlx::test]
+async fn the_email_changes_only_once_the_new_address_is_confirmed(
+    pool: DbPool,
+) {
+    let server = TestServer::run(pool).await;
+    create_list(&server, "beta").await;
+    let token = confirmed_subscriber_token(&server).await;
+    sqlx::query!(
+        r#"
+        insert into list_memberships (list_id, subscriber_id, joined_at)
+        select l.list_id, s.id, now()
+        from lists l cross join subscriptions s
+        where l.slug = 'beta';
+        "#
+    )
+    .execute(&server.db_pool)
+    .await
+    .unwrap();
+    let old_email = sqlx::query!("select email from subscriptions")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap()
+        .email;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(1))
+        .await;
+
+    let body = hashmap!(
+        "subscription_token" => token.as_str(),
+        "email" => "new@example.com"
+    );
+    let response = server.post_subscriptions_email_change(&body).await;
+    let url = format!("/subscriptions/preferences?subscription_token={token}");
+    server.assert_is_redirect_to(&response, &url);
+    let html_page = server
+        .get_subscriptions_preferences(&token)
+        .await
+        .text()
+        .await
+        .unwrap();
+    assert!(
+        html_page.contains("We sent a confirmation link to new@example.com.")
+    );
+
+    let email_request = server
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let email: serde_json::Value = email_request.body_json().unwrap();
+    assert_eq!(email["To"], "new@example.com");
+    let saved = sqlx::query!("select email from subscriptions")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(saved.email, old_email);
+
+    let link = server.extract_links(&email_request).html;
+    let response = reqwest::get(link.clone()).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(response
+        .text()
+        .await
+        .unwrap()
+        .contains("The newsletter will now be sent to new@example.com."));
+    let saved = sqlx::query!("select id, email, status from subscriptions")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(saved.email, "new@example.com");
+    assert_eq!(saved.status, "confirmed");
+    let memberships = sqlx::query!(
+        "select list_id from list_memberships where subscriber_id = $1",
+        saved.id
+    )
+    .fetch_all(&server.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(memberships.len(), 2);
+    let history = sqlx::query!(
+        "select old_email, new_email, confirmed_at from email_changes"
+    )
+    .fetch_one(&server.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(history.old_email, old_email);
+    assert_eq!(history.new_email, "new@example.com");
+    assert!(history.confirmed_at.is_some());
+
+    // A change can only be confirmed once.
+    let response = reqwest::get(link).await.unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[sqlx::test]
+async fn invalid_or_taken_addresses_are_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let token = confirmed_subscriber_token(&server).await;
+    let taken = create_confirmed_subscriber_on(&server, &["default"]).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(0))
+        .await;
+    let taken = taken.to_uppercase();
+    let taken_message = format!("{taken} is already subscribed");
+    let cases = [
+        ("not-an-email", "not-an-email is not a valid email"),
+        (taken.as_str(), taken_message.as_str()),
+    ];
+    for (email, message) in cases {
+        let body = hashmap!(
+            "subscription_token" => token.as_str(),
+            "email" => email
+        );
+        server.post_subscriptions_email_change(&body).await;
+        let html_page = server
+            .get_subscriptions_preferences(&token)
+            .await
+            .text()
+            .await
+            .unwrap();
+        assert!(html_page.contains(message), "{message}");
+    }
+    let changes = sqlx::query!("select new_email from email_changes")
+        .fetch_all(&server.db_pool)
+        .await
+        .unwrap();
+    assert!(changes.is_empty());
+}
+
+#[sqlx::test]
+async fn addresses_taken_before_the_confirmation_are_reported(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let token = confirmed_subscriber_token(&server).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(1))
+        .await;
+    let body = hashmap!(
+        "subscription_token" => token.as_str(),
+        "email" => "new@example.com"
+    );
+    server.post_subscriptions_email_change(&body).await;
+    let email_request = server
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    sqlx::query!(
+        r#"
+        insert into subscriptions (id, email, name, subscribed_at, status)
+        values (gen_random_uuid(), 'NEW@example.com', 'Eve', now(), 'confirmed')
+        "#
+    )
+    .execute(&server.db_pool)
+    .await
+    .unwrap();
+
+    let link = server.extract_links(&email_request).html;
+    let response = server
+        .http_client
+        .get(link)
+        .header("Accept-Language", "fr")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 409);
+    let html_page = response.text().await.unwrap();
+    assert!(html_page.contains("<title>Adresse e-mail inchangée</title>"));
+    let changes = sqlx::query!("select confirmed_at from email_changes")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap();
+    assert!(changes.confirmed_at.is_none());
+}
+
+async fn request_change(
+    server: &TestServer,
+    token: &str,
+    email: &str,
+) -> String {
+    let body = hashmap!("subscription_token" => token, "email" => email);
+    server.post_subscriptions_email_change(&body).await;
+    let email_request = server
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    server.extract_links(&email_request).html.to_string()
+}
+
+#[sqlx::test]
+async fn expired_change_links_are_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let token = confirmed_subscriber_token(&server).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(1))
+        .await;
+    let link = request_change(&server, &token, "new@example.com").await;
+    sqlx::query!(
+        "update email_changes set requested_at = now() - interval '25 hours'"
+    )
+    .execute(&server.db_pool)
+    .await
+    .unwrap();
+
+    let response = reqwest::get(link).await.unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+    let saved = sqlx::query!("select email from subscriptions")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap();
+    assert_ne!(saved.email, "new@example.com");
+}
+
+#[sqlx::test]
+async fn confirming_a_change_voids_the_other_pending_ones(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let token = confirmed_subscriber_token(&server).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(2))
+        .await;
+    let older = request_change(&server, &token, "older@example.com").await;
+    let newer = request_change(&server, &token, "newer@example.com").await;
+
+    let response = reqwest::get(newer).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    let response = reqwest::get(older).await.unwrap();
+    assert_eq!(response.status().as_u16(), 401);
+    let saved = sqlx::query!("select email from subscriptions")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(saved.email, "newer@example.com");
+}
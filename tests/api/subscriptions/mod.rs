@@ -1,9 +1,15 @@
 mod confirm;
+mod email_change;
+mod preferences;
+mod unsubscribe;
 
-use crate::TestServer;
+use crate::{
+    newsletter::{create_confirmed_subscriber_on, create_list},
+    TestServer,
+};
 use hashmap_macro::hashmap;
 use wiremock::ResponseTemplate;
-use zero2prod::DbPool;
+use zero2prod::{configuration::OptInMode, DbPool};
 
 #[sqlx::test]
 async fn post_returns_200_for_valid_data(pool: DbPool) {
@@ -68,6 +74,98 @@ async fn post_sends_an_email_with_confirmation_link(pool: DbPool) {
     assert_eq!(links.text, links.html);
 }
 
+#[sqlx::test]
+async fn confirmation_emails_use_the_configured_subject(pool: DbPool) {
+    let server = TestServer::run_with_config(pool, |c| {
+        c.email_templates.subjects.confirmation =
+            "{{ name }}, please confirm".into();
+    })
+    .await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(1))
+        .await;
+    let body = hashmap!["name" => "John Doe", "email" => "example@gmail.com"];
+    server.post_subscriptions(&body).await;
+
+    let email_request = server
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let email: serde_json::Value = email_request.body_json().unwrap();
+    assert_eq!(email["Subject"], "John Doe, please confirm");
+    assert!(email["TextBody"]
+        .as_str()
+        .unwrap()
+        .starts_with("Welcome to the newsletter, John Doe."));
+}
+
+#[sqlx::test]
+async fn subscribers_are_written_to_in_the_language_they_chose(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(1))
+        .await;
+    let body = hashmap![
+        "name" => "Jeanne",
+        "email" => "jeanne@example.com",
+        "locale" => "fr-CA"
+    ];
+    server.post_subscriptions(&body).await;
+
+    let saved = sqlx::query!("select locale from subscriptions")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(saved.locale, "fr");
+    let email_request = server
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let email: serde_json::Value = email_request.body_json().unwrap();
+    assert_eq!(email["Subject"], "Veuillez confirmer votre abonnement");
+    assert!(email["TextBody"]
+        .as_str()
+        .unwrap()
+        .starts_with("Bienvenue dans la newsletter, Jeanne."));
+}
+
+#[sqlx::test]
+async fn the_language_is_inferred_from_accept_language(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(2))
+        .await;
+    for (email, accept_language) in [
+        ("jeanne@example.com", "fr-FR, fr;q=0.9, en;q=0.8"),
+        ("hans@example.com", "de-DE"),
+    ] {
+        server
+            .http_client
+            .post(server.subscriptions())
+            .header("Accept-Language", accept_language)
+            .form(&hashmap!["name" => "Reader", "email" => email])
+            .send()
+            .await
+            .unwrap();
+    }
+
+    let saved =
+        sqlx::query!("select email, locale from subscriptions order by email")
+            .fetch_all(&server.db_pool)
+            .await
+            .unwrap();
+    assert_eq!(saved[0].email, "hans@example.com");
+    assert_eq!(saved[0].locale, "en");
+    assert_eq!(saved[1].email, "jeanne@example.com");
+    assert_eq!(saved[1].locale, "fr");
+}
+
 #[sqlx::test]
 async fn post_returns_400_when_data_is_missing(pool: DbPool) {
     let server = TestServer::run(pool).await;
@@ -115,3 +213,181 @@ async fn post_returns_400_when_data_is_invalid(pool: DbPool) {
         );
     }
 }
+
+#[sqlx::test]
+async fn post_returns_400_for_an_unknown_list(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(0))
+        .await;
+    for list in ["unknown", "Not a list!"] {
+        let body = hashmap![
+            "name" => "John Doe",
+            "email" => "example@gmail.com",
+            "list" => list
+        ];
+        let response = server.post_subscriptions(&body).await;
+        assert_eq!(response.status().as_u16(), 400, "{list}");
+    }
+}
+
+#[sqlx::test]
+async fn confirmed_subscribers_join_another_list_without_confirming(
+    pool: DbPool,
+) {
+    let server = TestServer::run(pool).await;
+    create_list(&server, "beta").await;
+    let email = create_confirmed_subscriber_on(&server, &["default"]).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(0))
+        .await;
+
+    let body = hashmap![
+        "name" => "John Doe",
+        "email" => email.as_str(),
+        "list" => "beta"
+    ];
+    let response = server.post_subscriptions(&body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let lists = sqlx::query!(
+        r#"
+        select l.slug
+        from list_memberships m
+        join lists l using (list_id)
+        order by l.slug
+        "#
+    )
+    .fetch_all(&server.db_pool)
+    .await
+    .unwrap();
+    let lists: Vec<_> = lists.into_iter().map(|r| r.slug).collect();
+    assert_eq!(lists, vec!["beta", "default"]);
+}
+
+#[sqlx::test]
+async fn single_opt_in_subscribers_are_confirmed_and_welcomed(pool: DbPool) {
+    let server = TestServer::run_with_config(pool, |c| {
+        c.application.opt_in = OptInMode::Single;
+    })
+    .await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(1))
+        .await;
+
+    let body = hashmap!["name" => "John Doe", "email" => "example@gmail.com"];
+    let response = server.post_subscriptions(&body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let saved = sqlx::query!("select status from subscriptions")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(saved.status, "confirmed");
+    let email_request = server
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let email: serde_json::Value = email_request.body_json().unwrap();
+    assert_eq!(email["Subject"], "Welcome to the newsletter");
+    let link = server.extract_links(&email_request).html;
+    assert_eq!(link.path(), "/subscriptions/preferences");
+    let response = server.http_client.get(link).send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[sqlx::test]
+async fn lists_can_override_the_opt_in_mode(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_list(&server, "beta").await;
+    sqlx::query!("update lists set opt_in = 'single' where slug = 'beta'")
+        .execute(&server.db_pool)
+        .await
+        .unwrap();
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(2))
+        .await;
+
+    let body = hashmap![
+        "name" => "Beta Tester",
+        "email" => "beta@example.com",
+        "list" => "beta"
+    ];
+    server
+        .post_subscriptions(&body)
+        .await
+        .error_for_status()
+        .unwrap();
+    let body = hashmap!["name" => "John Doe", "email" => "john@example.com"];
+    server
+        .post_subscriptions(&body)
+        .await
+        .error_for_status()
+        .unwrap();
+
+    let saved =
+        sqlx::query!("select email, status from subscriptions order by email")
+            .fetch_all(&server.db_pool)
+            .await
+            .unwrap();
+    assert_eq!(saved[0].email, "beta@example.com");
+    assert_eq!(saved[0].status, "confirmed");
+    assert_eq!(saved[1].email, "john@example.com");
+    assert_eq!(saved[1].status, "pending_confirmation");
+}
+
+#[sqlx::test]
+async fn confirmed_subscribers_are_welcomed_to_single_opt_in_lists(
+    pool: DbPool,
+) {
+    let server = TestServer::run(pool).await;
+    let email = create_confirmed_subscriber_on(&server, &["default"]).await;
+    create_list(&server, "beta").await;
+    sqlx::query!("update lists set opt_in = 'single' where slug = 'beta'")
+        .execute(&server.db_pool)
+        .await
+        .unwrap();
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(1))
+        .await;
+
+    let body = hashmap![
+        "name" => "Beta Tester",
+        "email" => email.as_str(),
+        "list" => "beta"
+    ];
+    for _ in 0..2 {
+        let response = server.post_subscriptions(&body).await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+
+    let email_request = server
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let email: serde_json::Value = email_request.body_json().unwrap();
+    assert_eq!(email["Subject"], "Welcome to the newsletter");
+}
+
+#[sqlx::test]
+async fn subscribing_again_in_another_case_keeps_one_subscriber(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let email = create_confirmed_subscriber_on(&server, &["default"]).await;
+    let email = email.to_uppercase();
+    let body = hashmap!("name" => "Ursula", "email" => email.as_str());
+    let response = server.post_subscriptions(&body).await;
+    assert_eq!(response.status().as_u16(), 200);
+
+    let saved = sqlx::query!("select email, status from subscriptions")
+        .fetch_all(&server.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(saved.len(), 1);
+    assert_eq!(saved[0].status, "confirmed");
+}
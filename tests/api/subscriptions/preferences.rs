@@ -0,0 +1,191 @@
+use crate::{
+    newsletter::{create_list, create_unconfirmed_subscriber},
+    TestServer, TestUser,
+};
+use hashmap_macro::hashmap;
+use uuid::Uuid;
+use wiremock::ResponseTemplate;
+use zero2prod::DbPool;
+
+/// Subscribes and confirms a new reader, returning their token.
+pub async fn confirmed_subscriber_token(server: &TestServer) -> String {
+    let links = create_unconfirmed_subscriber(server).await;
+    reqwest::get(links.html.clone())
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+    links
+        .html
+        .query_pairs()
+        .find(|(key, _)| key == "subscription_token")
+        .unwrap()
+        .1
+        .into_owned()
+}
+
+#[sqlx::test]
+async fn unknown_tokens_are_rejected_with_a_401(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let response = server.get_subscriptions_preferences("unknown").await;
+    assert_eq!(response.status().as_u16(), 401);
+
+    let body = hashmap!("subscription_token" => "unknown", "name" => "Ursula");
+    let response = server.post_subscriptions_preferences(&body).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[sqlx::test]
+async fn the_preference_center_shows_the_current_preferences(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let token = confirmed_subscriber_token(&server).await;
+    let response = server.get_subscriptions_preferences(&token).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let html_page = response.text().await.unwrap();
+    assert!(html_page.contains(r#"value="default" checked"#));
+    assert!(html_page.contains(r#"name="plain_text_only" value="on">"#));
+    assert!(html_page.contains(r#"name="paused_until" value="""#));
+}
+
+#[sqlx::test]
+async fn subscribers_can_update_their_preferences(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_list(&server, "beta").await;
+    let token = confirmed_subscriber_token(&server).await;
+    let paused_until = (chrono::Utc::now().date_naive()
+        + chrono::Duration::days(30))
+    .to_string();
+
+    let body = [
+        ("subscription_token", token.as_str()),
+        ("name", "Ursula Le Guin"),
+        ("lists", "beta"),
+        ("plain_text_only", "on"),
+        ("paused_until", &paused_until),
+    ];
+    let response = server.post_subscriptions_preferences(&body).await;
+    let url = format!("/subscriptions/preferences?subscription_token={token}");
+    server.assert_is_redirect_to(&response, &url);
+    let html_page = server
+        .get_subscriptions_preferences(&token)
+        .await
+        .text()
+        .await
+        .unwrap();
+    assert!(html_page.contains("Your preferences have been saved."));
+    assert!(html_page.contains(r#"value="Ursula Le Guin""#));
+    assert!(html_page.contains(r#"value="beta" checked"#));
+    assert!(!html_page.contains(r#"value="default" checked"#));
+    assert!(html_page.contains(r#"value="on" checked"#));
+    assert!(html_page.contains(&format!(r#"value="{paused_until}""#)));
+
+    // Clearing the date resumes delivery.
+    let body = hashmap!(
+        "subscription_token" => token.as_str(),
+        "name" => "Ursula Le Guin",
+        "paused_until" => ""
+    );
+    server.post_subscriptions_preferences(&body).await;
+    let saved =
+        sqlx::query!("select plain_text_only, paused_until from subscriptions")
+            .fetch_one(&server.db_pool)
+            .await
+            .unwrap();
+    assert!(!saved.plain_text_only);
+    assert!(saved.paused_until.is_none());
+    let memberships = sqlx::query!("select list_id from list_memberships")
+        .fetch_all(&server.db_pool)
+        .await
+        .unwrap();
+    assert!(memberships.is_empty());
+}
+
+#[sqlx::test]
+async fn invalid_preferences_are_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let token = confirmed_subscriber_token(&server).await;
+    let cases = [
+        (
+            hashmap!("name" => "<Ursula>"),
+            "<Ursula> is not a valid username",
+        ),
+        (
+            hashmap!("name" => "Ursula", "paused_until" => "2000-01-01"),
+            "A pause must end after today.",
+        ),
+        (
+            hashmap!("name" => "Ursula", "lists" => "unknown"),
+            "There is no list called unknown",
+        ),
+    ];
+    for (mut body, message) in cases {
+        body.insert("subscription_token", token.as_str());
+        server.post_subscriptions_preferences(&body).await;
+        let html_page = server
+            .get_subscriptions_preferences(&token)
+            .await
+            .text()
+            .await
+            .unwrap();
+        assert!(
+            html_page.contains(&htmlescape::encode_minimal(message)),
+            "{message}"
+        );
+        assert!(!html_page.contains("Your preferences have been saved."));
+    }
+    let saved = sqlx::query!("select name from subscriptions")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap();
+    assert_ne!(saved.name, "Ursula");
+}
+
+#[sqlx::test]
+async fn deliveries_honour_pauses_and_plain_text_only(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let plain_text = confirmed_subscriber_token(&server).await;
+    let paused = confirmed_subscriber_token(&server).await;
+    let tomorrow = (chrono::Utc::now().date_naive()
+        + chrono::Duration::days(1))
+    .to_string();
+    let body = [
+        ("subscription_token", plain_text.as_str()),
+        ("name", "Plain"),
+        ("lists", "default"),
+        ("plain_text_only", "on"),
+    ];
+    server.post_subscriptions_preferences(&body).await;
+    let body = [
+        ("subscription_token", paused.as_str()),
+        ("name", "Paused"),
+        ("lists", "default"),
+        ("paused_until", &tomorrow),
+    ];
+    server.post_subscriptions_preferences(&body).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(1))
+        .await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+
+    let idempotency_key = Uuid::new_v4().to_string();
+    let body = hashmap!(
+        "title" => "Newsletter title",
+        "textContent" => "Newsletter body as plain text",
+        "htmlContent" => "<p>Newsletter body as HTML</p>",
+        "idempotencyKey" => idempotency_key.as_str(),
+        "lists" => "default"
+    );
+    server.post_admin_newsletters(&body).await;
+    server.dispatch_pending_emails().await;
+
+    let email_request = server
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let email: serde_json::Value = email_request.body_json().unwrap();
+    assert_eq!(email["TextBody"], "Newsletter body as plain text");
+    assert!(email.get("HtmlBody").is_none());
+}
@@ -0,0 +1,185 @@
+use super::preferences::confirmed_subscriber_token;
+use crate::{newsletter::create_confirmed_subscriber, TestServer, TestUser};
+use hashmap_macro::hashmap;
+use uuid::Uuid;
+use wiremock::ResponseTemplate;
+use zero2prod::DbPool;
+
+/// Issues an unsubscribe token to the only subscriber, as sending them an
+/// issue would.
+async fn unsubscribe_token_of(server: &TestServer) -> String {
+    let token = Uuid::new_v4().to_string();
+    sqlx::query!(
+        r#"
+        insert into unsubscribe_tokens (unsubscribe_token, subscriber_id)
+        select $1, id from subscriptions;
+        "#,
+        token,
+    )
+    .execute(&server.db_pool)
+    .await
+    .unwrap();
+    token
+}
+
+async fn unsubscribe_token(server: &TestServer) -> String {
+    confirmed_subscriber_token(server).await;
+    unsubscribe_token_of(server).await
+}
+
+#[sqlx::test]
+async fn unknown_tokens_are_rejected_with_a_401(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let response = server.get_subscriptions_unsubscribe("unknown").await;
+    assert_eq!(response.status().as_u16(), 401);
+    let response = server.post_subscriptions_unsubscribe("unknown").await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[sqlx::test]
+async fn following_the_link_only_asks_for_confirmation(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let token = unsubscribe_token(&server).await;
+    let response = server.get_subscriptions_unsubscribe(&token).await;
+    assert_eq!(response.status().as_u16(), 200);
+    let html_page = response.text().await.unwrap();
+    assert!(html_page.contains(r#"<button type="submit">Unsubscribe</button>"#));
+
+    let saved = sqlx::query!("select status from subscriptions")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(saved.status, "confirmed");
+}
+
+#[sqlx::test]
+async fn the_pages_are_in_the_subscribers_language(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let token = unsubscribe_token(&server).await;
+    sqlx::query!("update subscriptions set locale = 'fr'")
+        .execute(&server.db_pool)
+        .await
+        .unwrap();
+
+    let response = server.get_subscriptions_unsubscribe(&token).await;
+    let html_page = response.text().await.unwrap();
+    assert!(html_page.contains("<title>Se désabonner</title>"));
+    assert!(html_page.contains("Ne plus envoyer la newsletter à"));
+    let response = server.post_subscriptions_unsubscribe(&token).await;
+    assert!(response
+        .text()
+        .await
+        .unwrap()
+        .contains("Vous êtes désabonné"));
+}
+
+#[sqlx::test]
+async fn unsubscribed_readers_stop_receiving_issues(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let token = unsubscribe_token(&server).await;
+    create_confirmed_subscriber(&server).await;
+    let response = server.post_subscriptions_unsubscribe(&token).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(response
+        .text()
+        .await
+        .unwrap()
+        .contains("You have been unsubscribed"));
+
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(1))
+        .await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let idempotency_key = Uuid::new_v4().to_string();
+    let body = hashmap!(
+        "title" => "Newsletter title",
+        "textContent" => "Newsletter body as plain text",
+        "htmlContent" => "<p>Newsletter body as HTML</p>",
+        "idempotencyKey" => idempotency_key.as_str(),
+        "lists" => "default"
+    );
+    server.post_admin_newsletters(&body).await;
+    server.dispatch_pending_emails().await;
+}
+
+#[sqlx::test]
+async fn unsubscribed_readers_can_subscribe_again(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let token = confirmed_subscriber_token(&server).await;
+    server
+        .post_subscriptions_unsubscribe(&unsubscribe_token_of(&server).await)
+        .await;
+    let saved = sqlx::query!("select name, email, status from subscriptions")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap();
+    let response = server
+        .http_client
+        .get(server.subscriptions_confirm())
+        .query(&[("subscription_token", &token)])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    let status = sqlx::query!("select status from subscriptions")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap()
+        .status;
+    assert_eq!(status, "unsubscribed");
+
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(1))
+        .await;
+    let body = hashmap!("name" => saved.name.as_str(), "email" => saved.email.as_str());
+    server
+        .post_subscriptions(&body)
+        .await
+        .error_for_status()
+        .unwrap();
+    let email_request = server
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    reqwest::get(server.extract_links(&email_request).html)
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+    let status = sqlx::query!("select status from subscriptions")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap()
+        .status;
+    assert_eq!(status, "confirmed");
+}
+
+#[sqlx::test]
+async fn unsubscribe_tokens_are_good_for_nothing_else(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let token = unsubscribe_token(&server).await;
+
+    let response = server.get_subscriptions_preferences(&token).await;
+    assert_eq!(response.status().as_u16(), 401);
+    let body =
+        hashmap!("subscription_token" => token.as_str(), "name" => "Eve");
+    let response = server.post_subscriptions_preferences(&body).await;
+    assert_eq!(response.status().as_u16(), 401);
+    let body = hashmap!(
+        "subscription_token" => token.as_str(),
+        "email" => "eve@example.com"
+    );
+    let response = server.post_subscriptions_email_change(&body).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[sqlx::test]
+async fn subscription_tokens_can_not_unsubscribe(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let token = confirmed_subscriber_token(&server).await;
+    let response = server.post_subscriptions_unsubscribe(&token).await;
+    assert_eq!(response.status().as_u16(), 401);
+}
@@ -0,0 +1,174 @@
+use crate::{
+    newsletter::{body, create_confirmed_subscriber},
+    TestServer, TestUser, FAILED_TO_EXECUTE_REQUEST,
+};
+use reqwest::header::LOCATION;
+use uuid::Uuid;
+use wiremock::ResponseTemplate;
+use zero2prod::DbPool;
+
+const HTML_CONTENT: &str = r#"<p>Read <a href="https://example.com/post?a=1&amp;b=2">the post</a>
+    or <a href="https://example.com/post?a=1&amp;b=2">here</a>,
+    then <a href="{{ unsubscribe_url }}">leave</a>.</p>"#;
+
+/// Publishes an issue to a confirmed subscriber and returns its HTML body.
+async fn send_issue(server: &TestServer, tracking: bool) -> String {
+    create_confirmed_subscriber(server).await;
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(1))
+        .await;
+    TestUser::stored(&server.db_pool).await.login(server).await;
+    let idempotency_key = Uuid::new_v4().to_string();
+    let mut body = body(&idempotency_key);
+    body.insert("htmlContent", HTML_CONTENT);
+    if tracking {
+        body.insert("tracking", "true");
+    }
+    let response = server.post_admin_newsletters(&body).await;
+    server.assert_is_redirect_to(&response, "/admin/newsletters");
+    server.dispatch_pending_emails().await;
+    let email_request = server
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let email: serde_json::Value = email_request.body_json().unwrap();
+    email["HtmlBody"].as_str().unwrap().to_owned()
+}
+
+/// The tracking URLs of `html` with the given prefix, e.g. `/t/c/`.
+fn tracking_urls(server: &TestServer, html: &str, prefix: &str) -> Vec<String> {
+    let prefix = format!("{}{prefix}", server.base_url);
+    html.split('"')
+        .filter(|s| s.starts_with(&prefix))
+        .map(|s| s.replacen(&server.base_url, &server.addr(), 1))
+        .collect()
+}
+
+async fn issue_id(server: &TestServer) -> Uuid {
+    sqlx::query!("select newsletter_issue_id from newsletter_issues")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap()
+        .newsletter_issue_id
+}
+
+#[sqlx::test]
+async fn links_and_an_open_pixel_are_tracked(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let html = send_issue(&server, true).await;
+
+    let clicks = tracking_urls(&server, &html, "/t/c/");
+    assert_eq!(clicks.len(), 2);
+    assert_eq!(clicks[0], clicks[1]);
+    assert!(html.contains("/subscriptions/unsubscribe?unsubscribe_token="));
+    let opens = tracking_urls(&server, &html, "/t/o/");
+    assert_eq!(opens.len(), 1);
+
+    let response = server.http_client.get(&clicks[0]).send().await.unwrap();
+    assert_eq!(response.status().as_u16(), 302);
+    assert_eq!(
+        response.headers()[LOCATION],
+        "https://example.com/post?a=1&b=2"
+    );
+    server.http_client.get(&clicks[0]).send().await.unwrap();
+    let response = reqwest::get(&opens[0]).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()["Content-Type"], "image/gif");
+
+    let html_page = server.get_admin_stats("").await.text().await.unwrap();
+    let issue_id = issue_id(&server).await;
+    assert!(html_page.contains(&format!(
+        r#"<a href="/admin/stats/{issue_id}">Newsletter title</a>"#
+    )));
+    let html_page = server
+        .get_admin_stats(&format!("/{issue_id}"))
+        .await
+        .text()
+        .await
+        .unwrap();
+    assert!(html_page.contains("<li>Recipients: 1</li>"));
+    assert!(html_page.contains("<li>Opens: 1, by 1 subscribers</li>"));
+    assert!(html_page.contains("<li>Clicks: 2, by 1 subscribers</li>"));
+    assert!(html_page.contains("<td>https://example.com/post?a=1&amp;b=2</td>"));
+}
+
+#[sqlx::test]
+async fn issues_are_not_tracked_unless_asked_to(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let html = send_issue(&server, false).await;
+
+    assert!(html.contains(r#"<a href="https://example.com/post?a=1&amp;b=2">"#));
+    assert!(tracking_urls(&server, &html, "/t/").is_empty());
+    let html_page = server.get_admin_stats("").await.text().await.unwrap();
+    assert!(!html_page.contains("Newsletter title"));
+    let html_page = server
+        .get_admin_stats(&format!("/{}", issue_id(&server).await))
+        .await
+        .text()
+        .await
+        .unwrap();
+    assert!(html_page.contains("This issue was published without tracking."));
+}
+
+#[sqlx::test]
+async fn unknown_tracking_tokens_are_not_recorded(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let response = server
+        .http_client
+        .get(format!("{}/t/c/unknown", server.addr()))
+        .send()
+        .await
+        .expect(FAILED_TO_EXECUTE_REQUEST);
+    assert_eq!(response.status().as_u16(), 404);
+    let response = reqwest::get(format!("{}/t/o/unknown", server.addr()))
+        .await
+        .expect(FAILED_TO_EXECUTE_REQUEST);
+    assert_eq!(response.status().as_u16(), 200);
+    let events = sqlx::query!("select token from tracking_events")
+        .fetch_all(&server.db_pool)
+        .await
+        .unwrap();
+    assert!(events.is_empty());
+}
+
+#[sqlx::test]
+async fn opens_and_clicks_are_part_of_the_data_subject_export(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let html = send_issue(&server, true).await;
+    reqwest::get(&tracking_urls(&server, &html, "/t/o/")[0])
+        .await
+        .unwrap();
+    let email = sqlx::query!("select email from subscriptions")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap()
+        .email;
+
+    let response = server.post_admin_gdpr("export", &email).await;
+    let data: serde_json::Value = response.json().await.unwrap();
+    let events = data["tracking_events"].as_array().unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["url"], serde_json::Value::Null);
+
+    server.post_admin_gdpr("erase", &email).await;
+    let tokens = sqlx::query!("select token from tracking_tokens")
+        .fetch_all(&server.db_pool)
+        .await
+        .unwrap();
+    assert!(tokens.is_empty());
+}
+
+#[sqlx::test]
+async fn stats_are_for_logged_in_admins(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let response = server.get_admin_stats("").await;
+    server.assert_is_redirect_to(&response, "/login");
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let response = server
+        .get_admin_stats(&format!("/{}", Uuid::new_v4()))
+        .await;
+    assert_eq!(response.status().as_u16(), 404);
+}
@@ -0,0 +1,323 @@
+use crate::{
+    newsletter::{
+        body, create_confirmed_subscriber_on, create_unconfirmed_subscriber,
+    },
+    TestServer, TestUser,
+};
+use hashmap_macro::hashmap;
+use serde_json::{json, Value};
+use uuid::Uuid;
+use wiremock::{matchers::path, Mock, ResponseTemplate};
+use zero2prod::{configuration::OptInMode, DbPool};
+
+const CREDENTIALS: Option<(&str, &str)> = Some(("postmark", "webhook-secret"));
+
+fn bounce(email: &str, kind: &str) -> Value {
+    json!({
+        "RecordType": "Bounce",
+        "ID": 4323372036854775807u64,
+        "Type": kind,
+        "TypeCode": 1,
+        "MessageID": Uuid::new_v4(),
+        "Email": email,
+        "BouncedAt": "2026-10-19T16:09:19Z",
+        "Inactive": kind == "HardBounce",
+    })
+}
+
+async fn subscriber_status(server: &TestServer, email: &str) -> String {
+    sqlx::query!("select status from subscriptions where email = $1", email)
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap()
+        .status
+}
+
+#[sqlx::test]
+async fn hard_bounces_stop_issues_from_being_sent(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let email = create_confirmed_subscriber_on(&server, &["default"]).await;
+
+    let response = server
+        .post_postmark_webhook(
+            CREDENTIALS,
+            &bounce(&email.to_uppercase(), "HardBounce"),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(subscriber_status(&server, &email).await, "bounced");
+
+    server
+        .mock_email_server(ResponseTemplate::new(200), Some(0))
+        .await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let idempotency_key = Uuid::new_v4().to_string();
+    server.post_admin_newsletters(&body(&idempotency_key)).await;
+    server.dispatch_pending_emails().await;
+}
+
+#[sqlx::test]
+async fn spam_complaints_are_recorded(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let email = create_confirmed_subscriber_on(&server, &["default"]).await;
+    let complaint = json!({
+        "RecordType": "SpamComplaint",
+        "Type": "SpamComplaint",
+        "TypeCode": 512,
+        "MessageID": Uuid::new_v4(),
+        "Email": email,
+        "BouncedAt": "2026-10-19T16:09:19Z",
+        "Inactive": true,
+    });
+    let response = server.post_postmark_webhook(CREDENTIALS, &complaint).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(subscriber_status(&server, &email).await, "complained");
+}
+
+#[sqlx::test]
+async fn queued_deliveries_are_dropped_on_a_bounce(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let email = create_confirmed_subscriber_on(&server, &["default"]).await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let idempotency_key = Uuid::new_v4().to_string();
+    server.post_admin_newsletters(&body(&idempotency_key)).await;
+
+    server
+        .post_postmark_webhook(CREDENTIALS, &bounce(&email, "HardBounce"))
+        .await;
+    let queued =
+        sqlx::query!("select subscriber_email from issue_delivery_queue")
+            .fetch_all(&server.db_pool)
+            .await
+            .unwrap();
+    assert!(queued.is_empty());
+}
+
+#[sqlx::test]
+async fn soft_bounces_and_other_events_are_ignored(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let email = create_confirmed_subscriber_on(&server, &["default"]).await;
+    let delivery = json!({
+        "RecordType": "Delivery",
+        "MessageID": Uuid::new_v4(),
+        "Recipient": email,
+        "DeliveredAt": "2026-10-19T16:09:19Z",
+    });
+    for event in [bounce(&email, "SoftBounce"), delivery] {
+        let response = server.post_postmark_webhook(CREDENTIALS, &event).await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+    assert_eq!(subscriber_status(&server, &email).await, "confirmed");
+}
+
+#[sqlx::test]
+async fn events_about_unknown_addresses_are_accepted(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let response = server
+        .post_postmark_webhook(
+            CREDENTIALS,
+            &bounce("nobody@example.com", "HardBounce"),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[sqlx::test]
+async fn webhooks_require_the_configured_credentials(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let email = create_confirmed_subscriber_on(&server, &["default"]).await;
+    for credentials in [
+        None,
+        Some(("postmark", "wrong")),
+        Some(("other", "webhook-secret")),
+    ] {
+        let response = server
+            .post_postmark_webhook(credentials, &bounce(&email, "HardBounce"))
+            .await;
+        assert_eq!(response.status().as_u16(), 401);
+        assert_eq!(
+            response.headers()["WWW-Authenticate"],
+            r#"Basic realm="webhooks""#
+        );
+    }
+    assert_eq!(subscriber_status(&server, &email).await, "confirmed");
+}
+
+#[sqlx::test]
+async fn malformed_events_are_rejected(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let response = server
+        .post_postmark_webhook(
+            CREDENTIALS,
+            &json!({ "Email": "a@example.com" }),
+        )
+        .await;
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+struct Delivery {
+    status: String,
+    message_id: Option<String>,
+    detail: Option<String>,
+}
+
+async fn delivery(server: &TestServer) -> Delivery {
+    sqlx::query_as!(
+        Delivery,
+        "select status, message_id, detail from deliveries"
+    )
+    .fetch_one(&server.db_pool)
+    .await
+    .unwrap()
+}
+
+/// Sends an issue to a new subscriber, the provider answering with
+/// `message_id`.
+async fn send_issue(server: &TestServer, message_id: &str) -> String {
+    let email = create_confirmed_subscriber_on(server, &["default"]).await;
+    server
+        .mock_email_server(
+            ResponseTemplate::new(200).set_body_json(json!({
+                "To": email,
+                "SubmittedAt": "2026-10-19T16:09:19Z",
+                "MessageID": message_id,
+                "ErrorCode": 0,
+                "Message": "OK",
+            })),
+            Some(1),
+        )
+        .await;
+    TestUser::stored(&server.db_pool).await.login(server).await;
+    let idempotency_key = Uuid::new_v4().to_string();
+    server.post_admin_newsletters(&body(&idempotency_key)).await;
+    server.dispatch_pending_emails().await;
+    email
+}
+
+#[sqlx::test]
+async fn sent_issues_are_logged_with_the_provider_message_id(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let message_id = Uuid::new_v4().to_string();
+    send_issue(&server, &message_id).await;
+
+    let delivery = delivery(&server).await;
+    assert_eq!(delivery.status, "sent");
+    assert_eq!(delivery.message_id.as_deref(), Some(message_id.as_str()));
+    assert_eq!(delivery.detail, None);
+}
+
+#[sqlx::test]
+async fn failed_sends_are_logged(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    create_confirmed_subscriber_on(&server, &["default"]).await;
+    server
+        .mock_email_server(
+            ResponseTemplate::new(422).set_body_json(json!({
+                "ErrorCode": 300,
+                "Message": "Invalid 'To' address",
+            })),
+            None,
+        )
+        .await;
+    TestUser::stored(&server.db_pool).await.login(&server).await;
+    let idempotency_key = Uuid::new_v4().to_string();
+    server.post_admin_newsletters(&body(&idempotency_key)).await;
+    server.dispatch_pending_emails().await;
+
+    let delivery = delivery(&server).await;
+    assert_eq!(delivery.status, "failed");
+    assert_eq!(delivery.message_id, None);
+    assert!(delivery.detail.unwrap().contains("Invalid 'To' address"));
+}
+
+#[sqlx::test]
+async fn delivery_events_update_the_logged_delivery(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let message_id = Uuid::new_v4().to_string();
+    let email = send_issue(&server, &message_id).await;
+
+    let delivered = json!({
+        "RecordType": "Delivery",
+        "MessageID": message_id,
+        "Recipient": email,
+        "DeliveredAt": "2026-10-19T16:09:19Z",
+    });
+    let response = server.post_postmark_webhook(CREDENTIALS, &delivered).await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(delivery(&server).await.status, "delivered");
+}
+
+#[sqlx::test]
+async fn bounces_are_not_overridden_by_later_deliveries(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let message_id = Uuid::new_v4().to_string();
+    let email = send_issue(&server, &message_id).await;
+
+    let mut bounced = bounce(&email, "HardBounce");
+    bounced["MessageID"] = json!(message_id);
+    let delivered = json!({
+        "RecordType": "Delivery",
+        "MessageID": message_id,
+        "Recipient": email,
+        "DeliveredAt": "2026-10-19T16:09:19Z",
+    });
+    for event in [bounced, delivered] {
+        let response = server.post_postmark_webhook(CREDENTIALS, &event).await;
+        assert_eq!(response.status().as_u16(), 200);
+    }
+
+    let delivery = delivery(&server).await;
+    assert_eq!(delivery.status, "bounced");
+    assert_eq!(delivery.detail.as_deref(), Some("HardBounce"));
+    assert_eq!(subscriber_status(&server, &email).await, "bounced");
+
+    let response = server.post_admin_gdpr("export", &email).await;
+    let data: Value = response.json().await.unwrap();
+    let deliveries = data["deliveries"].as_array().unwrap();
+    assert_eq!(deliveries.len(), 1);
+    assert_eq!(deliveries[0]["status"], "bounced");
+    assert_eq!(deliveries[0]["message_id"], message_id.as_str());
+}
+
+#[sqlx::test]
+async fn suppressed_addresses_can_not_subscribe_again(pool: DbPool) {
+    for opt_in in [OptInMode::Double, OptInMode::Single] {
+        let server = TestServer::run_with_config(pool.clone(), |c| {
+            c.application.opt_in = opt_in;
+        })
+        .await;
+        let email = create_confirmed_subscriber_on(&server, &["default"]).await;
+        server
+            .post_postmark_webhook(CREDENTIALS, &bounce(&email, "HardBounce"))
+            .await;
+
+        let _mock_guard = Mock::given(path("/email"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount_as_scoped(&server.email_server)
+            .await;
+        let body = hashmap!("name" => "Ursula", "email" => email.as_str());
+        let response = server.post_subscriptions(&body).await;
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(subscriber_status(&server, &email).await, "bounced");
+    }
+}
+
+#[sqlx::test]
+async fn old_confirmation_links_do_not_undo_suppression(pool: DbPool) {
+    let server = TestServer::run(pool).await;
+    let links = create_unconfirmed_subscriber(&server).await;
+    reqwest::get(links.html.clone()).await.unwrap();
+    let email = sqlx::query!("select email from subscriptions")
+        .fetch_one(&server.db_pool)
+        .await
+        .unwrap()
+        .email;
+    let mut complaint = bounce(&email, "SpamComplaint");
+    complaint["RecordType"] = json!("SpamComplaint");
+    server.post_postmark_webhook(CREDENTIALS, &complaint).await;
+
+    let response = reqwest::get(links.html).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(subscriber_status(&server, &email).await, "complained");
+}